@@ -0,0 +1,206 @@
+use super::{AccountCache, CachedAccount};
+use crate::rpc::{RpcClient, WithContext};
+use crate::{subscribe, LaserstreamConfig, LaserstreamError, StreamHandle};
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeUpdate,
+    SubscribeUpdateAccountInfo,
+};
+use serde_json::Value;
+use tracing::{info, instrument};
+
+/// What the initial snapshot loads.
+#[derive(Debug, Clone)]
+pub enum SnapshotSource {
+    /// `getProgramAccounts` for `program_id` with optional RPC filters
+    /// (`{"memcmp": ...}`, `{"dataSize": ...}`).
+    Program {
+        program_id: String,
+        filters: Vec<Value>,
+    },
+    /// `getMultipleAccounts` for these base58 pubkeys.
+    Accounts(Vec<String>),
+}
+
+/// Options for [`bootstrap`].
+#[derive(Debug, Clone)]
+pub struct BootstrapOptions {
+    /// Solana JSON-RPC URL the snapshot is read from.
+    pub rpc_url: String,
+    pub source: SnapshotSource,
+}
+
+impl BootstrapOptions {
+    /// Snapshot of every account owned by `program_id`.
+    pub fn program(rpc_url: impl Into<String>, program_id: impl Into<String>) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            source: SnapshotSource::Program {
+                program_id: program_id.into(),
+                filters: Vec::new(),
+            },
+        }
+    }
+
+    /// Snapshot of a fixed set of accounts.
+    pub fn accounts(rpc_url: impl Into<String>, pubkeys: Vec<String>) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            source: SnapshotSource::Accounts(pubkeys),
+        }
+    }
+
+    /// Adds a `getProgramAccounts` filter. Ignored for [`SnapshotSource::Accounts`].
+    pub fn with_filter(mut self, filter: Value) -> Self {
+        if let SnapshotSource::Program { filters, .. } = &mut self.source {
+            filters.push(filter);
+        }
+        self
+    }
+}
+
+/// Output of [`bootstrap`].
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum BootstrapEvent {
+    /// The initial state, yielded once before any account update. `slot` is the slot
+    /// the RPC answered at; every account carries it with `write_version = u64::MAX`,
+    /// so it sorts after any streamed write of the same slot.
+    Snapshot {
+        slot: u64,
+        accounts: Vec<CachedAccount>,
+    },
+    /// A streamed update. Account writes already reflected in the snapshot are removed.
+    Update(SubscribeUpdate),
+}
+
+impl BootstrapEvent {
+    /// Applies the event to an [`AccountCache`].
+    pub fn apply_to(&self, cache: &mut AccountCache) {
+        match self {
+            BootstrapEvent::Snapshot { accounts, .. } => {
+                for account in accounts {
+                    cache.insert(account.slot, account.account.clone());
+                }
+            }
+            BootstrapEvent::Update(update) => cache.apply(update),
+        }
+    }
+}
+
+/// Loads the current state of a set of accounts over JSON-RPC and continues with the
+/// account stream, without a gap between the two.
+///
+/// The current slot is read first and the stream is opened with `from_slot` set to it,
+/// so the stream covers everything from before the snapshot was taken. The snapshot is
+/// then requested with `minContextSlot` at that slot while the stream is already being
+/// read; stream updates arriving meanwhile are held back. Account writes at or below the
+/// slot the snapshot was answered at are dropped, since the snapshot already contains
+/// them.
+///
+/// Replay is always enabled on this stream because `from_slot` is what closes the gap.
+/// The request's commitment (default processed) is used for both the RPC and the stream.
+#[instrument(skip(config, request, options))]
+pub async fn bootstrap(
+    config: LaserstreamConfig,
+    mut request: SubscribeRequest,
+    options: BootstrapOptions,
+) -> Result<
+    (
+        impl Stream<Item = Result<BootstrapEvent, LaserstreamError>>,
+        StreamHandle,
+    ),
+    LaserstreamError,
+> {
+    let commitment = request
+        .commitment
+        .and_then(|c| CommitmentLevel::try_from(c).ok())
+        .unwrap_or(CommitmentLevel::Processed);
+    let rpc = RpcClient::new(options.rpc_url);
+    let start_slot = rpc.get_slot(commitment).await?;
+    request.from_slot = Some(start_slot);
+    info!(start_slot, "Bootstrapping account state");
+
+    let (updates, handle) = subscribe(config.with_replay(true), request);
+    let events = stream! {
+        futures::pin_mut!(updates);
+        let snapshot = load_snapshot(&rpc, &options.source, commitment, start_slot);
+        futures::pin_mut!(snapshot);
+
+        let mut held = Vec::new();
+        let snapshot = loop {
+            tokio::select! {
+                snapshot = &mut snapshot => break snapshot,
+                item = updates.next() => match item {
+                    Some(item) => held.push(item),
+                    None => break Err(LaserstreamError::StreamEnded),
+                },
+            }
+        };
+        let (slot, accounts) = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                yield Err(e);
+                return;
+            }
+        };
+        info!(slot, accounts = accounts.len(), held = held.len(), "Snapshot loaded");
+        yield Ok(BootstrapEvent::Snapshot { slot, accounts });
+
+        for item in held {
+            match item {
+                Ok(update) if !covered(&update, slot) => yield Ok(BootstrapEvent::Update(update)),
+                Ok(_) => {}
+                Err(e) => yield Err(e),
+            }
+        }
+        while let Some(item) = updates.next().await {
+            match item {
+                Ok(update) if !covered(&update, slot) => yield Ok(BootstrapEvent::Update(update)),
+                Ok(_) => {}
+                Err(e) => yield Err(e),
+            }
+        }
+    };
+    Ok((events, handle))
+}
+
+/// Whether `update` is an account write the snapshot taken at `slot` already contains.
+fn covered(update: &SubscribeUpdate, slot: u64) -> bool {
+    matches!(&update.update_oneof, Some(UpdateOneof::Account(account)) if account.slot <= slot)
+}
+
+async fn load_snapshot(
+    rpc: &RpcClient,
+    source: &SnapshotSource,
+    commitment: CommitmentLevel,
+    min_context_slot: u64,
+) -> Result<(u64, Vec<CachedAccount>), LaserstreamError> {
+    let WithContext { slot, value } = match source {
+        SnapshotSource::Program { program_id, filters } => {
+            rpc.get_program_accounts(program_id, filters, commitment, Some(min_context_slot))
+                .await?
+        }
+        SnapshotSource::Accounts(pubkeys) => {
+            let result = rpc
+                .get_multiple_accounts(pubkeys, commitment, Some(min_context_slot))
+                .await?;
+            WithContext {
+                slot: result.slot,
+                value: result.value.into_iter().flatten().collect(),
+            }
+        }
+    };
+    let accounts: Vec<CachedAccount> = value
+        .into_iter()
+        .map(|account| CachedAccount {
+            slot,
+            account: SubscribeUpdateAccountInfo {
+                write_version: u64::MAX,
+                ..account
+            },
+        })
+        .collect();
+    Ok((slot, accounts))
+}
@@ -0,0 +1,381 @@
+use crate::slots::{SlotEvent, SlotTracker};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate, SubscribeUpdateAccountInfo,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Default number of versions kept per account while their slots are unsettled.
+const DEFAULT_MAX_VERSIONS: usize = 32;
+
+/// Unsettled slots further than this behind the newest one are treated as settled,
+/// which bounds memory when the stream carries no slot statuses.
+const MAX_UNSETTLED_SLOT_AGE: u64 = 4096;
+
+/// One version of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedAccount {
+    /// Slot the write happened in.
+    pub slot: u64,
+    pub account: SubscribeUpdateAccountInfo,
+}
+
+impl CachedAccount {
+    /// Ordering key: later slots win, then later writes within a slot.
+    pub fn version(&self) -> (u64, u64) {
+        (self.slot, self.account.write_version)
+    }
+
+    pub fn pubkey(&self) -> &[u8] {
+        &self.account.pubkey
+    }
+
+    pub fn owner(&self) -> &[u8] {
+        &self.account.owner
+    }
+
+    /// Zero-lamport accounts are closed; views report them as absent.
+    pub fn is_closed(&self) -> bool {
+        self.account.lamports == 0
+    }
+}
+
+type Versions = BTreeMap<(u64, u64), Arc<CachedAccount>>;
+
+/// Latest account state per pubkey, fed from a `subscribe` stream.
+///
+/// Every retained version of an account is kept until its slot is finalized, so the
+/// cache can answer at any commitment and drop writes from slots that die or end up
+/// off the finalized chain. For that the stream needs slot updates for every stage
+/// (add [`SlotTracker::slot_filter`] to the request); without them, views above the
+/// stream's own commitment stay empty and nothing is rolled back.
+///
+/// ```no_run
+/// use helius_laserstream::accounts::AccountCache;
+/// use helius_laserstream::grpc::CommitmentLevel;
+/// # fn run(updates: Vec<helius_laserstream::grpc::SubscribeUpdate>, key: &[u8]) {
+/// let mut cache = AccountCache::new();
+/// for update in &updates {
+///     cache.apply(update);
+/// }
+/// let latest = cache.get(key);
+/// let confirmed = cache.view(CommitmentLevel::Confirmed).get(key);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AccountCache {
+    accounts: HashMap<Vec<u8>, Versions>,
+    by_owner: HashMap<Vec<u8>, HashSet<Vec<u8>>>,
+    unsettled: BTreeMap<u64, HashSet<Vec<u8>>>,
+    watchers: HashMap<Vec<u8>, watch::Sender<Option<Arc<CachedAccount>>>>,
+    tracker: SlotTracker,
+    stream_commitment: CommitmentLevel,
+    max_versions: usize,
+}
+
+impl Default for AccountCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AccountCache {
+    /// Cache for a PROCESSED stream.
+    pub fn new() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            by_owner: HashMap::new(),
+            unsettled: BTreeMap::new(),
+            watchers: HashMap::new(),
+            tracker: SlotTracker::new(),
+            stream_commitment: CommitmentLevel::Processed,
+            max_versions: DEFAULT_MAX_VERSIONS,
+        }
+    }
+
+    /// Commitment of the stream feeding the cache; every write is at least at this level.
+    /// Default: `Processed`
+    pub fn with_stream_commitment(mut self, commitment: CommitmentLevel) -> Self {
+        self.stream_commitment = commitment;
+        self
+    }
+
+    /// Caps the versions kept per account while unsettled; the oldest go first.
+    /// Default: 32
+    pub fn with_max_versions(mut self, max: usize) -> Self {
+        self.max_versions = max.max(1);
+        self
+    }
+
+    /// Slot statuses seen so far.
+    pub fn tracker(&self) -> &SlotTracker {
+        &self.tracker
+    }
+
+    /// Feeds one update. Account writes are stored; slot updates settle or roll back
+    /// pending versions; everything else is ignored.
+    pub fn apply(&mut self, update: &SubscribeUpdate) {
+        match &update.update_oneof {
+            Some(UpdateOneof::Account(account)) => {
+                if let Some(info) = &account.account {
+                    self.insert(account.slot, info.clone());
+                }
+            }
+            Some(UpdateOneof::Slot(_)) | Some(UpdateOneof::BlockMeta(_)) => {
+                for event in self.tracker.apply(update) {
+                    match event {
+                        SlotEvent::Orphaned { slot, .. } => self.roll_back(slot),
+                        SlotEvent::Stage { .. } | SlotEvent::Reorg { .. } => {}
+                    }
+                }
+                if let Some(finalized) = self.tracker.finalized_slot() {
+                    self.settle_through(finalized);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Stores a write made in `slot`. Returns `false` if this exact version was already
+    /// known or its slot was rolled back.
+    pub fn insert(&mut self, slot: u64, account: SubscribeUpdateAccountInfo) -> bool {
+        if self.tracker.get(slot).is_some_and(|info| info.is_orphaned()) {
+            return false;
+        }
+        let entry = Arc::new(CachedAccount { slot, account });
+        let pubkey = entry.account.pubkey.clone();
+        let before = self.latest(&pubkey);
+
+        let versions = self.accounts.entry(pubkey.clone()).or_default();
+        if versions.contains_key(&entry.version()) {
+            return false;
+        }
+        versions.insert(entry.version(), entry.clone());
+        let mut removed = Vec::new();
+        while versions.len() > self.max_versions {
+            removed.extend(versions.pop_first().map(|(_, v)| v));
+        }
+        self.by_owner
+            .entry(entry.account.owner.clone())
+            .or_default()
+            .insert(pubkey.clone());
+
+        if self.level(slot) == CommitmentLevel::Finalized {
+            let settled = if self.stream_commitment == CommitmentLevel::Finalized {
+                u64::MAX
+            } else {
+                self.tracker.finalized_slot().unwrap_or(slot).max(slot)
+            };
+            removed.extend(self.compact(&pubkey, settled));
+        } else {
+            self.unsettled.entry(slot).or_default().insert(pubkey.clone());
+            self.expire_unsettled(slot);
+        }
+        self.unindex(&pubkey, removed);
+        self.notify(&pubkey, before);
+        true
+    }
+
+    /// Latest version of an account at any commitment, `None` if unknown or closed.
+    pub fn get(&self, pubkey: &[u8]) -> Option<Arc<CachedAccount>> {
+        self.view(CommitmentLevel::Processed).get(pubkey)
+    }
+
+    /// Latest versions of the accounts owned by `owner`.
+    pub fn by_owner<'a>(&'a self, owner: &[u8]) -> impl Iterator<Item = Arc<CachedAccount>> + 'a {
+        self.view(CommitmentLevel::Processed).by_owner(owner)
+    }
+
+    /// Latest versions of all open accounts.
+    pub fn iter(&self) -> impl Iterator<Item = Arc<CachedAccount>> + '_ {
+        self.view(CommitmentLevel::Processed).iter()
+    }
+
+    /// Number of accounts with at least one retained version (closed ones included).
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// The cache as seen at `commitment`: each account's newest version whose slot has
+    /// reached it.
+    pub fn view(&self, commitment: CommitmentLevel) -> AccountView<'_> {
+        AccountView {
+            cache: self,
+            commitment,
+        }
+    }
+
+    /// Watches the latest version of `pubkey`. The receiver sees every change to
+    /// [`get`](Self::get), including rollbacks.
+    pub fn watch(&mut self, pubkey: &[u8]) -> watch::Receiver<Option<Arc<CachedAccount>>> {
+        if let Some(sender) = self.watchers.get(pubkey) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = watch::channel(self.get(pubkey));
+        self.watchers.insert(pubkey.to_vec(), sender);
+        receiver
+    }
+
+    fn latest(&self, pubkey: &[u8]) -> Option<Arc<CachedAccount>> {
+        if !self.watchers.contains_key(pubkey) {
+            return None;
+        }
+        self.get(pubkey)
+    }
+
+    fn notify(&mut self, pubkey: &[u8], before: Option<Arc<CachedAccount>>) {
+        let Some(sender) = self.watchers.get(pubkey) else {
+            return;
+        };
+        if sender.receiver_count() == 0 {
+            self.watchers.remove(pubkey);
+            return;
+        }
+        let after = self.get(pubkey);
+        if after != before {
+            let _ = sender.send(after);
+        }
+    }
+
+    fn level(&self, slot: u64) -> CommitmentLevel {
+        let reached = match self.tracker.get(slot) {
+            Some(info) if info.finalized.is_some() => CommitmentLevel::Finalized,
+            Some(info) if info.confirmed.is_some() => CommitmentLevel::Confirmed,
+            Some(_) => CommitmentLevel::Processed,
+            // Older than anything tracked: it made it onto the finalized chain.
+            None if self.tracker.finalized_slot() >= Some(slot) => CommitmentLevel::Finalized,
+            None => CommitmentLevel::Processed,
+        };
+        if reached as i32 >= self.stream_commitment as i32 {
+            reached
+        } else {
+            self.stream_commitment
+        }
+    }
+
+    /// Drops every version written in `slot`.
+    fn roll_back(&mut self, slot: u64) {
+        let Some(pubkeys) = self.unsettled.remove(&slot) else {
+            return;
+        };
+        for pubkey in pubkeys {
+            let before = self.latest(&pubkey);
+            let mut removed = Vec::new();
+            if let Some(versions) = self.accounts.get_mut(&pubkey) {
+                versions.retain(|(version_slot, _), version| {
+                    let keep = *version_slot != slot;
+                    if !keep {
+                        removed.push(version.clone());
+                    }
+                    keep
+                });
+                if versions.is_empty() {
+                    self.accounts.remove(&pubkey);
+                }
+            }
+            self.unindex(&pubkey, removed);
+            self.notify(&pubkey, before);
+        }
+    }
+
+    fn settle_through(&mut self, finalized: u64) {
+        let later = self.unsettled.split_off(&(finalized + 1));
+        let settled = std::mem::replace(&mut self.unsettled, later);
+        let pubkeys: HashSet<Vec<u8>> = settled.into_values().flatten().collect();
+        for pubkey in pubkeys {
+            let removed = self.compact(&pubkey, finalized);
+            self.unindex(&pubkey, removed);
+        }
+    }
+
+    fn expire_unsettled(&mut self, newest: u64) {
+        let cutoff = newest.saturating_sub(MAX_UNSETTLED_SLOT_AGE);
+        while let Some((&slot, _)) = self.unsettled.first_key_value() {
+            if slot >= cutoff {
+                break;
+            }
+            if let Some(pubkeys) = self.unsettled.remove(&slot) {
+                for pubkey in pubkeys {
+                    let removed = self.compact(&pubkey, slot);
+                    self.unindex(&pubkey, removed);
+                }
+            }
+        }
+    }
+
+    /// Keeps the newest version at or below `settled` and everything after it;
+    /// returns the versions dropped.
+    fn compact(&mut self, pubkey: &[u8], settled: u64) -> Vec<Arc<CachedAccount>> {
+        let Some(versions) = self.accounts.get_mut(pubkey) else {
+            return Vec::new();
+        };
+        let Some(&floor) = versions.range(..=(settled, u64::MAX)).next_back().map(|(k, _)| k) else {
+            return Vec::new();
+        };
+        let kept = versions.split_off(&floor);
+        std::mem::replace(versions, kept).into_values().collect()
+    }
+
+    /// Removes `pubkey` from the owner index of dropped versions no retained version
+    /// shares.
+    fn unindex(&mut self, pubkey: &[u8], removed: Vec<Arc<CachedAccount>>) {
+        let retained = self.accounts.get(pubkey);
+        for version in removed {
+            let owner = &version.account.owner;
+            if retained.is_some_and(|versions| versions.values().any(|v| &v.account.owner == owner)) {
+                continue;
+            }
+            if let Some(keys) = self.by_owner.get_mut(owner) {
+                keys.remove(pubkey);
+                if keys.is_empty() {
+                    self.by_owner.remove(owner);
+                }
+            }
+        }
+    }
+}
+
+/// Read-only view of an [`AccountCache`] at one commitment level.
+#[derive(Clone, Copy)]
+pub struct AccountView<'a> {
+    cache: &'a AccountCache,
+    commitment: CommitmentLevel,
+}
+
+impl<'a> AccountView<'a> {
+    /// Newest version of `pubkey` at this commitment, `None` if unknown or closed.
+    pub fn get(&self, pubkey: &[u8]) -> Option<Arc<CachedAccount>> {
+        self.cache
+            .accounts
+            .get(pubkey)?
+            .values()
+            .rev()
+            .find(|v| self.cache.level(v.slot) as i32 >= self.commitment as i32)
+            .filter(|v| !v.is_closed())
+            .cloned()
+    }
+
+    /// Accounts whose version at this commitment is owned by `owner`.
+    pub fn by_owner(&self, owner: &[u8]) -> impl Iterator<Item = Arc<CachedAccount>> + 'a {
+        let view = *self;
+        let owner = owner.to_vec();
+        self.cache
+            .by_owner
+            .get(&owner)
+            .into_iter()
+            .flatten()
+            .filter_map(move |pubkey| view.get(pubkey))
+            .filter(move |account| account.account.owner == owner)
+    }
+
+    /// Every open account at this commitment.
+    pub fn iter(&self) -> impl Iterator<Item = Arc<CachedAccount>> + 'a {
+        let view = *self;
+        self.cache.accounts.keys().filter_map(move |pubkey| view.get(pubkey))
+    }
+}
@@ -0,0 +1,97 @@
+//! Account data decoders for the core programs.
+//!
+//! [`decode`] recognizes an account by its owner and parses SPL Token and Token-2022
+//! token accounts and mints (with Token-2022 extensions), stake accounts, vote
+//! accounts and durable nonce accounts. Data cut down by `accounts_data_slice`
+//! can't be parsed as a whole; [`decode_sliced`] reads whatever fields the slices
+//! cover and lists the rest as unavailable.
+
+mod nonce;
+mod sliced;
+mod stake;
+mod token;
+mod vote;
+
+pub use nonce::NonceAccount;
+pub use sliced::{decode_sliced, FieldValue, PartialAccount};
+pub use stake::{Delegation, StakeAccount, StakeMeta};
+pub use token::{Mint, TokenAccount, TokenAccountState, TokenExtension, TransferFee};
+pub use vote::{EpochCredits, Lockout, VoteAccount};
+
+use crate::grpc::SubscribeUpdateAccountInfo;
+use crate::instructions::{
+    STAKE_PROGRAM_ID, SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, VOTE_PROGRAM_ID,
+};
+
+/// Account layouts understood by this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountKind {
+    TokenAccount,
+    Mint,
+    Token2022Account,
+    Token2022Mint,
+    Stake,
+    Vote,
+    Nonce,
+}
+
+impl AccountKind {
+    /// Picks the layout from the owner program and the full, unsliced data.
+    /// Returns `None` for unknown owners and for token multisig accounts.
+    pub fn identify(owner: &[u8], data: &[u8]) -> Option<Self> {
+        let owner = bs58::encode(owner).into_string();
+        match owner.as_str() {
+            TOKEN_PROGRAM_ID => match data.len() {
+                token::TOKEN_ACCOUNT_LEN => Some(Self::TokenAccount),
+                token::MINT_LEN => Some(Self::Mint),
+                _ => None,
+            },
+            TOKEN_2022_PROGRAM_ID => {
+                if data.len() == token::MULTISIG_LEN {
+                    None
+                } else if token::is_token_2022_mint(data) {
+                    Some(Self::Token2022Mint)
+                } else {
+                    Some(Self::Token2022Account)
+                }
+            }
+            STAKE_PROGRAM_ID => Some(Self::Stake),
+            VOTE_PROGRAM_ID => Some(Self::Vote),
+            SYSTEM_PROGRAM_ID if data.len() == nonce::NONCE_ACCOUNT_LEN => Some(Self::Nonce),
+            _ => None,
+        }
+    }
+}
+
+/// A fully decoded account.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedAccount {
+    TokenAccount(TokenAccount),
+    Mint(Mint),
+    Stake(StakeAccount),
+    Vote(VoteAccount),
+    Nonce(NonceAccount),
+}
+
+/// Decodes full account data owned by `owner`. Returns `None` for unknown owners
+/// and for data that doesn't match the owner's layout, including sliced data.
+pub fn decode_account(owner: &[u8], data: &[u8]) -> Option<DecodedAccount> {
+    Some(match AccountKind::identify(owner, data)? {
+        AccountKind::TokenAccount => {
+            DecodedAccount::TokenAccount(token::token_account(data, false)?)
+        }
+        AccountKind::Token2022Account => {
+            DecodedAccount::TokenAccount(token::token_account(data, true)?)
+        }
+        AccountKind::Mint => DecodedAccount::Mint(token::mint(data, false)?),
+        AccountKind::Token2022Mint => DecodedAccount::Mint(token::mint(data, true)?),
+        AccountKind::Stake => DecodedAccount::Stake(stake::stake_account(data)?),
+        AccountKind::Vote => DecodedAccount::Vote(vote::vote_account(data)?),
+        AccountKind::Nonce => DecodedAccount::Nonce(nonce::nonce_account(data)?),
+    })
+}
+
+/// [`decode_account`] for a streamed account update.
+pub fn decode(info: &SubscribeUpdateAccountInfo) -> Option<DecodedAccount> {
+    decode_account(&info.owner, &info.data)
+}
@@ -0,0 +1,32 @@
+use crate::layout::Reader;
+
+/// Durable nonce account owned by the System program. Uninitialized nonce
+/// accounts do not decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceAccount {
+    /// `0` for legacy nonces, `1` for current ones.
+    pub version: u32,
+    pub authority: String,
+    /// The stored blockhash, base58.
+    pub durable_nonce: String,
+    pub lamports_per_signature: u64,
+}
+
+pub(super) const NONCE_ACCOUNT_LEN: usize = 80;
+
+pub(super) fn nonce_account(data: &[u8]) -> Option<NonceAccount> {
+    if data.len() != NONCE_ACCOUNT_LEN {
+        return None;
+    }
+    let mut r = Reader::new(data);
+    let version = r.u32()?;
+    if version > 1 || r.u32()? != 1 {
+        return None;
+    }
+    Some(NonceAccount {
+        version,
+        authority: r.pubkey()?,
+        durable_nonce: r.pubkey()?,
+        lamports_per_signature: r.u64()?,
+    })
+}
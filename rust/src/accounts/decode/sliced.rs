@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use super::AccountKind;
+use crate::grpc::SubscribeRequestAccountsDataSlice;
+use crate::layout::Reader;
+
+/// Value of one field read from sliced data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Pubkey(String),
+    OptionalPubkey(Option<String>),
+    U64(u64),
+    OptionalU64(Option<u64>),
+    I64(i64),
+    U32(u32),
+    U8(u8),
+    Bool(bool),
+    F64(f64),
+}
+
+/// Account decoded from data cut down by `accounts_data_slice`.
+///
+/// Every field of `kind` ends up either in `fields` or in `unavailable`. Fields
+/// behind variable-length data (vote towers, Token-2022 extensions) are always
+/// unavailable, since their offsets can't be known without the bytes before them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialAccount {
+    pub kind: AccountKind,
+    pub fields: BTreeMap<&'static str, FieldValue>,
+    pub unavailable: Vec<&'static str>,
+}
+
+impl PartialAccount {
+    pub fn get(&self, field: &str) -> Option<&FieldValue> {
+        self.fields.get(field)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unavailable.is_empty()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FieldType {
+    Pubkey,
+    /// Account-style `COption<Pubkey>` (u32 tag + 32 bytes).
+    OptionalPubkey,
+    U64,
+    /// Account-style `COption<u64>` (u32 tag + 8 bytes).
+    OptionalU64,
+    I64,
+    U32,
+    U8,
+    Bool,
+    F64,
+}
+
+impl FieldType {
+    fn len(self) -> usize {
+        match self {
+            Self::Pubkey => 32,
+            Self::OptionalPubkey => 36,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+            Self::OptionalU64 => 12,
+            Self::U32 => 4,
+            Self::U8 | Self::Bool => 1,
+        }
+    }
+
+    fn read(self, data: &[u8]) -> Option<FieldValue> {
+        let mut r = Reader::new(data);
+        Some(match self {
+            Self::Pubkey => FieldValue::Pubkey(r.pubkey()?),
+            Self::OptionalPubkey => FieldValue::OptionalPubkey(r.pubkey_option_u32()?),
+            Self::U64 => FieldValue::U64(r.u64()?),
+            Self::OptionalU64 => FieldValue::OptionalU64(r.u64_option_u32()?),
+            Self::I64 => FieldValue::I64(r.i64()?),
+            Self::U32 => FieldValue::U32(r.u32()?),
+            Self::U8 => FieldValue::U8(r.u8()?),
+            Self::Bool => FieldValue::Bool(r.bool()?),
+            Self::F64 => FieldValue::F64(f64::from_bits(r.u64()?)),
+        })
+    }
+}
+
+type Field = (&'static str, usize, FieldType);
+
+const TOKEN_ACCOUNT: &[Field] = &[
+    ("mint", 0, FieldType::Pubkey),
+    ("owner", 32, FieldType::Pubkey),
+    ("amount", 64, FieldType::U64),
+    ("delegate", 72, FieldType::OptionalPubkey),
+    ("state", 108, FieldType::U8),
+    ("is_native", 109, FieldType::OptionalU64),
+    ("delegated_amount", 121, FieldType::U64),
+    ("close_authority", 129, FieldType::OptionalPubkey),
+];
+
+const MINT: &[Field] = &[
+    ("mint_authority", 0, FieldType::OptionalPubkey),
+    ("supply", 36, FieldType::U64),
+    ("decimals", 44, FieldType::U8),
+    ("is_initialized", 45, FieldType::Bool),
+    ("freeze_authority", 46, FieldType::OptionalPubkey),
+];
+
+/// Delegation fields only hold meaningful values when `state` is 2 (delegated).
+const STAKE: &[Field] = &[
+    ("state", 0, FieldType::U32),
+    ("rent_exempt_reserve", 4, FieldType::U64),
+    ("staker", 12, FieldType::Pubkey),
+    ("withdrawer", 44, FieldType::Pubkey),
+    ("lockup_unix_timestamp", 76, FieldType::I64),
+    ("lockup_epoch", 84, FieldType::U64),
+    ("custodian", 92, FieldType::Pubkey),
+    ("voter", 124, FieldType::Pubkey),
+    ("stake", 156, FieldType::U64),
+    ("activation_epoch", 164, FieldType::U64),
+    ("deactivation_epoch", 172, FieldType::U64),
+    ("warmup_cooldown_rate", 180, FieldType::F64),
+    ("credits_observed", 188, FieldType::U64),
+    ("flags", 196, FieldType::U8),
+];
+
+const VOTE: &[Field] = &[
+    ("version", 0, FieldType::U32),
+    ("node_pubkey", 4, FieldType::Pubkey),
+    ("authorized_withdrawer", 36, FieldType::Pubkey),
+    ("commission", 68, FieldType::U8),
+];
+
+const NONCE: &[Field] = &[
+    ("version", 0, FieldType::U32),
+    ("state", 4, FieldType::U32),
+    ("authority", 8, FieldType::Pubkey),
+    ("durable_nonce", 40, FieldType::Pubkey),
+    ("lamports_per_signature", 72, FieldType::U64),
+];
+
+fn layout(kind: AccountKind) -> (&'static [Field], &'static [&'static str]) {
+    match kind {
+        AccountKind::TokenAccount => (TOKEN_ACCOUNT, &[]),
+        AccountKind::Token2022Account => (TOKEN_ACCOUNT, &["extensions"]),
+        AccountKind::Mint => (MINT, &[]),
+        AccountKind::Token2022Mint => (MINT, &["extensions"]),
+        AccountKind::Stake => (STAKE, &[]),
+        AccountKind::Vote => (
+            VOTE,
+            &[
+                "votes",
+                "root_slot",
+                "authorized_voters",
+                "epoch_credits",
+                "last_timestamp",
+            ],
+        ),
+        AccountKind::Nonce => (NONCE, &[]),
+    }
+}
+
+/// Sliced bytes placed back at their account offsets, with adjacent slices merged.
+fn place(slices: &[SubscribeRequestAccountsDataSlice], mut data: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    let mut regions: Vec<(usize, Vec<u8>)> = Vec::new();
+    for slice in slices {
+        // The server concatenates slices in request order, cutting any that run
+        // past the end of the account.
+        let len = (slice.length as usize).min(data.len());
+        let (bytes, rest) = data.split_at(len);
+        data = rest;
+        let offset = slice.offset as usize;
+        match regions.last_mut() {
+            Some((start, region)) if *start + region.len() == offset => {
+                region.extend_from_slice(bytes)
+            }
+            _ => regions.push((offset, bytes.to_vec())),
+        }
+    }
+    regions
+}
+
+/// Decodes the fields of `kind` that are fully covered by the returned slices.
+///
+/// `slices` must be the `accounts_data_slice` list of the subscription and `data`
+/// the `SubscribeUpdateAccountInfo.data` it produced.
+pub fn decode_sliced(
+    kind: AccountKind,
+    slices: &[SubscribeRequestAccountsDataSlice],
+    data: &[u8],
+) -> PartialAccount {
+    let regions = place(slices, data);
+    let (fields, variable) = layout(kind);
+    let mut partial = PartialAccount {
+        kind,
+        fields: BTreeMap::new(),
+        unavailable: Vec::new(),
+    };
+    for &(name, offset, ty) in fields {
+        let value = regions.iter().find_map(|(start, region)| {
+            let from = offset.checked_sub(*start)?;
+            ty.read(region.get(from..from + ty.len())?)
+        });
+        match value {
+            Some(value) => {
+                partial.fields.insert(name, value);
+            }
+            None => partial.unavailable.push(name),
+        }
+    }
+    partial.unavailable.extend_from_slice(variable);
+    partial
+}
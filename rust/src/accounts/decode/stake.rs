@@ -0,0 +1,70 @@
+use crate::layout::Reader;
+
+/// Authorities and lockup shared by initialized and delegated stake accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeMeta {
+    pub rent_exempt_reserve: u64,
+    pub staker: String,
+    pub withdrawer: String,
+    pub lockup_unix_timestamp: i64,
+    pub lockup_epoch: u64,
+    pub custodian: String,
+}
+
+/// Active or deactivating delegation of a stake account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delegation {
+    pub voter: String,
+    pub stake: u64,
+    pub activation_epoch: u64,
+    /// `u64::MAX` while the stake has not been deactivated.
+    pub deactivation_epoch: u64,
+    pub warmup_cooldown_rate: f64,
+    pub credits_observed: u64,
+    pub flags: u8,
+}
+
+/// Stake program account (`StakeStateV2`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum StakeAccount {
+    Uninitialized,
+    Initialized(StakeMeta),
+    Delegated {
+        meta: StakeMeta,
+        delegation: Delegation,
+    },
+    RewardsPool,
+}
+
+fn meta(r: &mut Reader<'_>) -> Option<StakeMeta> {
+    Some(StakeMeta {
+        rent_exempt_reserve: r.u64()?,
+        staker: r.pubkey()?,
+        withdrawer: r.pubkey()?,
+        lockup_unix_timestamp: r.i64()?,
+        lockup_epoch: r.u64()?,
+        custodian: r.pubkey()?,
+    })
+}
+
+pub(super) fn stake_account(data: &[u8]) -> Option<StakeAccount> {
+    let mut r = Reader::new(data);
+    Some(match r.u32()? {
+        0 => StakeAccount::Uninitialized,
+        1 => StakeAccount::Initialized(meta(&mut r)?),
+        2 => StakeAccount::Delegated {
+            meta: meta(&mut r)?,
+            delegation: Delegation {
+                voter: r.pubkey()?,
+                stake: r.u64()?,
+                activation_epoch: r.u64()?,
+                deactivation_epoch: r.u64()?,
+                warmup_cooldown_rate: f64::from_bits(r.u64()?),
+                credits_observed: r.u64()?,
+                flags: r.u8()?,
+            },
+        },
+        3 => StakeAccount::RewardsPool,
+        _ => return None,
+    })
+}
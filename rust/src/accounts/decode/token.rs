@@ -0,0 +1,300 @@
+use crate::layout::Reader;
+
+/// Size of an SPL token account; Token-2022 accounts and mints keep their account
+/// type byte right after it.
+pub(super) const TOKEN_ACCOUNT_LEN: usize = 165;
+pub(super) const MINT_LEN: usize = 82;
+pub(super) const MULTISIG_LEN: usize = 355;
+
+/// Token-2022 account type byte.
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// State of a token account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAccountState {
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+impl TokenAccountState {
+    pub(super) fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Uninitialized),
+            1 => Some(Self::Initialized),
+            2 => Some(Self::Frozen),
+            _ => None,
+        }
+    }
+}
+
+/// SPL Token or Token-2022 token account. Addresses are base58.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+    pub delegate: Option<String>,
+    pub state: TokenAccountState,
+    /// Rent-exempt reserve for wrapped SOL accounts; `None` for other mints.
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<String>,
+    /// Token-2022 extensions; always empty for SPL Token.
+    pub extensions: Vec<TokenExtension>,
+}
+
+/// SPL Token or Token-2022 mint. Addresses are base58.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<String>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<String>,
+    /// Token-2022 extensions; always empty for SPL Token.
+    pub extensions: Vec<TokenExtension>,
+}
+
+/// Transfer fee schedule of a [`TokenExtension::TransferFeeConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+/// Token-2022 extension. Authorities that are unset are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenExtension {
+    TransferFeeConfig {
+        transfer_fee_config_authority: Option<String>,
+        withdraw_withheld_authority: Option<String>,
+        withheld_amount: u64,
+        older_transfer_fee: TransferFee,
+        newer_transfer_fee: TransferFee,
+    },
+    TransferFeeAmount {
+        withheld_amount: u64,
+    },
+    MintCloseAuthority {
+        close_authority: Option<String>,
+    },
+    DefaultAccountState {
+        state: TokenAccountState,
+    },
+    ImmutableOwner,
+    MemoTransfer {
+        require_incoming_transfer_memos: bool,
+    },
+    NonTransferable,
+    InterestBearingConfig {
+        rate_authority: Option<String>,
+        initialization_timestamp: i64,
+        pre_update_average_rate: i16,
+        last_update_timestamp: i64,
+        current_rate: i16,
+    },
+    CpiGuard {
+        lock_cpi: bool,
+    },
+    PermanentDelegate {
+        delegate: Option<String>,
+    },
+    NonTransferableAccount,
+    TransferHook {
+        authority: Option<String>,
+        program_id: Option<String>,
+    },
+    TransferHookAccount {
+        transferring: bool,
+    },
+    MetadataPointer {
+        authority: Option<String>,
+        metadata_address: Option<String>,
+    },
+    TokenMetadata {
+        update_authority: Option<String>,
+        mint: String,
+        name: String,
+        symbol: String,
+        uri: String,
+        additional_metadata: Vec<(String, String)>,
+    },
+    GroupPointer {
+        authority: Option<String>,
+        group_address: Option<String>,
+    },
+    GroupMemberPointer {
+        authority: Option<String>,
+        member_address: Option<String>,
+    },
+    /// An extension without a typed decoder here (confidential transfers, ...).
+    Other {
+        extension_type: u16,
+        data: Vec<u8>,
+    },
+}
+
+pub(super) fn token_account(data: &[u8], token_2022: bool) -> Option<TokenAccount> {
+    let mut r = Reader::new(data);
+    let mut account = TokenAccount {
+        mint: r.pubkey()?,
+        owner: r.pubkey()?,
+        amount: r.u64()?,
+        delegate: r.pubkey_option_u32()?,
+        state: TokenAccountState::from_u8(r.u8()?)?,
+        is_native: r.u64_option_u32()?,
+        delegated_amount: r.u64()?,
+        close_authority: r.pubkey_option_u32()?,
+        extensions: Vec::new(),
+    };
+    if token_2022 && data.len() > TOKEN_ACCOUNT_LEN {
+        if data[TOKEN_ACCOUNT_LEN] != ACCOUNT_TYPE_ACCOUNT {
+            return None;
+        }
+        account.extensions = extensions(&data[TOKEN_ACCOUNT_LEN + 1..])?;
+    }
+    Some(account)
+}
+
+pub(super) fn mint(data: &[u8], token_2022: bool) -> Option<Mint> {
+    let mut r = Reader::new(data);
+    let mut mint = Mint {
+        mint_authority: r.pubkey_option_u32()?,
+        supply: r.u64()?,
+        decimals: r.u8()?,
+        is_initialized: r.bool()?,
+        freeze_authority: r.pubkey_option_u32()?,
+        extensions: Vec::new(),
+    };
+    // Token-2022 mints with extensions are padded to the token account size so the
+    // account type byte sits at the same offset.
+    if token_2022 && data.len() > TOKEN_ACCOUNT_LEN {
+        if data[TOKEN_ACCOUNT_LEN] != ACCOUNT_TYPE_MINT {
+            return None;
+        }
+        mint.extensions = extensions(&data[TOKEN_ACCOUNT_LEN + 1..])?;
+    }
+    Some(mint)
+}
+
+/// Whether full Token-2022 data holds a mint (as opposed to a token account).
+pub(super) fn is_token_2022_mint(data: &[u8]) -> bool {
+    match data.get(TOKEN_ACCOUNT_LEN) {
+        Some(account_type) => *account_type == ACCOUNT_TYPE_MINT,
+        None => data.len() == MINT_LEN,
+    }
+}
+
+/// Parses the type-length-value extension area.
+fn extensions(mut data: &[u8]) -> Option<Vec<TokenExtension>> {
+    let mut out = Vec::new();
+    while data.len() >= 4 {
+        let mut header = Reader::new(data);
+        let extension_type = header.u16()?;
+        let len = header.u16()? as usize;
+        // Zeroed space after the last extension.
+        if extension_type == 0 {
+            break;
+        }
+        let value = data.get(4..4 + len)?;
+        out.push(
+            extension(extension_type, value).unwrap_or_else(|| TokenExtension::Other {
+                extension_type,
+                data: value.to_vec(),
+            }),
+        );
+        data = &data[4 + len..];
+    }
+    Some(out)
+}
+
+fn transfer_fee(r: &mut Reader<'_>) -> Option<TransferFee> {
+    Some(TransferFee {
+        epoch: r.u64()?,
+        maximum_fee: r.u64()?,
+        transfer_fee_basis_points: r.u16()?,
+    })
+}
+
+fn extension(extension_type: u16, value: &[u8]) -> Option<TokenExtension> {
+    let mut r = Reader::new(value);
+    Some(match extension_type {
+        1 => TokenExtension::TransferFeeConfig {
+            transfer_fee_config_authority: r.nonzero_pubkey()?,
+            withdraw_withheld_authority: r.nonzero_pubkey()?,
+            withheld_amount: r.u64()?,
+            older_transfer_fee: transfer_fee(&mut r)?,
+            newer_transfer_fee: transfer_fee(&mut r)?,
+        },
+        2 => TokenExtension::TransferFeeAmount {
+            withheld_amount: r.u64()?,
+        },
+        3 => TokenExtension::MintCloseAuthority {
+            close_authority: r.nonzero_pubkey()?,
+        },
+        6 => TokenExtension::DefaultAccountState {
+            state: TokenAccountState::from_u8(r.u8()?)?,
+        },
+        7 => TokenExtension::ImmutableOwner,
+        8 => TokenExtension::MemoTransfer {
+            require_incoming_transfer_memos: r.bool()?,
+        },
+        9 => TokenExtension::NonTransferable,
+        10 => TokenExtension::InterestBearingConfig {
+            rate_authority: r.nonzero_pubkey()?,
+            initialization_timestamp: r.i64()?,
+            pre_update_average_rate: r.i16()?,
+            last_update_timestamp: r.i64()?,
+            current_rate: r.i16()?,
+        },
+        11 => TokenExtension::CpiGuard {
+            lock_cpi: r.bool()?,
+        },
+        12 => TokenExtension::PermanentDelegate {
+            delegate: r.nonzero_pubkey()?,
+        },
+        13 => TokenExtension::NonTransferableAccount,
+        14 => TokenExtension::TransferHook {
+            authority: r.nonzero_pubkey()?,
+            program_id: r.nonzero_pubkey()?,
+        },
+        15 => TokenExtension::TransferHookAccount {
+            transferring: r.bool()?,
+        },
+        18 => TokenExtension::MetadataPointer {
+            authority: r.nonzero_pubkey()?,
+            metadata_address: r.nonzero_pubkey()?,
+        },
+        19 => {
+            let update_authority = r.nonzero_pubkey()?;
+            let mint = r.pubkey()?;
+            let name = r.borsh_string()?;
+            let symbol = r.borsh_string()?;
+            let uri = r.borsh_string()?;
+            let pairs = r.u32()?;
+            let additional_metadata = (0..pairs)
+                .map(|_| Some((r.borsh_string()?, r.borsh_string()?)))
+                .collect::<Option<_>>()?;
+            TokenExtension::TokenMetadata {
+                update_authority,
+                mint,
+                name,
+                symbol,
+                uri,
+                additional_metadata,
+            }
+        }
+        20 => TokenExtension::GroupPointer {
+            authority: r.nonzero_pubkey()?,
+            group_address: r.nonzero_pubkey()?,
+        },
+        22 => TokenExtension::GroupMemberPointer {
+            authority: r.nonzero_pubkey()?,
+            member_address: r.nonzero_pubkey()?,
+        },
+        _ => return None,
+    })
+}
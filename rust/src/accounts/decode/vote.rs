@@ -0,0 +1,90 @@
+use crate::layout::Reader;
+
+/// `prior_voters`: 32 entries of `(Pubkey, Epoch, Epoch)`, an index and an empty flag.
+const PRIOR_VOTERS_LEN: usize = 32 * 48 + 8 + 1;
+
+/// A vote on the tower.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lockout {
+    pub slot: u64,
+    pub confirmation_count: u32,
+    /// Slots between the voted slot and the slot the vote landed in; `None` for
+    /// the 1.14.11 layout, which doesn't record it.
+    pub latency: Option<u8>,
+}
+
+/// Credits earned in one epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochCredits {
+    pub epoch: u64,
+    pub credits: u64,
+    pub prev_credits: u64,
+}
+
+/// Vote program account. Only the 1.14.11 and current layouts decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteAccount {
+    pub node_pubkey: String,
+    pub authorized_withdrawer: String,
+    pub commission: u8,
+    pub votes: Vec<Lockout>,
+    pub root_slot: Option<u64>,
+    /// `(epoch, voter)` pairs in epoch order.
+    pub authorized_voters: Vec<(u64, String)>,
+    pub epoch_credits: Vec<EpochCredits>,
+    pub last_timestamp_slot: u64,
+    pub last_timestamp: i64,
+}
+
+pub(super) fn vote_account(data: &[u8]) -> Option<VoteAccount> {
+    let mut r = Reader::new(data);
+    let landed_votes = match r.u32()? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let node_pubkey = r.pubkey()?;
+    let authorized_withdrawer = r.pubkey()?;
+    let commission = r.u8()?;
+
+    let votes = (0..r.u64()?)
+        .map(|_| {
+            let latency = if landed_votes { Some(r.u8()?) } else { None };
+            Some(Lockout {
+                slot: r.u64()?,
+                confirmation_count: r.u32()?,
+                latency,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let root_slot = match r.u8()? {
+        0 => None,
+        1 => Some(r.u64()?),
+        _ => return None,
+    };
+    let authorized_voters = (0..r.u64()?)
+        .map(|_| Some((r.u64()?, r.pubkey()?)))
+        .collect::<Option<_>>()?;
+    r.bytes(PRIOR_VOTERS_LEN)?;
+    let epoch_credits = (0..r.u64()?)
+        .map(|_| {
+            Some(EpochCredits {
+                epoch: r.u64()?,
+                credits: r.u64()?,
+                prev_credits: r.u64()?,
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    Some(VoteAccount {
+        node_pubkey,
+        authorized_withdrawer,
+        commission,
+        votes,
+        root_slot,
+        authorized_voters,
+        epoch_credits,
+        last_timestamp_slot: r.u64()?,
+        last_timestamp: r.i64()?,
+    })
+}
@@ -0,0 +1,19 @@
+//! Account state kept from the account stream.
+//!
+//! [`AccountCache`] holds the latest state of every account seen on a `subscribe`
+//! stream, ordered by `(slot, write_version)` so replays never move an account
+//! backwards, with per-commitment views when slot statuses are part of the stream.
+//! [`bootstrap`] fills it with a JSON-RPC snapshot before the stream takes over.
+//! [`decode`] parses account data of the core programs into typed structs.
+//! [`AccountWatchlist`] keeps the `watch` channels behind
+//! [`LaserstreamClient::watch_account`](crate::LaserstreamClient::watch_account).
+
+mod bootstrap;
+mod cache;
+pub mod decode;
+mod watch;
+
+pub use bootstrap::{bootstrap, BootstrapEvent, BootstrapOptions, SnapshotSource};
+pub use cache::{AccountCache, AccountView, CachedAccount};
+pub use watch::{AccountReceiver, AccountWatchlist};
+pub(crate) use watch::AccountSubscription;
@@ -0,0 +1,264 @@
+//! Account watches over a shared `accounts` subscription.
+//!
+//! [`AccountWatchlist`] keeps `accounts` filters listing every watched pubkey, split
+//! to stay under the endpoint's per-filter limit, and publishes each account's latest
+//! update to its `watch` channel.
+//! [`LaserstreamClient::watch_account`](crate::LaserstreamClient::watch_account)
+//! runs it on a single subscription shared by all of a client's watches, rewriting
+//! the list through [`StreamHandle`](crate::StreamHandle) as accounts gain their
+//! first receiver or lose their last. Changes are coalesced into one debounced write.
+
+use crate::client::subscribe;
+use crate::LaserstreamConfig;
+use futures::StreamExt;
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeUpdate, SubscribeUpdateAccountInfo,
+};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::warn;
+
+/// Name of the accounts filters the watchlist maintains; filters after the first get
+/// a `-<n>` suffix.
+const FILTER_NAME: &str = "watch-accounts";
+/// Default for [`AccountWatchlist::with_max_accounts_per_filter`].
+const MAX_ACCOUNTS_PER_FILTER: usize = 10_000;
+/// Window in which watch changes are coalesced into one write.
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Latest version of a watched account: `None` until the first update arrives.
+pub type AccountReceiver = watch::Receiver<Option<SubscribeUpdateAccountInfo>>;
+
+#[derive(Debug)]
+struct Watched {
+    sender: Arc<watch::Sender<Option<SubscribeUpdateAccountInfo>>>,
+    /// `(slot, write_version)` of the value the receivers hold.
+    version: Option<(u64, u64)>,
+}
+
+/// Watched accounts, each with a `watch` channel holding its latest update.
+///
+/// An account stays in [`request`](Self::request) while it has receivers; once the
+/// last one is dropped, [`release`](Self::release) removes it.
+#[derive(Debug)]
+pub struct AccountWatchlist {
+    accounts: BTreeMap<Vec<u8>, Watched>,
+    commitment: CommitmentLevel,
+    max_accounts_per_filter: usize,
+}
+
+impl Default for AccountWatchlist {
+    fn default() -> Self {
+        Self::new(CommitmentLevel::Confirmed)
+    }
+}
+
+impl AccountWatchlist {
+    pub fn new(commitment: CommitmentLevel) -> Self {
+        Self {
+            accounts: BTreeMap::new(),
+            commitment,
+            max_accounts_per_filter: MAX_ACCOUNTS_PER_FILTER,
+        }
+    }
+
+    /// Largest pubkey list one filter of [`request`](Self::request) carries; match
+    /// it to the endpoint's per-filter limit. Default: 10_000
+    pub fn with_max_accounts_per_filter(mut self, max: usize) -> Self {
+        self.max_accounts_per_filter = max.max(1);
+        self
+    }
+
+    /// Number of watched accounts.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Returns a receiver for `pubkey` and whether the account is new, i.e. the
+    /// request changed.
+    pub fn watch(&mut self, pubkey: &[u8]) -> (AccountReceiver, bool) {
+        if let Some(watched) = self.accounts.get(pubkey) {
+            return (watched.sender.subscribe(), false);
+        }
+        let (sender, receiver) = watch::channel(None);
+        self.accounts.insert(
+            pubkey.to_vec(),
+            Watched {
+                sender: Arc::new(sender),
+                version: None,
+            },
+        );
+        (receiver, true)
+    }
+
+    /// Stops watching `pubkey` if it has no receivers left. Returns whether it was
+    /// removed, i.e. the request changed.
+    pub fn release(&mut self, pubkey: &[u8]) -> bool {
+        let unused = self
+            .accounts
+            .get(pubkey)
+            .is_some_and(|watched| watched.sender.receiver_count() == 0);
+        if unused {
+            self.accounts.remove(pubkey);
+        }
+        unused
+    }
+
+    /// Request with `accounts` filters listing every watched account, as few as the
+    /// per-filter limit allows.
+    pub fn request(&self) -> SubscribeRequest {
+        let mut request = SubscribeRequest {
+            commitment: Some(self.commitment as i32),
+            ..Default::default()
+        };
+        let pubkeys: Vec<String> = self
+            .accounts
+            .keys()
+            .map(|pubkey| bs58::encode(pubkey).into_string())
+            .collect();
+        for (index, chunk) in pubkeys.chunks(self.max_accounts_per_filter).enumerate() {
+            let name = match index {
+                0 => FILTER_NAME.to_string(),
+                n => format!("{FILTER_NAME}-{n}"),
+            };
+            request.accounts.insert(
+                name,
+                SubscribeRequestFilterAccounts {
+                    account: chunk.to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
+        request
+    }
+
+    /// Publishes account updates of watched accounts. Versions older than the one
+    /// the receivers hold (as seen on replay after a reconnect) are ignored.
+    pub fn apply(&mut self, update: &SubscribeUpdate) {
+        let Some(UpdateOneof::Account(account)) = &update.update_oneof else {
+            return;
+        };
+        let Some(info) = &account.account else {
+            return;
+        };
+        let Some(watched) = self.accounts.get_mut(&info.pubkey) else {
+            return;
+        };
+        let version = (account.slot, info.write_version);
+        if watched.version.is_some_and(|current| current >= version) {
+            return;
+        }
+        watched.version = Some(version);
+        watched.sender.send_replace(Some(info.clone()));
+    }
+
+    fn sender(
+        &self,
+        pubkey: &[u8],
+    ) -> Option<Arc<watch::Sender<Option<SubscribeUpdateAccountInfo>>>> {
+        self.accounts
+            .get(pubkey)
+            .map(|watched| watched.sender.clone())
+    }
+}
+
+/// The shared subscription behind `watch_account`.
+pub(crate) struct AccountSubscription {
+    watchlist: Arc<Mutex<AccountWatchlist>>,
+    /// Whether a write is already scheduled.
+    pending: Arc<AtomicBool>,
+    /// Wakes the writer; dropped with the subscription, which ends it.
+    changed: mpsc::UnboundedSender<()>,
+    /// Dropped with the subscription, which ends the task driving the stream.
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl AccountSubscription {
+    /// Starts the subscription on the current Tokio runtime.
+    pub(crate) fn start(config: LaserstreamConfig, commitment: CommitmentLevel) -> Self {
+        let watchlist = Arc::new(Mutex::new(AccountWatchlist::new(commitment)));
+        let request = watchlist.lock().unwrap().request();
+        let (stream, handle) = subscribe(config, request);
+        let (shutdown, mut stopped) = oneshot::channel::<()>();
+        let driven = watchlist.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            loop {
+                let item = tokio::select! {
+                    item = stream.next() => item,
+                    _ = &mut stopped => break,
+                };
+                match item {
+                    Some(Ok(update)) => driven.lock().unwrap().apply(&update),
+                    Some(Err(e)) => warn!(error = %e, "Account watch subscription error"),
+                    None => break,
+                }
+            }
+        });
+
+        let (changed, mut changes) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicBool::new(false));
+        let written = watchlist.clone();
+        let scheduled = pending.clone();
+        tokio::spawn(async move {
+            while changes.recv().await.is_some() {
+                // Watches and releases during the window ride along with this write.
+                tokio::time::sleep(WRITE_DEBOUNCE).await;
+                scheduled.store(false, Ordering::Release);
+                let request = written.lock().unwrap().request();
+                if let Err(e) = handle.send(request) {
+                    warn!(error = %e, "Failed to update watched accounts");
+                }
+            }
+        });
+        Self {
+            watchlist,
+            pending,
+            changed,
+            _shutdown: shutdown,
+        }
+    }
+
+    /// Watches `pubkey`, unsubscribing it once every receiver is dropped.
+    pub(crate) fn watch(self: &Arc<Self>, pubkey: &[u8]) -> AccountReceiver {
+        let mut watchlist = self.watchlist.lock().unwrap();
+        let (receiver, added) = watchlist.watch(pubkey);
+        if !added {
+            return receiver;
+        }
+        self.schedule_write();
+        let sender = watchlist.sender(pubkey).expect("account was just watched");
+        let subscription = Arc::downgrade(self);
+        let pubkey = pubkey.to_vec();
+        tokio::spawn(async move {
+            loop {
+                sender.closed().await;
+                let Some(subscription) = subscription.upgrade() else {
+                    return;
+                };
+                let mut watchlist = subscription.watchlist.lock().unwrap();
+                // A new receiver may have subscribed since the last one dropped.
+                if watchlist.release(&pubkey) {
+                    subscription.schedule_write();
+                    return;
+                }
+            }
+        });
+        receiver
+    }
+
+    fn schedule_write(&self) {
+        // One write per debounce window, however many watches change in it.
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            let _ = self.changed.send(());
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Compute-unit, fee and failure analytics per program.
+//!
+//! [`ProgramAnalytics`] walks the invocation tree of each transaction's logs
+//! ([`ProgramLogs`]) and credits every invoked program with the compute units its own
+//! code consumed (what `consumed N of M` reports minus what its CPIs consumed), the
+//! fees of the transactions it took part in, and the failures that originated in it.
+//! Stats are kept per slot over a rolling window, so any recent span can be ranked
+//! into a [`leaderboard`](ProgramAnalytics::leaderboard) or compared with the
+//! network-wide totals.
+
+use crate::logs::ProgramLogs;
+use crate::transaction::TransactionView;
+use laserstream_core_proto::geyser::SubscribeUpdate;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::AddAssign;
+
+/// Default number of slots kept, about one minute.
+const DEFAULT_MAX_SLOTS: u64 = 150;
+
+/// Totals for one program, or for the whole network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgramStats {
+    /// Transactions the program was invoked in.
+    pub transactions: u64,
+    /// Of those, transactions that failed.
+    pub failed_transactions: u64,
+    pub invocations: u64,
+    /// Failures that originated in the program.
+    pub failures_originated: u64,
+    /// Compute units consumed by the program's own code.
+    pub compute_units: u64,
+    /// Fees of the transactions the program was invoked in, in lamports.
+    pub fees: u64,
+}
+
+impl ProgramStats {
+    /// Share of transactions that failed.
+    pub fn failure_rate(&self) -> f64 {
+        if self.transactions == 0 {
+            0.0
+        } else {
+            self.failed_transactions as f64 / self.transactions as f64
+        }
+    }
+
+    /// Mean compute units per invocation.
+    pub fn compute_units_per_invocation(&self) -> Option<f64> {
+        (self.invocations > 0).then(|| self.compute_units as f64 / self.invocations as f64)
+    }
+}
+
+impl AddAssign for ProgramStats {
+    fn add_assign(&mut self, other: Self) {
+        self.transactions += other.transactions;
+        self.failed_transactions += other.failed_transactions;
+        self.invocations += other.invocations;
+        self.failures_originated += other.failures_originated;
+        self.compute_units += other.compute_units;
+        self.fees += other.fees;
+    }
+}
+
+/// What a [`leaderboard`](ProgramAnalytics::leaderboard) ranks programs by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    ComputeUnits,
+    Fees,
+    Transactions,
+    FailedTransactions,
+    FailuresOriginated,
+}
+
+impl Metric {
+    fn value(self, stats: &ProgramStats) -> u64 {
+        match self {
+            Self::ComputeUnits => stats.compute_units,
+            Self::Fees => stats.fees,
+            Self::Transactions => stats.transactions,
+            Self::FailedTransactions => stats.failed_transactions,
+            Self::FailuresOriginated => stats.failures_originated,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SlotStats {
+    /// Every non-vote transaction; `compute_units` is the transactions' total.
+    network: ProgramStats,
+    programs: HashMap<String, ProgramStats>,
+}
+
+/// Rolling per-program stats over recent slots.
+///
+/// Vote transactions are ignored. Transactions without logs count toward the network
+/// totals only.
+#[derive(Debug)]
+pub struct ProgramAnalytics {
+    slots: BTreeMap<u64, SlotStats>,
+    max_slots: u64,
+}
+
+impl Default for ProgramAnalytics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgramAnalytics {
+    pub fn new() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            max_slots: DEFAULT_MAX_SLOTS,
+        }
+    }
+
+    /// Number of slots, counted back from the newest slot seen, that are kept.
+    /// Default: 150
+    pub fn with_max_slots(mut self, max_slots: u64) -> Self {
+        self.max_slots = max_slots.max(1);
+        self
+    }
+
+    /// Newest slot with recorded transactions.
+    pub fn latest_slot(&self) -> Option<u64> {
+        self.slots.keys().next_back().copied()
+    }
+
+    /// Records the transactions of a transaction or block update.
+    pub fn apply(&mut self, update: &SubscribeUpdate) {
+        for tx in TransactionView::from_update(update) {
+            self.record(&tx);
+        }
+    }
+
+    /// Records one transaction; slots that already left the window are ignored.
+    pub fn record(&mut self, tx: &TransactionView<'_>) {
+        if tx.is_vote() {
+            return;
+        }
+        let slot = tx.slot();
+        if self
+            .latest_slot()
+            .is_some_and(|latest| slot + self.max_slots <= latest)
+        {
+            return;
+        }
+        let failed = !tx.is_success();
+        let stats = self.slots.entry(slot).or_default();
+        stats.network += ProgramStats {
+            transactions: 1,
+            failed_transactions: failed as u64,
+            compute_units: tx.compute_units_consumed().unwrap_or(0),
+            fees: tx.fee(),
+            ..Default::default()
+        };
+        if let Some(logs) = tx.program_logs() {
+            for (program, program_stats) in attribute(&logs, failed, tx.fee()) {
+                *stats.programs.entry(program).or_default() += program_stats;
+            }
+        }
+        let floor = self
+            .latest_slot()
+            .unwrap_or(slot)
+            .saturating_sub(self.max_slots - 1);
+        self.slots = self.slots.split_off(&floor);
+    }
+
+    /// Stats of `program_id` (base58) over the last `slots` slots.
+    pub fn program(&self, program_id: &str, slots: u64) -> ProgramStats {
+        let mut total = ProgramStats::default();
+        for stats in self.window(slots) {
+            if let Some(program) = stats.programs.get(program_id) {
+                total += *program;
+            }
+        }
+        total
+    }
+
+    /// Totals over every transaction in the last `slots` slots. `compute_units` is
+    /// what the transactions consumed, `invocations` and `failures_originated` are
+    /// zero.
+    pub fn network(&self, slots: u64) -> ProgramStats {
+        let mut total = ProgramStats::default();
+        for stats in self.window(slots) {
+            total += stats.network;
+        }
+        total
+    }
+
+    /// The `limit` programs ranking highest by `metric` over the last `slots` slots.
+    pub fn leaderboard(
+        &self,
+        metric: Metric,
+        slots: u64,
+        limit: usize,
+    ) -> Vec<(String, ProgramStats)> {
+        let mut totals: HashMap<&str, ProgramStats> = HashMap::new();
+        for stats in self.window(slots) {
+            for (program, program_stats) in &stats.programs {
+                *totals.entry(program).or_default() += *program_stats;
+            }
+        }
+        let mut ranked: Vec<_> = totals
+            .into_iter()
+            .map(|(program, stats)| (program.to_string(), stats))
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| {
+            metric
+                .value(b)
+                .cmp(&metric.value(a))
+                .then_with(|| a_id.cmp(b_id))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+
+    fn window(&self, slots: u64) -> impl Iterator<Item = &SlotStats> {
+        let from = self
+            .latest_slot()
+            .map_or(u64::MAX, |latest| (latest + 1).saturating_sub(slots));
+        self.slots.range(from..).map(|(_, stats)| stats)
+    }
+}
+
+/// Stats of each program invoked in one transaction.
+fn attribute(logs: &ProgramLogs, failed: bool, fee: u64) -> HashMap<String, ProgramStats> {
+    let invocations = logs.invocations();
+    let origin = logs.failure_origin();
+    let mut programs: HashMap<String, ProgramStats> = HashMap::new();
+    let mut seen = HashSet::new();
+    for invocation in invocations {
+        let children: u64 = invocation
+            .children()
+            .filter_map(|child| invocations[child].compute_units_consumed)
+            .sum();
+        let own = invocation
+            .compute_units_consumed
+            .map_or(0, |consumed| consumed.saturating_sub(children));
+        let stats = programs.entry(invocation.program_id.clone()).or_default();
+        stats.invocations += 1;
+        stats.compute_units += own;
+        if origin.is_some_and(|origin| std::ptr::eq(origin, invocation)) {
+            stats.failures_originated += 1;
+        }
+        if seen.insert(invocation.program_id.as_str()) {
+            stats.transactions += 1;
+            stats.failed_transactions += failed as u64;
+            stats.fees += fee;
+        }
+    }
+    programs
+}
@@ -0,0 +1,331 @@
+//! Anchor IDL JSON, in both the current (Anchor 0.30+) and the legacy layout.
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Type of a field or argument.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum IdlType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+    U128,
+    I128,
+    Bytes,
+    String,
+    Pubkey,
+    Vec(Box<IdlType>),
+    Option(Box<IdlType>),
+    COption(Box<IdlType>),
+    Array(Box<IdlType>, usize),
+    Defined(String),
+    /// Generics and other types this decoder can't lay out; decoding fails when
+    /// one is reached.
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Fields {
+    Named(Vec<(String, IdlType)>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TypeDef {
+    Struct(Fields),
+    Enum(Vec<(String, Fields)>),
+    Alias(IdlType),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct IdlInstruction {
+    pub(crate) name: String,
+    pub(crate) discriminator: Vec<u8>,
+    /// Account names in order, nested account groups flattened.
+    pub(crate) accounts: Vec<String>,
+    pub(crate) args: Fields,
+}
+
+/// An account or event: its discriminator and the name of its type in `types`.
+#[derive(Debug, Clone)]
+pub(crate) struct IdlEntry {
+    pub(crate) name: String,
+    pub(crate) discriminator: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Idl {
+    pub(crate) name: String,
+    pub(crate) address: Option<String>,
+    pub(crate) instructions: Vec<IdlInstruction>,
+    pub(crate) accounts: Vec<IdlEntry>,
+    pub(crate) events: Vec<IdlEntry>,
+    pub(crate) types: HashMap<String, TypeDef>,
+}
+
+/// First 8 bytes of `sha256("<namespace>:<name>")`.
+pub(crate) fn discriminator(namespace: &str, name: &str) -> Vec<u8> {
+    Sha256::digest(format!("{namespace}:{name}").as_bytes())[..8].to_vec()
+}
+
+/// `initializeVault` -> `initialize_vault`, as legacy IDLs hash snake-case names.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let boundary = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(|p| p.is_uppercase())
+                    && next.is_some_and(|n| n.is_lowercase()));
+            if boundary {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(*c);
+        }
+    }
+    out
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing `{key}` in {value}"))
+}
+
+fn array_field<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn explicit_discriminator(value: &Value) -> Result<Option<Vec<u8>>, String> {
+    let Some(bytes) = value.get("discriminator") else {
+        return Ok(None);
+    };
+    serde_json::from_value(bytes.clone())
+        .map(Some)
+        .map_err(|e| format!("invalid discriminator {bytes}: {e}"))
+}
+
+pub(crate) fn parse_type(value: &Value) -> Result<IdlType, String> {
+    if let Some(name) = value.as_str() {
+        return Ok(match name {
+            "bool" => IdlType::Bool,
+            "u8" => IdlType::U8,
+            "i8" => IdlType::I8,
+            "u16" => IdlType::U16,
+            "i16" => IdlType::I16,
+            "u32" => IdlType::U32,
+            "i32" => IdlType::I32,
+            "f32" => IdlType::F32,
+            "u64" => IdlType::U64,
+            "i64" => IdlType::I64,
+            "f64" => IdlType::F64,
+            "u128" => IdlType::U128,
+            "i128" => IdlType::I128,
+            "bytes" => IdlType::Bytes,
+            "string" => IdlType::String,
+            "pubkey" | "publicKey" => IdlType::Pubkey,
+            other => IdlType::Unsupported(other.to_string()),
+        });
+    }
+    let boxed = |key: &str| -> Result<Option<Box<IdlType>>, String> {
+        value
+            .get(key)
+            .map(|inner| parse_type(inner).map(Box::new))
+            .transpose()
+    };
+    if let Some(inner) = boxed("vec")? {
+        return Ok(IdlType::Vec(inner));
+    }
+    if let Some(inner) = boxed("option")? {
+        return Ok(IdlType::Option(inner));
+    }
+    if let Some(inner) = boxed("coption")? {
+        return Ok(IdlType::COption(inner));
+    }
+    if let Some(array) = value.get("array").and_then(Value::as_array) {
+        let (Some(inner), Some(len)) = (array.first(), array.get(1)) else {
+            return Err(format!("invalid array type {value}"));
+        };
+        return Ok(match len.as_u64() {
+            Some(len) => IdlType::Array(Box::new(parse_type(inner)?), len as usize),
+            None => IdlType::Unsupported(value.to_string()),
+        });
+    }
+    if let Some(defined) = value.get("defined") {
+        if defined
+            .get("generics")
+            .is_some_and(|g| g.as_array().is_some_and(|g| !g.is_empty()))
+        {
+            return Ok(IdlType::Unsupported(value.to_string()));
+        }
+        let name = defined
+            .as_str()
+            .or_else(|| defined.get("name").and_then(Value::as_str));
+        return name
+            .map(|name| IdlType::Defined(name.to_string()))
+            .ok_or_else(|| format!("invalid defined type {value}"));
+    }
+    Ok(IdlType::Unsupported(value.to_string()))
+}
+
+/// Missing and empty field lists are an empty tuple, so fieldless enum variants
+/// decode as their name.
+fn parse_fields(fields: Option<&Value>) -> Result<Fields, String> {
+    let fields = fields
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    if fields.is_empty() {
+        return Ok(Fields::Tuple(Vec::new()));
+    }
+    if fields
+        .iter()
+        .all(|field| field.get("name").is_some() && field.get("type").is_some())
+    {
+        fields
+            .iter()
+            .map(|field| {
+                Ok((
+                    str_field(field, "name")?.to_string(),
+                    parse_type(&field["type"])?,
+                ))
+            })
+            .collect::<Result<_, String>>()
+            .map(Fields::Named)
+    } else {
+        fields
+            .iter()
+            .map(parse_type)
+            .collect::<Result<_, _>>()
+            .map(Fields::Tuple)
+    }
+}
+
+fn parse_type_def(ty: &Value) -> Result<TypeDef, String> {
+    match str_field(ty, "kind")? {
+        "struct" => Ok(TypeDef::Struct(parse_fields(ty.get("fields"))?)),
+        "enum" => array_field(ty, "variants")
+            .iter()
+            .map(|variant| {
+                Ok((
+                    str_field(variant, "name")?.to_string(),
+                    parse_fields(variant.get("fields"))?,
+                ))
+            })
+            .collect::<Result<_, String>>()
+            .map(TypeDef::Enum),
+        "type" | "alias" => Ok(TypeDef::Alias(parse_type(
+            ty.get("alias")
+                .ok_or_else(|| format!("missing `alias` in {ty}"))?,
+        )?)),
+        other => Err(format!("unknown type kind `{other}`")),
+    }
+}
+
+fn flatten_accounts(accounts: &[Value], out: &mut Vec<String>) -> Result<(), String> {
+    for account in accounts {
+        match account.get("accounts").and_then(Value::as_array) {
+            Some(nested) => flatten_accounts(nested, out)?,
+            None => out.push(str_field(account, "name")?.to_string()),
+        }
+    }
+    Ok(())
+}
+
+impl Idl {
+    pub(crate) fn parse(idl: &Value) -> Result<Self, String> {
+        let name = idl
+            .get("metadata")
+            .and_then(|m| m.get("name"))
+            .or_else(|| idl.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let address = idl
+            .get("address")
+            .or_else(|| idl.get("metadata").and_then(|m| m.get("address")))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let mut types = HashMap::new();
+        for ty in array_field(idl, "types") {
+            let ty_def = ty
+                .get("type")
+                .ok_or_else(|| format!("missing `type` in {ty}"))?;
+            types.insert(str_field(ty, "name")?.to_string(), parse_type_def(ty_def)?);
+        }
+
+        let instructions = array_field(idl, "instructions")
+            .iter()
+            .map(|ix| {
+                let name = str_field(ix, "name")?.to_string();
+                let discriminator = match explicit_discriminator(ix)? {
+                    Some(d) => d,
+                    None => discriminator("global", &snake_case(&name)),
+                };
+                let mut accounts = Vec::new();
+                flatten_accounts(array_field(ix, "accounts"), &mut accounts)?;
+                Ok(IdlInstruction {
+                    name,
+                    discriminator,
+                    accounts,
+                    args: parse_fields(ix.get("args"))?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        // Legacy IDLs define account and event layouts inline; move them into `types`
+        // so both layouts decode the same way.
+        let mut entries = |key: &str, namespace: &str| -> Result<Vec<IdlEntry>, String> {
+            array_field(idl, key)
+                .iter()
+                .map(|entry| {
+                    let name = str_field(entry, "name")?.to_string();
+                    if let Some(ty) = entry.get("type") {
+                        types.insert(name.clone(), parse_type_def(ty)?);
+                    } else if entry.get("fields").is_some() {
+                        types.insert(
+                            name.clone(),
+                            TypeDef::Struct(parse_fields(entry.get("fields"))?),
+                        );
+                    }
+                    let discriminator = match explicit_discriminator(entry)? {
+                        Some(d) => d,
+                        None => discriminator(namespace, &name),
+                    };
+                    Ok(IdlEntry {
+                        name,
+                        discriminator,
+                    })
+                })
+                .collect()
+        };
+        let accounts = entries("accounts", "account")?;
+        let events = entries("events", "event")?;
+
+        Ok(Self {
+            name,
+            address,
+            instructions,
+            accounts,
+            events,
+            types,
+        })
+    }
+}
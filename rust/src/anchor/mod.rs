@@ -0,0 +1,249 @@
+//! Decoding of Anchor programs from their IDL.
+//!
+//! An [`AnchorIdl`] is loaded from IDL JSON at runtime (Anchor 0.30+ or the legacy
+//! layout) and decodes accounts, instructions and events by their discriminators into
+//! `serde_json::Value`. Events are read from `Program data:` log lines (`emit!`) and
+//! from self-CPI inner instructions (`emit_cpi!`). It also implements
+//! [`InstructionDecoder`] so an IDL can sit in an [`InstructionRegistry`] next to the
+//! built-in decoders.
+//!
+//! Values follow the Anchor TypeScript client where JSON allows: public keys are
+//! base58, `u128`/`i128` are decimal strings, `bytes` are arrays of numbers, unit
+//! enum variants are their name and other variants `{ "Name": fields }`. Zero-copy
+//! accounts are read as borsh, which matches their layout only when it has no padding.
+//!
+//! [`InstructionRegistry`]: crate::InstructionRegistry
+
+mod idl;
+mod value;
+
+use crate::error::LaserstreamError;
+use crate::grpc::SubscribeUpdateAccountInfo;
+use crate::instructions::{InstructionDecoder, InstructionNode, ParsedInstruction};
+use crate::layout::Reader;
+use crate::logs::ProgramLogs;
+use crate::transaction::TransactionView;
+use idl::{Idl, IdlType};
+use serde_json::{json, Map, Value};
+use value::ValueReader;
+
+/// Prefix of the self-CPI instruction `emit_cpi!` uses to carry an event.
+pub const EVENT_IX_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+
+/// An account decoded by its discriminator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorAccount {
+    /// Account type name from the IDL, e.g. `"Vault"`.
+    pub name: String,
+    pub data: Value,
+}
+
+/// An instruction decoded by its discriminator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorInstruction {
+    /// Instruction name from the IDL, e.g. `"deposit"`.
+    pub name: String,
+    /// Arguments as an object keyed by argument name.
+    pub args: Value,
+    /// IDL account names in order, nested account groups flattened. Remaining
+    /// accounts passed beyond these have no name.
+    pub accounts: Vec<String>,
+}
+
+/// An event emitted by the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorEvent {
+    /// Event name from the IDL, e.g. `"DepositMade"`.
+    pub name: String,
+    pub data: Value,
+}
+
+/// A loaded Anchor IDL.
+#[derive(Debug, Clone)]
+pub struct AnchorIdl {
+    idl: Idl,
+    program_id: Option<Vec<u8>>,
+}
+
+impl AnchorIdl {
+    /// Parses IDL JSON. The program id is read from `address` (or the legacy
+    /// `metadata.address`); set it with [`with_program_id`](Self::with_program_id)
+    /// when the IDL has none.
+    pub fn from_json(json: &str) -> Result<Self, LaserstreamError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| LaserstreamError::InvalidIdl(e.to_string()))?;
+        Self::from_value(&value)
+    }
+
+    /// [`from_json`](Self::from_json) for already parsed JSON.
+    pub fn from_value(value: &Value) -> Result<Self, LaserstreamError> {
+        let idl = Idl::parse(value).map_err(LaserstreamError::InvalidIdl)?;
+        let program_id = match &idl.address {
+            Some(address) => Some(decode_program_id(address)?),
+            None => None,
+        };
+        Ok(Self { idl, program_id })
+    }
+
+    /// Overrides the program id, for IDLs without an address or programs deployed
+    /// under a different one.
+    pub fn with_program_id(mut self, program_id: &str) -> Result<Self, LaserstreamError> {
+        self.program_id = Some(decode_program_id(program_id)?);
+        self.idl.address = Some(program_id.to_string());
+        Ok(self)
+    }
+
+    /// Program name from the IDL.
+    pub fn name(&self) -> &str {
+        &self.idl.name
+    }
+
+    /// Program id (base58), if known.
+    pub fn program_id(&self) -> Option<&str> {
+        self.idl.address.as_deref()
+    }
+
+    fn reader(&self) -> ValueReader<'_> {
+        ValueReader::new(&self.idl.types)
+    }
+
+    /// Decodes account data by its discriminator. `None` if no IDL account matches
+    /// or the data doesn't fit the layout.
+    pub fn decode_account(&self, data: &[u8]) -> Option<AnchorAccount> {
+        let entry = self
+            .idl
+            .accounts
+            .iter()
+            .find(|entry| data.starts_with(&entry.discriminator))?;
+        let mut r = Reader::new(&data[entry.discriminator.len()..]);
+        Some(AnchorAccount {
+            name: entry.name.clone(),
+            data: self
+                .reader()
+                .read(&IdlType::Defined(entry.name.clone()), &mut r, 0)?,
+        })
+    }
+
+    /// [`decode_account`](Self::decode_account) for a streamed account, skipping
+    /// accounts owned by other programs when the program id is known.
+    pub fn decode_account_info(&self, info: &SubscribeUpdateAccountInfo) -> Option<AnchorAccount> {
+        if self.program_id.as_ref().is_some_and(|id| *id != info.owner) {
+            return None;
+        }
+        self.decode_account(&info.data)
+    }
+
+    /// Decodes instruction data by its discriminator.
+    pub fn decode_instruction(&self, data: &[u8]) -> Option<AnchorInstruction> {
+        let ix = self
+            .idl
+            .instructions
+            .iter()
+            .find(|ix| data.starts_with(&ix.discriminator))?;
+        let mut r = Reader::new(&data[ix.discriminator.len()..]);
+        Some(AnchorInstruction {
+            name: ix.name.clone(),
+            args: self.reader().read_fields(&ix.args, &mut r, 0)?,
+            accounts: ix.accounts.clone(),
+        })
+    }
+
+    /// Decodes event bytes (discriminator followed by the borsh payload), as logged
+    /// by `emit!`.
+    pub fn decode_event(&self, data: &[u8]) -> Option<AnchorEvent> {
+        let entry = self
+            .idl
+            .events
+            .iter()
+            .find(|entry| data.starts_with(&entry.discriminator))?;
+        let mut r = Reader::new(&data[entry.discriminator.len()..]);
+        Some(AnchorEvent {
+            name: entry.name.clone(),
+            data: self
+                .reader()
+                .read(&IdlType::Defined(entry.name.clone()), &mut r, 0)?,
+        })
+    }
+
+    /// Events in `Program data:` lines logged while this program was executing.
+    /// Without a program id every `Program data:` line is tried.
+    pub fn events_from_logs(&self, logs: &[String]) -> Vec<AnchorEvent> {
+        ProgramLogs::parse(logs)
+            .invocations()
+            .iter()
+            .filter(|invocation| match self.program_id() {
+                Some(id) => invocation.program_id == id,
+                None => true,
+            })
+            .flat_map(|invocation| invocation.data())
+            .filter_map(|fields| self.decode_event(fields.first()?))
+            .collect()
+    }
+
+    /// Event carried by an `emit_cpi!` self-CPI instruction.
+    pub fn decode_cpi_event(&self, ix: &InstructionNode<'_>) -> Option<AnchorEvent> {
+        if self
+            .program_id
+            .as_deref()
+            .is_some_and(|id| id != ix.program_id)
+        {
+            return None;
+        }
+        self.decode_event(ix.data.strip_prefix(&EVENT_IX_TAG)?)
+    }
+
+    /// All events of a transaction: logged events first, then self-CPI events in
+    /// execution order.
+    pub fn decode_events(&self, tx: &TransactionView<'_>) -> Vec<AnchorEvent> {
+        let mut events = tx
+            .logs()
+            .map(|logs| self.events_from_logs(logs))
+            .unwrap_or_default();
+        events.extend(
+            InstructionNode::collect(tx)
+                .iter()
+                .filter(|ix| ix.is_inner())
+                .filter_map(|ix| self.decode_cpi_event(ix)),
+        );
+        events
+    }
+}
+
+fn decode_program_id(program_id: &str) -> Result<Vec<u8>, LaserstreamError> {
+    bs58::decode(program_id)
+        .into_vec()
+        .map_err(|e| LaserstreamError::InvalidIdl(format!("invalid program id {program_id}: {e}")))
+}
+
+/// Instructions decode to `{ "name", "args", "accounts": { name: address } }`;
+/// `emit_cpi!` self-CPIs to `{ "event", "data" }`.
+impl InstructionDecoder for AnchorIdl {
+    fn program_ids(&self) -> Vec<String> {
+        self.program_id().map(str::to_string).into_iter().collect()
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        self.idl.name.clone()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        if let Some(event) = self.decode_cpi_event(ix) {
+            return Some(ParsedInstruction::Other(json!({
+                "event": event.name,
+                "data": event.data,
+            })));
+        }
+        let decoded = self.decode_instruction(ix.data)?;
+        let accounts: Map<String, Value> = decoded
+            .accounts
+            .iter()
+            .zip(&ix.accounts)
+            .map(|(name, key)| (name.clone(), bs58::encode(key).into_string().into()))
+            .collect();
+        Some(ParsedInstruction::Other(json!({
+            "name": decoded.name,
+            "args": decoded.args,
+            "accounts": accounts,
+        })))
+    }
+}
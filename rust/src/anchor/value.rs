@@ -0,0 +1,188 @@
+//! Borsh data laid out by IDL types, read into `serde_json::Value`.
+
+use super::idl::{Fields, IdlType, TypeDef};
+use crate::layout::Reader;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+/// Deepest nesting of defined types; guards against self-referencing IDLs.
+const MAX_DEPTH: usize = 64;
+/// Longest vector of elements that take no bytes, which the data length can't bound.
+const MAX_EMPTY_ELEMENTS: usize = 1 << 16;
+
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+pub(crate) struct ValueReader<'a> {
+    types: &'a HashMap<String, TypeDef>,
+}
+
+impl<'a> ValueReader<'a> {
+    pub(crate) fn new(types: &'a HashMap<String, TypeDef>) -> Self {
+        Self { types }
+    }
+
+    pub(crate) fn read(&self, ty: &IdlType, r: &mut Reader<'_>, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match ty {
+            IdlType::Bool => Value::Bool(r.bool()?),
+            IdlType::U8 => r.u8()?.into(),
+            IdlType::I8 => i8::from_le_bytes(r.array()?).into(),
+            IdlType::U16 => r.u16()?.into(),
+            IdlType::I16 => r.i16()?.into(),
+            IdlType::U32 => r.u32()?.into(),
+            IdlType::I32 => i32::from_le_bytes(r.array()?).into(),
+            IdlType::F32 => float(f32::from_le_bytes(r.array()?) as f64),
+            IdlType::U64 => r.u64()?.into(),
+            IdlType::I64 => r.i64()?.into(),
+            IdlType::F64 => float(f64::from_le_bytes(r.array()?)),
+            // Wider than JSON numbers can carry exactly.
+            IdlType::U128 => u128::from_le_bytes(r.array()?).to_string().into(),
+            IdlType::I128 => i128::from_le_bytes(r.array()?).to_string().into(),
+            IdlType::Bytes => {
+                let len = r.u32()? as usize;
+                r.bytes(len)?.to_vec().into()
+            }
+            IdlType::String => r.borsh_string()?.into(),
+            IdlType::Pubkey => r.pubkey()?.into(),
+            IdlType::Vec(inner) => {
+                let len = r.u32()? as usize;
+                let bound = match self.min_size(inner, depth + 1) {
+                    0 => MAX_EMPTY_ELEMENTS,
+                    size => r.remaining().len() / size,
+                };
+                if len > bound {
+                    return None;
+                }
+                Value::Array(self.read_many(inner, len, r, depth)?)
+            }
+            IdlType::Option(inner) => match r.u8()? {
+                0 => Value::Null,
+                1 => self.read(inner, r, depth + 1)?,
+                _ => return None,
+            },
+            // The value's bytes are present even when the tag says none.
+            IdlType::COption(inner) => match r.u32()? {
+                0 => {
+                    r.bytes(self.fixed_size(inner, depth + 1)?)?;
+                    Value::Null
+                }
+                1 => self.read(inner, r, depth + 1)?,
+                _ => return None,
+            },
+            IdlType::Array(inner, len) => Value::Array(self.read_many(inner, *len, r, depth)?),
+            IdlType::Defined(name) => match self.types.get(name)? {
+                TypeDef::Struct(fields) => self.read_fields(fields, r, depth + 1)?,
+                TypeDef::Enum(variants) => {
+                    let (name, fields) = variants.get(r.u8()? as usize)?;
+                    if matches!(fields, Fields::Tuple(types) if types.is_empty()) {
+                        Value::String(name.clone())
+                    } else {
+                        let mut variant = Map::new();
+                        variant.insert(name.clone(), self.read_fields(fields, r, depth + 1)?);
+                        Value::Object(variant)
+                    }
+                }
+                TypeDef::Alias(ty) => self.read(ty, r, depth + 1)?,
+            },
+            IdlType::Unsupported(_) => return None,
+        })
+    }
+
+    fn read_many(
+        &self,
+        ty: &IdlType,
+        len: usize,
+        r: &mut Reader<'_>,
+        depth: usize,
+    ) -> Option<Vec<Value>> {
+        (0..len).map(|_| self.read(ty, r, depth + 1)).collect()
+    }
+
+    /// Named fields as an object, tuple fields as an array.
+    pub(crate) fn read_fields(
+        &self,
+        fields: &Fields,
+        r: &mut Reader<'_>,
+        depth: usize,
+    ) -> Option<Value> {
+        Some(match fields {
+            Fields::Named(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, ty)| Some((name.clone(), self.read(ty, r, depth)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Fields::Tuple(types) => Value::Array(
+                types
+                    .iter()
+                    .map(|ty| self.read(ty, r, depth))
+                    .collect::<Option<_>>()?,
+            ),
+        })
+    }
+
+    /// Fewest bytes a value of `ty` can be encoded in.
+    fn min_size(&self, ty: &IdlType, depth: usize) -> usize {
+        if depth > MAX_DEPTH {
+            return 0;
+        }
+        let fields_size = |fields: &Fields| -> usize {
+            match fields {
+                Fields::Named(fields) => fields
+                    .iter()
+                    .map(|(_, ty)| self.min_size(ty, depth + 1))
+                    .sum(),
+                Fields::Tuple(types) => types.iter().map(|ty| self.min_size(ty, depth + 1)).sum(),
+            }
+        };
+        match ty {
+            IdlType::Bytes | IdlType::String | IdlType::Vec(_) => 4,
+            IdlType::Option(_) => 1,
+            IdlType::COption(inner) => 4 + self.min_size(inner, depth + 1),
+            IdlType::Array(inner, len) => self.min_size(inner, depth + 1).saturating_mul(*len),
+            IdlType::Defined(name) => match self.types.get(name) {
+                Some(TypeDef::Struct(fields)) => fields_size(fields),
+                Some(TypeDef::Alias(ty)) => self.min_size(ty, depth + 1),
+                // The variant tag.
+                Some(TypeDef::Enum(_)) => 1,
+                None => 0,
+            },
+            IdlType::Unsupported(_) => 0,
+            ty => self.fixed_size(ty, depth).unwrap_or(0),
+        }
+    }
+
+    /// Encoded size of types that don't vary with their value.
+    fn fixed_size(&self, ty: &IdlType, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let fields_size = |fields: &Fields| -> Option<usize> {
+            let types: Vec<&IdlType> = match fields {
+                Fields::Named(fields) => fields.iter().map(|(_, ty)| ty).collect(),
+                Fields::Tuple(types) => types.iter().collect(),
+            };
+            types.iter().map(|ty| self.fixed_size(ty, depth + 1)).sum()
+        };
+        match ty {
+            IdlType::Bool | IdlType::U8 | IdlType::I8 => Some(1),
+            IdlType::U16 | IdlType::I16 => Some(2),
+            IdlType::U32 | IdlType::I32 | IdlType::F32 => Some(4),
+            IdlType::U64 | IdlType::I64 | IdlType::F64 => Some(8),
+            IdlType::U128 | IdlType::I128 => Some(16),
+            IdlType::Pubkey => Some(32),
+            IdlType::COption(inner) => Some(4 + self.fixed_size(inner, depth + 1)?),
+            IdlType::Array(inner, len) => self.fixed_size(inner, depth + 1)?.checked_mul(*len),
+            IdlType::Defined(name) => match self.types.get(name)? {
+                TypeDef::Struct(fields) => fields_size(fields),
+                TypeDef::Alias(ty) => self.fixed_size(ty, depth + 1),
+                TypeDef::Enum(_) => None,
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Recent blockhashes served from the block meta stream.
+//!
+//! [`BlockhashCache`] follows `blocks_meta` updates of a PROCESSED subscription,
+//! together with slot statuses, to keep the freshest blockhash at each commitment
+//! and every blockhash that is still valid. A blockhash stays valid for
+//! [`MAX_PROCESSING_AGE`] blocks after the block that produced it, the same
+//! `last_valid_block_height` the unary `GetLatestBlockhash` call reports, which
+//! [`BlockhashCache::start`] uses to seed the cache.
+
+use crate::client::{get_latest_blockhash, subscribe_with_mode, SlotTrackingMode, StreamItem};
+use crate::slots::{SlotEvent, SlotStage, SlotTracker};
+use crate::{LaserstreamConfig, LaserstreamError};
+use futures::StreamExt;
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, GetLatestBlockhashResponse, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeUpdate, SubscribeUpdateBlockMeta,
+    SubscribeUpdateSlot,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// Number of blocks a blockhash can be used for after the block that produced it.
+pub const MAX_PROCESSING_AGE: u64 = 150;
+
+const COMMITMENTS: [CommitmentLevel; 3] = [
+    CommitmentLevel::Processed,
+    CommitmentLevel::Confirmed,
+    CommitmentLevel::Finalized,
+];
+
+/// A blockhash and the block heights it is valid through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blockhash {
+    pub blockhash: String,
+    pub slot: u64,
+    pub last_valid_block_height: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Blockhashes of processed blocks by slot.
+    blocks: BTreeMap<u64, Blockhash>,
+    /// Every known blockhash and the last block height it is valid at.
+    valid_through: HashMap<String, u64>,
+    latest: HashMap<i32, Blockhash>,
+    /// Highest processed block height.
+    block_height: u64,
+    slots: SlotTracker,
+    /// Set by [`BlockhashCache::start`] and dropped with the last clone, which ends
+    /// the subscription task even while no updates arrive.
+    _shutdown: Option<oneshot::Sender<()>>,
+}
+
+/// Blockhashes shared between the stream that updates them and the senders that read
+/// them. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct BlockhashCache {
+    state: Arc<RwLock<State>>,
+}
+
+impl BlockhashCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seeds a cache from `GetLatestBlockhash` at every commitment and keeps it
+    /// current from a background subscription until the last clone is dropped.
+    pub async fn start(config: LaserstreamConfig) -> Result<Self, LaserstreamError> {
+        let cache = Self::new();
+        for commitment in COMMITMENTS {
+            let response = get_latest_blockhash(&config, commitment).await?;
+            cache.seed(commitment, &response);
+        }
+
+        let (stream, _handle) =
+            subscribe_with_mode(config, Self::request(), SlotTrackingMode::AllStatuses);
+        let (shutdown, mut stopped) = oneshot::channel::<()>();
+        cache.state.write().unwrap()._shutdown = Some(shutdown);
+        let state = Arc::downgrade(&cache.state);
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            loop {
+                let item = tokio::select! {
+                    item = stream.next() => item,
+                    _ = &mut stopped => break,
+                };
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+                let cache = BlockhashCache { state };
+                match item {
+                    Some(Ok(StreamItem::Update(update))) => cache.apply(&update),
+                    Some(Ok(StreamItem::InternalSlot(slot))) => cache.slot(&slot),
+                    Some(Err(e)) => warn!(error = %e, "Blockhash subscription error"),
+                    None => break,
+                }
+            }
+        });
+        Ok(cache)
+    }
+
+    /// PROCESSED request for every block meta; feed its updates to
+    /// [`apply`](Self::apply) along with every slot status.
+    pub fn request() -> SubscribeRequest {
+        let mut request = SubscribeRequest {
+            commitment: Some(CommitmentLevel::Processed as i32),
+            ..Default::default()
+        };
+        request.blocks_meta.insert(
+            "blockhash-cache".to_string(),
+            SubscribeRequestFilterBlocksMeta::default(),
+        );
+        request
+    }
+
+    /// Stores the answer of a `GetLatestBlockhash` call made at `commitment`.
+    pub fn seed(&self, commitment: CommitmentLevel, response: &GetLatestBlockhashResponse) {
+        let blockhash = Blockhash {
+            blockhash: response.blockhash.clone(),
+            slot: response.slot,
+            last_valid_block_height: response.last_valid_block_height,
+        };
+        let mut state = self.state.write().unwrap();
+        state.valid_through.insert(
+            blockhash.blockhash.clone(),
+            blockhash.last_valid_block_height,
+        );
+        let height = response
+            .last_valid_block_height
+            .saturating_sub(MAX_PROCESSING_AGE);
+        state.block_height = state.block_height.max(height);
+        state.promote(commitment, blockhash);
+    }
+
+    /// Applies block meta and slot updates; other updates are ignored.
+    pub fn apply(&self, update: &SubscribeUpdate) {
+        match &update.update_oneof {
+            Some(UpdateOneof::BlockMeta(meta)) => self.block_meta(meta),
+            Some(UpdateOneof::Slot(slot)) => self.slot(slot),
+            _ => {}
+        }
+    }
+
+    /// Records the blockhash of a processed block.
+    pub fn block_meta(&self, meta: &SubscribeUpdateBlockMeta) {
+        let Some(height) = meta.block_height.as_ref().map(|h| h.block_height) else {
+            return;
+        };
+        let mut state = self.state.write().unwrap();
+        if state
+            .slots
+            .get(meta.slot)
+            .is_some_and(|info| info.is_orphaned())
+        {
+            return;
+        }
+        let blockhash = Blockhash {
+            blockhash: meta.blockhash.clone(),
+            slot: meta.slot,
+            last_valid_block_height: height + MAX_PROCESSING_AGE,
+        };
+        state.valid_through.insert(
+            blockhash.blockhash.clone(),
+            blockhash.last_valid_block_height,
+        );
+        state.blocks.insert(meta.slot, blockhash.clone());
+        state.block_height = state.block_height.max(height);
+
+        let reached = state.reached(meta.slot);
+        for commitment in COMMITMENTS {
+            if commitment as i32 <= reached as i32 {
+                state.promote(commitment, blockhash.clone());
+            }
+        }
+        state.prune();
+    }
+
+    /// Records a slot status, promoting the slot's blockhash or dropping it when the
+    /// slot is orphaned.
+    pub fn slot(&self, update: &SubscribeUpdateSlot) {
+        let mut state = self.state.write().unwrap();
+        for event in state.slots.update(update) {
+            match event {
+                SlotEvent::Stage { slot, stage } => {
+                    let commitment = match stage {
+                        SlotStage::Confirmed => CommitmentLevel::Confirmed,
+                        SlotStage::Finalized => CommitmentLevel::Finalized,
+                        _ => continue,
+                    };
+                    if let Some(blockhash) = state.blocks.get(&slot).cloned() {
+                        state.promote(commitment, blockhash);
+                    }
+                }
+                SlotEvent::Orphaned { slot, .. } => state.orphan(slot),
+                SlotEvent::Reorg { .. } => {}
+            }
+        }
+    }
+
+    /// Freshest blockhash at `commitment` that is still valid.
+    pub fn latest(&self, commitment: CommitmentLevel) -> Option<Blockhash> {
+        let state = self.state.read().unwrap();
+        state
+            .latest
+            .get(&(commitment as i32))
+            .filter(|blockhash| blockhash.last_valid_block_height >= state.block_height)
+            .cloned()
+    }
+
+    /// Whether `blockhash` is known and can still be used in a transaction.
+    pub fn is_valid(&self, blockhash: &str) -> bool {
+        let state = self.state.read().unwrap();
+        state
+            .valid_through
+            .get(blockhash)
+            .is_some_and(|last_valid| *last_valid >= state.block_height)
+    }
+
+    /// Highest processed block height seen.
+    pub fn block_height(&self) -> u64 {
+        self.state.read().unwrap().block_height
+    }
+}
+
+impl State {
+    /// Commitment `slot` has reached.
+    fn reached(&self, slot: u64) -> CommitmentLevel {
+        match self.slots.get(slot) {
+            Some(info) if info.reached(SlotStage::Finalized).is_some() => {
+                CommitmentLevel::Finalized
+            }
+            Some(info) if info.reached(SlotStage::Confirmed).is_some() => {
+                CommitmentLevel::Confirmed
+            }
+            _ => CommitmentLevel::Processed,
+        }
+    }
+
+    fn promote(&mut self, commitment: CommitmentLevel, blockhash: Blockhash) {
+        let latest = self
+            .latest
+            .entry(commitment as i32)
+            .or_insert_with(|| blockhash.clone());
+        if blockhash.slot >= latest.slot {
+            *latest = blockhash;
+        }
+    }
+
+    fn orphan(&mut self, slot: u64) {
+        let Some(orphaned) = self.blocks.remove(&slot) else {
+            return;
+        };
+        self.valid_through.remove(&orphaned.blockhash);
+        for commitment in COMMITMENTS {
+            let key = commitment as i32;
+            if self.latest.get(&key).map(|b| b.slot) != Some(slot) {
+                continue;
+            }
+            // Fall back to the newest remaining block at this commitment.
+            let fallback = self
+                .blocks
+                .values()
+                .rev()
+                .find(|b| self.reached(b.slot) as i32 >= commitment as i32)
+                .cloned();
+            match fallback {
+                Some(blockhash) => self.latest.insert(key, blockhash),
+                None => self.latest.remove(&key),
+            };
+        }
+    }
+
+    /// Forgets blockhashes that expired.
+    fn prune(&mut self) {
+        let height = self.block_height;
+        self.valid_through
+            .retain(|_, last_valid| *last_valid >= height);
+        self.blocks
+            .retain(|_, blockhash| blockhash.last_valid_block_height >= height);
+    }
+}
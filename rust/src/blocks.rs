@@ -0,0 +1,335 @@
+//! Block assembly from transaction, entry and block-meta updates.
+//!
+//! A `blocks` subscription with transactions and accounts delivers each block as one
+//! very large message. [`BlockAssembler`] rebuilds the same [`SubscribeUpdateBlock`]
+//! from the much smaller `transactions`, `entry` and `blocks_meta` updates, and only
+//! emits it once the counts announced by the block meta are all there.
+
+use crate::LaserstreamError;
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdateAccountInfo,
+    SubscribeUpdateBlock, SubscribeUpdateBlockMeta, SubscribeUpdateEntry,
+    SubscribeUpdateTransactionInfo,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Filter name used by [`block_request`] for every filter it adds.
+pub const BLOCK_ASSEMBLY_FILTER: &str = "block-assembly";
+
+/// Number of recently emitted slots remembered to ignore late duplicates.
+const COMPLETED_SLOTS_REMEMBERED: usize = 1024;
+
+/// Options for [`BlockAssembler`].
+#[derive(Debug, Clone)]
+pub struct BlockAssemblyOptions {
+    /// How long a slot may stay incomplete after its first update before it is
+    /// reported as [`IncompleteBlock`]. Default: 30s
+    pub timeout: Duration,
+    /// Collect account updates of the slot into `SubscribeUpdateBlock::accounts`.
+    /// Their completeness cannot be checked. Default: false
+    pub include_accounts: bool,
+}
+
+impl Default for BlockAssemblyOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            include_accounts: false,
+        }
+    }
+}
+
+impl BlockAssemblyOptions {
+    /// Sets the incomplete-block timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Collects account updates into the assembled blocks.
+    pub fn with_accounts(mut self, include: bool) -> Self {
+        self.include_accounts = include;
+        self
+    }
+}
+
+/// A slot that did not receive everything its block meta announced in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompleteBlock {
+    pub slot: u64,
+    /// Transactions received.
+    pub transactions: u64,
+    /// `executed_transaction_count` from the block meta, if it arrived.
+    pub expected_transactions: Option<u64>,
+    /// Entries received.
+    pub entries: u64,
+    /// `entries_count` from the block meta, if it arrived.
+    pub expected_entries: Option<u64>,
+}
+
+impl fmt::Display for IncompleteBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.expected_transactions, self.expected_entries) {
+            (Some(txs), Some(entries)) => write!(
+                f,
+                "slot {}: {}/{} transactions, {}/{} entries",
+                self.slot, self.transactions, txs, self.entries, entries
+            ),
+            _ => write!(
+                f,
+                "slot {}: no block meta ({} transactions, {} entries)",
+                self.slot, self.transactions, self.entries
+            ),
+        }
+    }
+}
+
+/// Subscription for everything [`BlockAssembler`] needs: all transactions (votes and
+/// failed included, since the meta counts them), entries and block metas.
+///
+/// Add account filters separately if blocks should carry accounts.
+pub fn block_request(commitment: CommitmentLevel) -> SubscribeRequest {
+    let mut request = SubscribeRequest {
+        commitment: Some(commitment as i32),
+        ..Default::default()
+    };
+    request.transactions.insert(
+        BLOCK_ASSEMBLY_FILTER.to_string(),
+        SubscribeRequestFilterTransactions::default(),
+    );
+    request
+        .entry
+        .insert(BLOCK_ASSEMBLY_FILTER.to_string(), SubscribeRequestFilterEntry {});
+    request
+        .blocks_meta
+        .insert(BLOCK_ASSEMBLY_FILTER.to_string(), SubscribeRequestFilterBlocksMeta {});
+    request
+}
+
+struct PendingBlock {
+    first_seen: Instant,
+    meta: Option<SubscribeUpdateBlockMeta>,
+    transactions: BTreeMap<u64, SubscribeUpdateTransactionInfo>,
+    entries: BTreeMap<u64, SubscribeUpdateEntry>,
+    accounts: BTreeMap<(Vec<u8>, u64), SubscribeUpdateAccountInfo>,
+}
+
+impl PendingBlock {
+    fn new(first_seen: Instant) -> Self {
+        Self {
+            first_seen,
+            meta: None,
+            transactions: BTreeMap::new(),
+            entries: BTreeMap::new(),
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.meta.as_ref().is_some_and(|meta| {
+            self.transactions.len() as u64 == meta.executed_transaction_count
+                && self.entries.len() as u64 == meta.entries_count
+        })
+    }
+
+    fn incomplete(&self, slot: u64) -> IncompleteBlock {
+        IncompleteBlock {
+            slot,
+            transactions: self.transactions.len() as u64,
+            expected_transactions: self.meta.as_ref().map(|m| m.executed_transaction_count),
+            entries: self.entries.len() as u64,
+            expected_entries: self.meta.as_ref().map(|m| m.entries_count),
+        }
+    }
+
+    fn into_block(self) -> SubscribeUpdateBlock {
+        let meta = self.meta.unwrap_or_default();
+        let accounts: Vec<_> = self.accounts.into_values().collect();
+        SubscribeUpdateBlock {
+            slot: meta.slot,
+            blockhash: meta.blockhash,
+            rewards: meta.rewards,
+            block_time: meta.block_time,
+            block_height: meta.block_height,
+            parent_slot: meta.parent_slot,
+            parent_blockhash: meta.parent_blockhash,
+            executed_transaction_count: meta.executed_transaction_count,
+            transactions: self.transactions.into_values().collect(),
+            updated_account_count: accounts.len() as u64,
+            accounts,
+            entries_count: meta.entries_count,
+            entries: self.entries.into_values().collect(),
+        }
+    }
+}
+
+/// Rebuilds complete blocks from streamed transactions, entries and block metas.
+///
+/// Transactions and entries are keyed by their index within the block, so duplicates
+/// replayed after a reconnect are absorbed. A block is emitted as soon as the number of
+/// transactions and entries match the meta's `executed_transaction_count` and
+/// `entries_count`; slots that are still short after the timeout are reported through
+/// [`expire`](Self::expire).
+pub struct BlockAssembler {
+    options: BlockAssemblyOptions,
+    pending: BTreeMap<u64, PendingBlock>,
+    completed: BTreeSet<u64>,
+}
+
+impl Default for BlockAssembler {
+    fn default() -> Self {
+        Self::new(BlockAssemblyOptions::default())
+    }
+}
+
+impl BlockAssembler {
+    pub fn new(options: BlockAssemblyOptions) -> Self {
+        Self {
+            options,
+            pending: BTreeMap::new(),
+            completed: BTreeSet::new(),
+        }
+    }
+
+    /// Number of slots currently being assembled.
+    pub fn pending_slots(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Feeds one update; returns the block if it completed the slot.
+    pub fn push(&mut self, update: &SubscribeUpdate) -> Option<SubscribeUpdateBlock> {
+        self.push_at(update, Instant::now())
+    }
+
+    /// Like [`push`](Self::push) with an explicit arrival time.
+    pub fn push_at(&mut self, update: &SubscribeUpdate, now: Instant) -> Option<SubscribeUpdateBlock> {
+        let slot = match update.update_oneof.as_ref()? {
+            UpdateOneof::Transaction(tx) => {
+                let info = tx.transaction.as_ref()?;
+                self.entry(tx.slot, now)?
+                    .transactions
+                    .insert(info.index, info.clone());
+                tx.slot
+            }
+            UpdateOneof::Entry(entry) => {
+                self.entry(entry.slot, now)?
+                    .entries
+                    .insert(entry.index, entry.clone());
+                entry.slot
+            }
+            UpdateOneof::BlockMeta(meta) => {
+                self.entry(meta.slot, now)?.meta = Some(meta.clone());
+                meta.slot
+            }
+            UpdateOneof::Account(account) if self.options.include_accounts => {
+                let info = account.account.as_ref()?;
+                self.entry(account.slot, now)?
+                    .accounts
+                    .insert((info.pubkey.clone(), info.write_version), info.clone());
+                account.slot
+            }
+            _ => return None,
+        };
+
+        if !self.pending.get(&slot)?.is_complete() {
+            return None;
+        }
+        let block = self.pending.remove(&slot)?;
+        self.mark_done(slot);
+        Some(block.into_block())
+    }
+
+    /// Removes and reports slots that have been incomplete for longer than the timeout.
+    pub fn expire(&mut self, now: Instant) -> Vec<IncompleteBlock> {
+        let timeout = self.options.timeout;
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, block)| now.saturating_duration_since(block.first_seen) >= timeout)
+            .map(|(slot, _)| *slot)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|slot| {
+                let block = self.pending.remove(&slot)?;
+                self.mark_done(slot);
+                Some(block.incomplete(slot))
+            })
+            .collect()
+    }
+
+    /// Removes and reports every slot still being assembled, e.g. when the stream
+    /// feeding the assembler has ended.
+    pub fn flush(&mut self) -> Vec<IncompleteBlock> {
+        let pending = std::mem::take(&mut self.pending);
+        pending
+            .into_iter()
+            .map(|(slot, block)| {
+                self.mark_done(slot);
+                block.incomplete(slot)
+            })
+            .collect()
+    }
+
+    fn entry(&mut self, slot: u64, now: Instant) -> Option<&mut PendingBlock> {
+        if self.completed.contains(&slot) {
+            return None;
+        }
+        Some(self.pending.entry(slot).or_insert_with(|| PendingBlock::new(now)))
+    }
+
+    fn mark_done(&mut self, slot: u64) {
+        self.completed.insert(slot);
+        while self.completed.len() > COMPLETED_SLOTS_REMEMBERED {
+            self.completed.pop_first();
+        }
+    }
+}
+
+/// Assembles blocks from a `subscribe` stream, typically opened with
+/// [`block_request`]. Incomplete slots are yielded as
+/// [`LaserstreamError::IncompleteBlock`], including the slots still pending when the
+/// stream ends; other updates are dropped.
+pub fn assemble_blocks<S>(
+    stream: S,
+    options: BlockAssemblyOptions,
+) -> impl Stream<Item = Result<SubscribeUpdateBlock, LaserstreamError>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+{
+    stream! {
+        let check_every = (options.timeout / 4).max(Duration::from_millis(100));
+        let mut assembler = BlockAssembler::new(options);
+        let mut ticker = tokio::time::interval(check_every);
+        futures::pin_mut!(stream);
+        loop {
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(Ok(update)) => {
+                        if let Some(block) = assembler.push(&update) {
+                            yield Ok(block);
+                        }
+                    }
+                    Some(Err(e)) => yield Err(e),
+                    None => {
+                        for incomplete in assembler.flush() {
+                            yield Err(LaserstreamError::IncompleteBlock(incomplete));
+                        }
+                        break;
+                    }
+                },
+                _ = ticker.tick() => {
+                    for incomplete in assembler.expire(Instant::now()) {
+                        yield Err(LaserstreamError::IncompleteBlock(incomplete));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+//! TCP chaos proxy for resilience tests.
+//!
+//! [`ChaosProxy`] listens locally and forwards each connection to a plaintext
+//! (h2c) upstream, such as `testing::MockGeyser` or a plaintext
+//! LaserStream edge, while injecting faults: latency with jitter, a bandwidth cap,
+//! and [`Fault`]s fired on a per-connection [`Schedule`] or on demand with
+//! [`ChaosProxy::inject`]. Global online/offline windows, as in the TypeScript
+//! proxy, come from [`ChaosOptions::with_outages`].
+//!
+//! The server-to-client direction is followed frame by frame, so a cut lands inside
+//! an HTTP/2 frame and a GOAWAY is inserted between frames. All randomness comes
+//! from [`ChaosOptions::seed`]: connection `n` of two runs with the same seed
+//! follows the same [`plan`](ChaosOptions::plan).
+
+use crate::LaserstreamError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{sleep, sleep_until, Instant};
+use tracing::{info, warn};
+
+/// Bytes read from a socket at a time.
+const READ_BUFFER: usize = 64 * 1024;
+/// Chunks in flight per direction before reading pauses.
+const CHUNK_BUFFER: usize = 64;
+/// HTTP/2 frame header length.
+const FRAME_HEADER_LEN: usize = 9;
+/// HTTP/2 GOAWAY frame type.
+const GOAWAY: u8 = 0x7;
+/// Golden-ratio increment spreading connection indexes over seeds.
+const SEED_STEP: u64 = 0x9E37_79B9_7F4A_7C15;
+/// Shortest gap between repeats of a fault and between outage switches, so an
+/// empty range can't spin.
+const MIN_WINDOW: Duration = Duration::from_millis(10);
+
+/// A fault applied to one connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Close both sides at once.
+    Drop,
+    /// Forward part of the next server frame, then close: the client sees a
+    /// truncated frame. Waits for the server to send something.
+    Cut,
+    /// Send the client an HTTP/2 GOAWAY (`NO_ERROR`, last stream = newest open
+    /// stream) between frames, then close after `grace`, like a draining server.
+    GoAway { grace: Duration },
+    /// Stop forwarding in both directions for the duration, keeping the
+    /// connection open.
+    Stall(Duration),
+}
+
+/// When a [`Fault`] fires, measured from the moment a connection opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Once.
+    After(Duration),
+    /// Repeatedly, each time after a delay drawn uniformly from the range, and at
+    /// least 10ms.
+    Every(RangeInclusive<Duration>),
+}
+
+/// Online/offline windows, each at least 10ms long. Going offline drops every
+/// connection; connections made while offline are closed at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outages {
+    pub up: RangeInclusive<Duration>,
+    pub down: RangeInclusive<Duration>,
+}
+
+/// Options for [`ChaosProxy`].
+#[derive(Debug, Clone, Default)]
+pub struct ChaosOptions {
+    /// Seed for every random choice.
+    pub seed: u64,
+    /// Delay added to every chunk, in each direction.
+    pub latency: Duration,
+    /// Extra delay drawn from `0..=jitter` per chunk; order is preserved.
+    pub jitter: Duration,
+    /// Bytes per second per direction; `None` is unlimited.
+    pub bandwidth: Option<u64>,
+    /// Faults of every connection.
+    pub faults: Vec<(Schedule, Fault)>,
+    pub outages: Option<Outages>,
+}
+
+impl ChaosOptions {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    pub fn with_fault(mut self, schedule: Schedule, fault: Fault) -> Self {
+        self.faults.push((schedule, fault));
+        self
+    }
+
+    pub fn with_outages(
+        mut self,
+        up: RangeInclusive<Duration>,
+        down: RangeInclusive<Duration>,
+    ) -> Self {
+        self.outages = Some(Outages { up, down });
+        self
+    }
+
+    /// The faults connection `connection` (0 for the first accepted) will see, as
+    /// offsets from its start, in firing order. Endless if any schedule repeats.
+    pub fn plan(&self, connection: u64) -> impl Iterator<Item = (Duration, Fault)> {
+        let mut rng = connection_rng(self.seed, connection, 0);
+        let mut next: Vec<Option<Duration>> = self
+            .faults
+            .iter()
+            .map(|(schedule, _)| Some(first_delay(schedule, &mut rng)))
+            .collect();
+        let faults = self.faults.clone();
+        std::iter::from_fn(move || {
+            let (index, at) = next
+                .iter()
+                .enumerate()
+                .filter_map(|(index, at)| Some((index, (*at)?)))
+                .min_by_key(|(_, at)| *at)?;
+            let (schedule, fault) = &faults[index];
+            next[index] = match schedule {
+                Schedule::After(_) => None,
+                Schedule::Every(range) => Some(at + window(range, &mut rng)),
+            };
+            Some((at, *fault))
+        })
+    }
+}
+
+fn connection_rng(seed: u64, connection: u64, stream: u64) -> StdRng {
+    StdRng::seed_from_u64(
+        seed ^ connection.wrapping_add(1).wrapping_mul(SEED_STEP) ^ stream.rotate_left(32),
+    )
+}
+
+fn first_delay(schedule: &Schedule, rng: &mut StdRng) -> Duration {
+    match schedule {
+        Schedule::After(delay) => *delay,
+        Schedule::Every(range) => window(range, rng),
+    }
+}
+
+fn sample(range: &RangeInclusive<Duration>, rng: &mut StdRng) -> Duration {
+    let (start, end) = (range.start().as_nanos(), range.end().as_nanos());
+    if start >= end {
+        return *range.start();
+    }
+    Duration::from_nanos(rng.gen_range(start as u64..=end as u64))
+}
+
+/// [`sample`], but never shorter than [`MIN_WINDOW`].
+fn window(range: &RangeInclusive<Duration>, rng: &mut StdRng) -> Duration {
+    sample(range, rng).max(MIN_WINDOW)
+}
+
+async fn sleep_until_some(at: Option<Instant>) {
+    match at {
+        Some(at) => sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A running proxy, stopped (with every connection dropped) when dropped.
+#[derive(Debug)]
+pub struct ChaosProxy {
+    addr: SocketAddr,
+    control: broadcast::Sender<Fault>,
+    connections: Arc<AtomicUsize>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl ChaosProxy {
+    /// Listens on `listen` (e.g. `127.0.0.1:0`) and forwards to `upstream`
+    /// (`host:port`).
+    pub async fn start(
+        listen: &str,
+        upstream: impl Into<String>,
+        options: ChaosOptions,
+    ) -> Result<Self, LaserstreamError> {
+        let listener = TcpListener::bind(listen)
+            .await
+            .map_err(|e| LaserstreamError::ConnectionError(e.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| LaserstreamError::ConnectionError(e.to_string()))?;
+        let (control, _) = broadcast::channel(16);
+        let connections = Arc::new(AtomicUsize::new(0));
+        let (shutdown, stopped) = oneshot::channel();
+        tokio::spawn(serve(
+            listener,
+            upstream.into(),
+            Arc::new(options),
+            control.clone(),
+            connections.clone(),
+            stopped,
+        ));
+        Ok(Self {
+            addr,
+            control,
+            connections,
+            _shutdown: shutdown,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `http://` URL to point a client at.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Connections accepted so far, including ones refused while offline.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// Applies `fault` to every open connection now.
+    pub fn inject(&self, fault: Fault) {
+        let _ = self.control.send(fault);
+    }
+}
+
+async fn serve(
+    listener: TcpListener,
+    upstream: String,
+    options: Arc<ChaosOptions>,
+    control: broadcast::Sender<Fault>,
+    connections: Arc<AtomicUsize>,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut online = true;
+    let mut flip_at = options
+        .outages
+        .as_ref()
+        .map(|outages| Instant::now() + window(&outages.up, &mut rng));
+    loop {
+        tokio::select! {
+            _ = &mut stopped => {
+                let _ = control.send(Fault::Drop);
+                return;
+            }
+            _ = sleep_until_some(flip_at) => {
+                let Some(outages) = &options.outages else { continue };
+                online = !online;
+                info!(online, "Chaos proxy switched");
+                if !online {
+                    let _ = control.send(Fault::Drop);
+                }
+                let range = if online { &outages.up } else { &outages.down };
+                flip_at = Some(Instant::now() + window(range, &mut rng));
+            }
+            accepted = listener.accept() => {
+                let (client, _) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!(error = %e, "Chaos proxy accept failed");
+                        continue;
+                    }
+                };
+                let index = connections.fetch_add(1, Ordering::Relaxed) as u64;
+                if online {
+                    tokio::spawn(proxy_connection(
+                        client,
+                        upstream.clone(),
+                        index,
+                        options.clone(),
+                        control.subscribe(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Instructions for one direction of a connection.
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    Stall(Duration),
+    Cut,
+    GoAway,
+}
+
+/// How a direction stopped.
+#[derive(Debug, PartialEq, Eq)]
+enum Finish {
+    /// The reader reached end of stream and everything was written.
+    Eof,
+    /// A [`Fault::Cut`] was carried out.
+    Cut,
+}
+
+async fn proxy_connection(
+    client: TcpStream,
+    upstream: String,
+    index: u64,
+    options: Arc<ChaosOptions>,
+    mut control: broadcast::Receiver<Fault>,
+) {
+    let upstream = match TcpStream::connect(&upstream).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            warn!(error = %e, %upstream, "Chaos proxy could not reach upstream");
+            return;
+        }
+    };
+    let _ = client.set_nodelay(true);
+    let _ = upstream.set_nodelay(true);
+    let (client_read, client_write) = client.into_split();
+    let (upstream_read, upstream_write) = upstream.into_split();
+
+    let shaping = |stream| Shaping {
+        latency: options.latency,
+        jitter: options.jitter,
+        bandwidth: options.bandwidth,
+        rng: connection_rng(options.seed, index, stream),
+    };
+    let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+    let (responses_tx, responses_rx) = mpsc::unbounded_channel();
+    let mut requests = tokio::spawn(pump(
+        client_read,
+        upstream_write,
+        shaping(1),
+        None,
+        requests_rx,
+    ));
+    let mut responses = tokio::spawn(pump(
+        upstream_read,
+        client_write,
+        shaping(2),
+        Some(FrameTracker::default()),
+        responses_rx,
+    ));
+
+    let started = Instant::now();
+    let mut plan = options.plan(index).peekable();
+    let mut close_at: Option<Instant> = None;
+    let (mut requests_done, mut responses_done) = (false, false);
+    while !(requests_done && responses_done) {
+        let next_fault = plan.peek().map(|(at, _)| started + *at);
+        let fault = tokio::select! {
+            finished = &mut requests, if !requests_done => {
+                requests_done = true;
+                if !matches!(finished, Ok(Ok(Finish::Eof))) {
+                    break;
+                }
+                continue;
+            }
+            finished = &mut responses, if !responses_done => {
+                responses_done = true;
+                if !matches!(finished, Ok(Ok(Finish::Eof))) {
+                    break;
+                }
+                continue;
+            }
+            _ = sleep_until_some(close_at) => break,
+            _ = sleep_until_some(next_fault) => match plan.next() {
+                Some((_, fault)) => fault,
+                None => continue,
+            },
+            fault = control.recv() => match fault {
+                Ok(fault) => fault,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        match fault {
+            Fault::Drop => break,
+            Fault::Cut => {
+                let _ = responses_tx.send(Command::Cut);
+            }
+            Fault::GoAway { grace } => {
+                let _ = responses_tx.send(Command::GoAway);
+                close_at = Some(
+                    close_at.map_or(Instant::now() + grace, |at| at.min(Instant::now() + grace)),
+                );
+            }
+            Fault::Stall(duration) => {
+                let _ = requests_tx.send(Command::Stall(duration));
+                let _ = responses_tx.send(Command::Stall(duration));
+            }
+        }
+    }
+    requests.abort();
+    responses.abort();
+}
+
+/// Delays and rate limit of one direction.
+struct Shaping {
+    latency: Duration,
+    jitter: Duration,
+    bandwidth: Option<u64>,
+    rng: StdRng,
+}
+
+impl Shaping {
+    fn delay(&mut self) -> Duration {
+        self.latency + sample(&(Duration::ZERO..=self.jitter), &mut self.rng)
+    }
+}
+
+/// Forwards `reader` to `writer`, applying shaping and commands.
+async fn pump(
+    mut reader: OwnedReadHalf,
+    writer: OwnedWriteHalf,
+    mut shaping: Shaping,
+    frames: Option<FrameTracker>,
+    commands: mpsc::UnboundedReceiver<Command>,
+) -> io::Result<Finish> {
+    let (chunks_tx, chunks_rx) = mpsc::channel::<(Instant, Vec<u8>)>(CHUNK_BUFFER);
+    let bandwidth = shaping.bandwidth;
+    let read = async move {
+        let mut buffer = vec![0u8; READ_BUFFER];
+        let mut last = Instant::now();
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                return Ok::<_, io::Error>(());
+            }
+            // Never before an earlier chunk, so jitter keeps the byte order.
+            last = last.max(Instant::now() + shaping.delay());
+            if chunks_tx
+                .send((last, buffer[..read].to_vec()))
+                .await
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    };
+    let write = write_chunks(chunks_rx, commands, writer, bandwidth, frames);
+    tokio::pin!(read, write);
+    let mut reading = true;
+    loop {
+        tokio::select! {
+            read = &mut read, if reading => {
+                reading = false;
+                read?;
+            }
+            written = &mut write => return written,
+        }
+    }
+}
+
+async fn write_chunks(
+    mut chunks: mpsc::Receiver<(Instant, Vec<u8>)>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut writer: OwnedWriteHalf,
+    bandwidth: Option<u64>,
+    mut frames: Option<FrameTracker>,
+) -> io::Result<Finish> {
+    // A cut or GOAWAY waiting for the next frame position.
+    let mut pending: Option<Command> = None;
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Stall(duration)) => sleep(duration).await,
+                Some(Command::GoAway) if frames.as_ref().is_some_and(FrameTracker::at_boundary) => {
+                    let goaway = frames.as_ref().map(FrameTracker::goaway).unwrap_or_default();
+                    writer.write_all(&goaway).await?;
+                }
+                Some(command) => pending = Some(command),
+                None => {}
+            },
+            chunk = chunks.recv() => {
+                let Some((deliver_at, data)) = chunk else {
+                    writer.shutdown().await?;
+                    return Ok(Finish::Eof);
+                };
+                sleep_until(deliver_at).await;
+                let Some(tracker) = frames.as_mut() else {
+                    writer.write_all(&data).await?;
+                    throttle(bandwidth, data.len()).await;
+                    continue;
+                };
+                let boundaries = tracker.advance(&data);
+                match pending {
+                    Some(Command::Cut) => {
+                        // Stop after a prefix that ends inside a frame.
+                        let inside = (1..data.len()).find(|position| !boundaries.contains(position));
+                        if let Some(position) = inside {
+                            writer.write_all(&data[..position]).await?;
+                            writer.flush().await?;
+                            return Ok(Finish::Cut);
+                        }
+                        writer.write_all(&data).await?;
+                    }
+                    Some(Command::GoAway) => {
+                        let at = boundaries
+                            .iter()
+                            .copied()
+                            .find(|position| *position > 0)
+                            .or(tracker.at_boundary().then_some(data.len()));
+                        match at {
+                            Some(at) => {
+                                writer.write_all(&data[..at]).await?;
+                                writer.write_all(&tracker.goaway()).await?;
+                                writer.write_all(&data[at..]).await?;
+                                pending = None;
+                            }
+                            None => writer.write_all(&data).await?,
+                        }
+                    }
+                    _ => writer.write_all(&data).await?,
+                }
+                throttle(bandwidth, data.len()).await;
+            }
+        }
+    }
+}
+
+async fn throttle(bandwidth: Option<u64>, bytes: usize) {
+    if let Some(rate) = bandwidth.filter(|rate| *rate > 0) {
+        sleep(Duration::from_secs_f64(bytes as f64 / rate as f64)).await;
+    }
+}
+
+/// Follows HTTP/2 frame boundaries in a byte stream without buffering it.
+#[derive(Debug, Default)]
+struct FrameTracker {
+    header: Vec<u8>,
+    remaining: usize,
+    last_stream_id: u32,
+}
+
+impl FrameTracker {
+    fn at_boundary(&self) -> bool {
+        self.header.is_empty() && self.remaining == 0
+    }
+
+    /// Moves past `data`; returns the offsets in it where frames start.
+    fn advance(&mut self, data: &[u8]) -> Vec<usize> {
+        let mut boundaries = Vec::new();
+        let mut position = 0;
+        while position < data.len() {
+            if self.remaining > 0 {
+                let skip = self.remaining.min(data.len() - position);
+                self.remaining -= skip;
+                position += skip;
+                continue;
+            }
+            if self.header.is_empty() {
+                boundaries.push(position);
+            }
+            let take = (FRAME_HEADER_LEN - self.header.len()).min(data.len() - position);
+            self.header
+                .extend_from_slice(&data[position..position + take]);
+            position += take;
+            if self.header.len() == FRAME_HEADER_LEN {
+                let header = std::mem::take(&mut self.header);
+                self.remaining = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+                let stream_id =
+                    u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7FFF_FFFF;
+                self.last_stream_id = self.last_stream_id.max(stream_id);
+            }
+        }
+        boundaries
+    }
+
+    /// A GOAWAY frame letting the streams seen so far finish.
+    fn goaway(&self) -> Vec<u8> {
+        let mut frame = vec![0, 0, 8, GOAWAY, 0, 0, 0, 0, 0];
+        frame.extend_from_slice(&self.last_stream_id.to_be_bytes());
+        frame.extend_from_slice(&0u32.to_be_bytes());
+        frame
+    }
+}
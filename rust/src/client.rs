@@ -3,20 +3,29 @@ use async_stream::stream;
 use futures::StreamExt;
 use futures_channel::mpsc as futures_mpsc;
 use futures_util::{sink::SinkExt, Stream};
-use std::{pin::Pin, time::Duration};
+use crate::accounts::{AccountReceiver, AccountSubscription};
+use crate::signatures::{SignatureStatus, SignatureSubscription};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 use laserstream_core_proto::tonic::{
-    Status, Request, metadata::MetadataValue, transport::Endpoint, codec::CompressionEncoding,
+    Status, Request, metadata::MetadataValue, transport::{Channel, Endpoint},
+    codec::CompressionEncoding, service::interceptor::InterceptedService,
 };
 use tracing::{error, instrument, warn};
 use uuid;
 use laserstream_core_client::{ClientTlsConfig, Interceptor};
 use laserstream_core_proto::prelude::{geyser_client::GeyserClient};
 use laserstream_core_proto::geyser::{
-    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterSlots,
-    SubscribeRequestPing, SubscribeUpdate,
-    SubscribePreprocessedRequest, SubscribePreprocessedUpdate,
+    subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus, SubscribeRequest,
+    SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeUpdate, SubscribeUpdateSlot,
+    SubscribePreprocessedRequest, SubscribePreprocessedUpdate, GetLatestBlockhashRequest,
+    GetLatestBlockhashResponse,
 };
 
 const HARD_CAP_RECONNECT_ATTEMPTS: u32 = (20 * 60) / 5; // 20 mins / 5 sec interval
@@ -65,14 +74,46 @@ pub struct StreamHandle {
 }
 
 impl StreamHandle {
+    /// A handle for streams driven by something other than a connection; requests
+    /// written to it arrive on the returned receiver.
+    pub(crate) fn channel() -> (Self, mpsc::UnboundedReceiver<SubscribeRequest>) {
+        let (write_tx, write_rx) = mpsc::unbounded_channel();
+        (Self { write_tx }, write_rx)
+    }
+
     /// Send a new subscription request to update the active subscription.
     pub async fn write(&self, request: SubscribeRequest) -> Result<(), LaserstreamError> {
+        self.send(request)
+    }
+
+    /// Synchronous [`write`](Self::write), for callers that must order writes while
+    /// holding a lock.
+    pub(crate) fn send(&self, request: SubscribeRequest) -> Result<(), LaserstreamError> {
         self.write_tx
             .send(request)
             .map_err(|_| LaserstreamError::ConnectionError("Write channel closed".to_string()))
     }
 }
 
+/// Item produced by [`subscribe_with_mode`].
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum StreamItem {
+    /// An update for the consumer, with internal filter names removed.
+    Update(SubscribeUpdate),
+    /// A slot update from the internal slot tracker (only in [`SlotTrackingMode::AllStatuses`]).
+    InternalSlot(SubscribeUpdateSlot),
+}
+
+/// How the internal slot subscription is set up.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SlotTrackingMode {
+    /// Slot updates at the request's commitment, used only for replay.
+    Replay,
+    /// PROCESSED data plus every slot status, surfaced as [`StreamItem::InternalSlot`]
+    /// so callers can follow each slot to CONFIRMED and FINALIZED.
+    AllStatuses,
+}
+
 /// Establishes a gRPC connection, handles the subscription lifecycle,
 /// and provides a stream of updates. Automatically reconnects on failure.
 #[instrument(skip(config, request))]
@@ -82,6 +123,25 @@ pub fn subscribe(
 ) -> (
     impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
     StreamHandle,
+) {
+    let (stream, handle) = subscribe_with_mode(config, request, SlotTrackingMode::Replay);
+    let update_stream = stream.filter_map(|item| {
+        futures::future::ready(match item {
+            Ok(StreamItem::Update(update)) => Some(Ok(update)),
+            Ok(StreamItem::InternalSlot(_)) => None,
+            Err(e) => Some(Err(e)),
+        })
+    });
+    (update_stream, handle)
+}
+
+pub(crate) fn subscribe_with_mode(
+    config: LaserstreamConfig,
+    request: SubscribeRequest,
+    mode: SlotTrackingMode,
+) -> (
+    impl Stream<Item = Result<StreamItem, LaserstreamError>>,
+    StreamHandle,
 ) {
     let (write_tx, mut write_rx) = mpsc::unbounded_channel::<SubscribeRequest>();
     let handle = StreamHandle { write_tx };
@@ -101,9 +161,21 @@ pub fn subscribe(
         
         // Get replay behavior from config
         let replay_enabled = config.replay;
+        let all_statuses = mode == SlotTrackingMode::AllStatuses;
+        let internal_tracker = replay_enabled || all_statuses;
         
-        // Add internal slot subscription only when replay is enabled
-        if replay_enabled {
+        // Add internal slot subscription when replay is enabled or slot statuses are surfaced
+        if all_statuses {
+            // Data at PROCESSED; the tracker delivers every later status of each slot
+            current_request.commitment = Some(CommitmentLevel::Processed as i32);
+            current_request.slots.insert(
+                internal_slot_sub_id.clone(),
+                SubscribeRequestFilterSlots {
+                    filter_by_commitment: Some(false),
+                    interslot_updates: Some(true),
+                }
+            );
+        } else if replay_enabled {
             current_request.slots.insert(
                 internal_slot_sub_id.clone(),
                 SubscribeRequestFilterSlots {
@@ -125,6 +197,9 @@ pub fn subscribe(
             // This ensures writes sent while disconnected are included in the next connection.
             while let Ok(write_request) = write_rx.try_recv() {
                 merge_subscribe_requests(&mut current_request, &write_request, &internal_slot_sub_id);
+                if all_statuses {
+                    current_request.commitment = Some(CommitmentLevel::Processed as i32);
+                }
             }
 
             // Always update from_slot on current_request based on tracked_slot.
@@ -193,9 +268,14 @@ pub fn subscribe(
 
                                 // Track the latest slot from any slot update (including internal subscription)
                                 if let Some(UpdateOneof::Slot(s)) = &update.update_oneof {
-                                    if replay_enabled {
+                                    // With every status subscribed, only PROCESSED moves the replay point forward
+                                    if replay_enabled && (!all_statuses || s.status == SlotStatus::SlotProcessed as i32) {
                                         tracked_slot = s.slot;
                                     }
+
+                                    if all_statuses && update.filters.contains(&internal_slot_sub_id) {
+                                        yield Ok(StreamItem::InternalSlot(s.clone()));
+                                    }
                                     
                                     // Skip if this slot update is EXCLUSIVELY from our internal subscription
                                     if update.filters.len() == 1 && update.filters.contains(&internal_slot_sub_id) {
@@ -203,18 +283,18 @@ pub fn subscribe(
                                     }
                                 }
 
-                                            // Filter out internal subscription from filters before yielding (only if replay is enabled)
+                                            // Filter out internal subscription from filters before yielding (only if the tracker is active)
                                             let mut clean_update = update;
-                                            if replay_enabled {
+                                            if internal_tracker {
                                                 clean_update.filters.retain(|f| f != &internal_slot_sub_id);
                                                 
                                                 // Only yield if there are still filters after cleaning
                                                 if !clean_update.filters.is_empty() {
-                                                    yield Ok(clean_update);
+                                                    yield Ok(StreamItem::Update(clean_update));
                                                 }
                                             } else {
                                                 // When replay is disabled, yield all updates as-is
-                                                yield Ok(clean_update);
+                                                yield Ok(StreamItem::Update(clean_update));
                                             }
                                         }
                                         Err(status) => {
@@ -234,6 +314,9 @@ pub fn subscribe(
                             Some(write_request) = write_rx.recv() => {
                                 // Merge the write_request into current_request so it persists across reconnections
                                 merge_subscribe_requests(&mut current_request, &write_request, &internal_slot_sub_id);
+                                if all_statuses {
+                                    current_request.commitment = Some(CommitmentLevel::Processed as i32);
+                                }
 
                                 // Send the merged current_request (which preserves the internal slot
                                 // tracker) instead of the raw write_request. Yellowstone gRPC replaces
@@ -291,6 +374,42 @@ async fn connect_and_subscribe_once(
     ),
     Status,
 > {
+    let mut geyser_client = connect(config, api_key).await?;
+
+    // Create bidirectional stream
+    let (mut subscribe_tx, subscribe_rx) = futures_mpsc::unbounded();
+    subscribe_tx
+        .send(request)
+        .await
+        .map_err(|e| Status::internal(format!("Failed to send initial request: {}", e)))?;
+
+    let response = geyser_client
+        .subscribe(subscribe_rx)
+        .await
+        .map_err(|e| Status::internal(format!("Subscription failed: {}", e)))?;
+
+    Ok((subscribe_tx, response.into_inner()))
+}
+
+/// Fetches the latest blockhash through the unary `GetLatestBlockhash` call.
+pub(crate) async fn get_latest_blockhash(
+    config: &LaserstreamConfig,
+    commitment: CommitmentLevel,
+) -> Result<GetLatestBlockhashResponse, LaserstreamError> {
+    let mut geyser_client = connect(config, config.api_key.clone()).await?;
+    let response = geyser_client
+        .get_latest_blockhash(GetLatestBlockhashRequest {
+            commitment: Some(commitment as i32),
+        })
+        .await?;
+    Ok(response.into_inner())
+}
+
+/// Connects a Geyser client with the channel options of `config`.
+async fn connect(
+    config: &LaserstreamConfig,
+    api_key: String,
+) -> Result<GeyserClient<InterceptedService<Channel, SdkMetadataInterceptor>>, Status> {
     let options = &config.channel_options;
 
     // Create our custom interceptor with SDK metadata
@@ -353,19 +472,7 @@ async fn connect_and_subscribe_once(
         }
     }
 
-    // Create bidirectional stream
-    let (mut subscribe_tx, subscribe_rx) = futures_mpsc::unbounded();
-    subscribe_tx
-        .send(request)
-        .await
-        .map_err(|e| Status::internal(format!("Failed to send initial request: {}", e)))?;
-
-    let response = geyser_client
-        .subscribe(subscribe_rx)
-        .await
-        .map_err(|e| Status::internal(format!("Subscription failed: {}", e)))?;
-
-    Ok((subscribe_tx, response.into_inner()))
+    Ok(geyser_client)
 }
 
 /// Handle for managing a preprocessed subscription (no write support).
@@ -494,6 +601,128 @@ async fn connect_and_subscribe_preprocessed_once(
     Ok(response.into_inner())
 }
 
+/// Client that shares subscriptions between concurrent callers.
+///
+/// Cloning is cheap; clones share the same subscriptions, which are opened on first
+/// use and closed when the last clone is dropped.
+#[derive(Clone)]
+pub struct LaserstreamClient {
+    config: LaserstreamConfig,
+    watch_commitment: CommitmentLevel,
+    signatures: Arc<Mutex<Option<Arc<SignatureSubscription>>>>,
+    accounts: Arc<Mutex<Option<Arc<AccountSubscription>>>>,
+}
+
+impl LaserstreamClient {
+    pub fn new(config: LaserstreamConfig) -> Self {
+        Self {
+            config,
+            watch_commitment: CommitmentLevel::Confirmed,
+            signatures: Arc::new(Mutex::new(None)),
+            accounts: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Commitment of the account updates [`watch_account`](Self::watch_account)
+    /// publishes (default CONFIRMED). Takes effect if set before the first watch.
+    pub fn with_watch_commitment(mut self, commitment: CommitmentLevel) -> Self {
+        self.watch_commitment = commitment;
+        self
+    }
+
+    pub fn config(&self) -> &LaserstreamConfig {
+        &self.config
+    }
+
+    /// Waits until `signature` lands in a slot that reached `commitment`.
+    ///
+    /// All waits share one `transactions_status` subscription; the signature's filter
+    /// is added when this is called and removed when the returned future resolves or
+    /// is dropped. A signature that lands in a slot which is then orphaned keeps
+    /// waiting. Fails with [`LaserstreamError::Timeout`] if `timeout` elapses first.
+    ///
+    /// Only landings after the server applied the filter are seen, so call this
+    /// before sending the transaction and await it afterwards. For a transaction
+    /// that may already have landed, check its status over RPC as well.
+    ///
+    /// The signature is registered, and the shared subscription started, by this
+    /// call rather than on first poll, so it must be called from within a Tokio
+    /// runtime.
+    pub fn await_signature(
+        &self,
+        signature: &[u8; 64],
+        commitment: CommitmentLevel,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<SignatureStatus, LaserstreamError>> + Send + 'static {
+        let subscription = self.signature_subscription();
+        let (id, receiver) = subscription.register(signature, commitment);
+        let signature = *signature;
+        let registration = SignatureRegistration {
+            subscription,
+            signature,
+            id,
+        };
+        async move {
+            let result = tokio::time::timeout(timeout, receiver).await;
+            drop(registration);
+            match result {
+                Ok(Ok(status)) => Ok(status),
+                Ok(Err(_)) => Err(LaserstreamError::StreamEnded),
+                Err(_) => Err(LaserstreamError::Timeout(format!(
+                    "signature {} did not reach {} within {:?}",
+                    bs58::encode(signature).into_string(),
+                    commitment.as_str_name(),
+                    timeout
+                ))),
+            }
+        }
+    }
+
+    /// Returns a receiver that always holds the latest update of `pubkey`, `None`
+    /// until the first one arrives.
+    ///
+    /// All watched accounts share one `accounts` subscription. An account is added
+    /// to it on its first watch and removed once its last receiver is dropped.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn watch_account(&self, pubkey: &[u8; 32]) -> AccountReceiver {
+        let subscription = self
+            .accounts
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| {
+                Arc::new(AccountSubscription::start(
+                    self.config.clone(),
+                    self.watch_commitment,
+                ))
+            })
+            .clone();
+        subscription.watch(pubkey)
+    }
+
+    fn signature_subscription(&self) -> Arc<SignatureSubscription> {
+        self.signatures
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(SignatureSubscription::start(self.config.clone())))
+            .clone()
+    }
+}
+
+/// A waiter registered by [`LaserstreamClient::await_signature`]; cancelled when
+/// dropped, which removes the signature's filter once nobody else awaits it.
+struct SignatureRegistration {
+    subscription: Arc<SignatureSubscription>,
+    signature: [u8; 64],
+    id: u64,
+}
+
+impl Drop for SignatureRegistration {
+    fn drop(&mut self) {
+        self.subscription.cancel(&self.signature, self.id);
+    }
+}
+
 /// Merges a write request into the current stored request so that subscription
 /// changes made via `write()` persist across reconnections.
 ///
@@ -0,0 +1,421 @@
+//! Compressed account (cuckoo) filtering.
+//!
+//! Track large pubkey sets without re-uploading an explicit list every request:
+//! build a compact cuckoo filter (~3 bytes/account) and let the server match
+//! against it (no false negatives, <1% false positives — re-check locally with
+//! [`CompressedAccountFilterSet::contains`]).
+//!
+//! ```no_run
+//! use helius_laserstream::cuckoo::{CompressedAccountFilterSet, Pubkey};
+//! use helius_laserstream::grpc::SubscribeRequest;
+//! # fn tracked_pubkeys() -> Vec<Pubkey> { vec![] }
+//! # let new_pk = Pubkey::default();
+//! let mut set = CompressedAccountFilterSet::with_capacity(2_000_000).unwrap();
+//! for pk in tracked_pubkeys() { set.insert(pk).unwrap(); }
+//!
+//! let mut req = SubscribeRequest::default();
+//! set.insert_into_subscribe_request(&mut req, "tracked_accounts");
+//! // ... subscribe(req) ...
+//!
+//! // On change, mutate and re-send on the SAME stream:
+//! set.insert(new_pk).unwrap();
+//! if set.take_dirty() {
+//!     set.insert_into_subscribe_request(&mut req, "tracked_accounts");
+//!     // handle.write(req).await
+//! }
+//! ```
+//!
+//! Also works on transaction subscriptions: put `set.to_proto()` on
+//! `SubscribeRequestFilterTransactions.cuckoo_account_include` (see the
+//! `cuckoo_transaction_filter` example).
+//!
+//! For sets that change constantly, [`ManagedAccountFilter`] does the loop above
+//! for you: it batches changes into a debounced `write()`, drops server-side false
+//! positives, and grows the filter when it fills up.
+
+pub use laserstream_core_proto::cuckoo::{
+    CompressedAccountFilterSet, CuckooBuildError, CuckooFilter, DEFAULT_HASH_SEED,
+    TableFullError, YellowstoneHasherBuilder,
+};
+// Re-exported so callers use the exact Pubkey version the filter API expects.
+pub use solana_pubkey::Pubkey;
+
+use crate::{LaserstreamError, StreamHandle};
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeUpdate,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Options for [`ManagedAccountFilter`].
+#[derive(Debug, Clone)]
+pub struct ManagedFilterOptions {
+    /// Name of the filter in `SubscribeRequest.accounts`. Default: "tracked_accounts"
+    pub filter_name: String,
+    /// Number of accounts the filter is sized for up front. Default: 1_000_000
+    pub initial_capacity: usize,
+    /// How long changes are coalesced before a `write()` is sent. Default: 250ms
+    pub debounce: Duration,
+    /// Capacity multiplier applied when the filter is full. Default: 2
+    pub growth_factor: usize,
+}
+
+impl Default for ManagedFilterOptions {
+    fn default() -> Self {
+        Self {
+            filter_name: "tracked_accounts".to_string(),
+            initial_capacity: 1_000_000,
+            debounce: Duration::from_millis(250),
+            growth_factor: 2,
+        }
+    }
+}
+
+impl ManagedFilterOptions {
+    /// Sets the name the filter is registered under in the request.
+    pub fn with_filter_name(mut self, name: impl Into<String>) -> Self {
+        self.filter_name = name.into();
+        self
+    }
+
+    /// Sets the initial filter capacity.
+    pub fn with_initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = capacity;
+        self
+    }
+
+    /// Sets the debounce window for coalescing writes.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Sets the capacity multiplier used when the filter is rebuilt.
+    pub fn with_growth_factor(mut self, factor: usize) -> Self {
+        self.growth_factor = factor;
+        self
+    }
+}
+
+struct FilterState {
+    set: CompressedAccountFilterSet,
+    capacity: usize,
+    base_request: SubscribeRequest,
+    handle: Option<StreamHandle>,
+    /// A write failed; the current request is sent again on the next flush.
+    unsent: bool,
+}
+
+impl FilterState {
+    fn request(&self, filter_name: &str) -> SubscribeRequest {
+        let mut request = self.base_request.clone();
+        request
+            .accounts
+            .insert(filter_name.to_string(), self.set.to_account_filter());
+        request
+    }
+
+    /// The request to send if anything changed since the last write; clears the dirty flag.
+    fn take_pending(&mut self, filter_name: &str) -> Option<(StreamHandle, SubscribeRequest)> {
+        let handle = self.handle.clone()?;
+        let unsent = std::mem::take(&mut self.unsent);
+        if !self.set.take_dirty() && !unsent {
+            return None;
+        }
+        Some((handle, self.request(filter_name)))
+    }
+}
+
+/// A [`CompressedAccountFilterSet`] kept in sync with a live stream.
+///
+/// Inserts and removes are applied locally right away and coalesced into a single
+/// debounced `write()` on the bound [`StreamHandle`]. The filter is rebuilt with
+/// more capacity on [`TableFullError`], so callers never have to size it exactly.
+/// Cloning is cheap; all clones share the same set.
+///
+/// ```no_run
+/// use helius_laserstream::cuckoo::{ManagedAccountFilter, ManagedFilterOptions, Pubkey};
+/// use helius_laserstream::{grpc::SubscribeRequest, subscribe, LaserstreamConfig};
+/// # async fn run(config: LaserstreamConfig, wallets: Vec<Pubkey>) -> Result<(), Box<dyn std::error::Error>> {
+/// let filter = ManagedAccountFilter::new(SubscribeRequest::default(), ManagedFilterOptions::default())?;
+/// filter.insert_many(wallets)?;
+///
+/// let (stream, handle) = subscribe(config, filter.request());
+/// filter.bind(handle);
+/// // Drops server-side false positives before they reach the consumer.
+/// let stream = filter.filter_stream(stream);
+/// # Ok(()) }
+/// ```
+#[derive(Clone)]
+pub struct ManagedAccountFilter {
+    state: Arc<Mutex<FilterState>>,
+    options: Arc<ManagedFilterOptions>,
+    pending: Arc<AtomicBool>,
+    changed_tx: mpsc::UnboundedSender<()>,
+    changed_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<()>>>>,
+}
+
+impl ManagedAccountFilter {
+    /// Creates an empty managed filter. `base_request` carries every other filter of the
+    /// subscription; it is re-sent alongside the cuckoo filter on each write because the
+    /// server replaces, rather than merges, subscriptions.
+    pub fn new(
+        base_request: SubscribeRequest,
+        options: ManagedFilterOptions,
+    ) -> Result<Self, CuckooBuildError> {
+        let capacity = options.initial_capacity.max(1);
+        let set = CompressedAccountFilterSet::with_capacity(capacity)?;
+        let (changed_tx, changed_rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            state: Arc::new(Mutex::new(FilterState {
+                set,
+                capacity,
+                base_request,
+                handle: None,
+                unsent: false,
+            })),
+            options: Arc::new(options),
+            pending: Arc::new(AtomicBool::new(false)),
+            changed_tx,
+            changed_rx: Arc::new(Mutex::new(Some(changed_rx))),
+        })
+    }
+
+    /// Binds the filter to a live stream and starts the background writer.
+    /// Changes made before binding are sent with the first write.
+    ///
+    /// Must be called from within a Tokio runtime. Binding again replaces the handle,
+    /// and changes whose write failed on the old one are sent on the new one.
+    pub fn bind(&self, handle: StreamHandle) {
+        let unsent = {
+            let mut state = self.lock();
+            state.handle = Some(handle);
+            state.unsent
+        };
+        if unsent {
+            self.notify();
+        }
+
+        let Some(mut changed_rx) = self.changed_rx.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            // Writer already running; it picks up the new handle on its next flush.
+            return;
+        };
+        let state = Arc::downgrade(&self.state);
+        let options = self.options.clone();
+        let pending = self.pending.clone();
+        tokio::spawn(async move {
+            // Ends once every clone of the filter (and so every sender) is dropped.
+            while changed_rx.recv().await.is_some() {
+                // Changes made during the debounce window ride along with this write.
+                tokio::time::sleep(options.debounce).await;
+                pending.store(false, Ordering::Release);
+
+                let Some(state) = state.upgrade() else { return };
+                let pending = state
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .take_pending(&options.filter_name);
+                if let Some((handle, request)) = pending {
+                    if let Err(e) = handle.write(request).await {
+                        // Kept for the next change or `bind`; the writer stays up.
+                        warn!(error = %e, "Failed to write managed cuckoo filter");
+                        state.lock().unwrap_or_else(|e| e.into_inner()).unsent = true;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Adds an account. Returns `true` if it was not tracked yet.
+    ///
+    /// Grows the filter when it is full; only fails if the larger filter cannot be allocated.
+    pub fn insert(&self, key: Pubkey) -> Result<bool, CuckooBuildError> {
+        let inserted = {
+            let mut state = self.lock();
+            self.insert_locked(&mut state, key)?
+        };
+        if inserted {
+            self.notify();
+        }
+        Ok(inserted)
+    }
+
+    /// Adds many accounts under a single lock and schedules one write.
+    /// Returns the number of accounts that were newly tracked. On error, the
+    /// accounts added before it stay tracked and are still written.
+    pub fn insert_many(&self, keys: impl IntoIterator<Item = Pubkey>) -> Result<usize, CuckooBuildError> {
+        let mut added = 0;
+        let result = {
+            let mut state = self.lock();
+            keys.into_iter().try_for_each(|key| {
+                if self.insert_locked(&mut state, key)? {
+                    added += 1;
+                }
+                Ok(())
+            })
+        };
+        if added > 0 {
+            self.notify();
+        }
+        result.map(|()| added)
+    }
+
+    /// Stops tracking an account. Returns whether it was tracked.
+    pub fn remove(&self, key: Pubkey) -> bool {
+        let removed = self.lock().set.remove(key);
+        if removed {
+            self.notify();
+        }
+        removed
+    }
+
+    /// Stops tracking many accounts and schedules one write.
+    /// Returns the number of accounts that were removed.
+    pub fn remove_many(&self, keys: impl IntoIterator<Item = Pubkey>) -> usize {
+        let removed = {
+            let mut state = self.lock();
+            keys.into_iter().filter(|key| state.set.remove(*key)).count()
+        };
+        if removed > 0 {
+            self.notify();
+        }
+        removed
+    }
+
+    /// Exact membership check (no false positives).
+    pub fn contains(&self, key: Pubkey) -> bool {
+        self.lock().set.contains(key)
+    }
+
+    /// Number of tracked accounts.
+    pub fn len(&self) -> usize {
+        self.lock().set.len()
+    }
+
+    /// Returns `true` if no accounts are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.lock().set.is_empty()
+    }
+
+    /// Current filter capacity; grows by `growth_factor` each time the filter fills up.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    /// Replaces the non-cuckoo part of the subscription and schedules a write.
+    pub fn set_base_request(&self, base_request: SubscribeRequest) {
+        self.lock().base_request = base_request;
+        self.notify();
+    }
+
+    /// The full request: the base request plus the cuckoo filter under `filter_name`.
+    /// Use it for the initial `subscribe` call.
+    pub fn request(&self) -> SubscribeRequest {
+        self.lock().request(&self.options.filter_name)
+    }
+
+    /// Sends pending changes immediately instead of waiting for the debounce window.
+    pub async fn flush(&self) -> Result<(), LaserstreamError> {
+        let pending = self.lock().take_pending(&self.options.filter_name);
+        match pending {
+            Some((handle, request)) => {
+                let result = handle.write(request).await;
+                if result.is_err() {
+                    self.lock().unsent = true;
+                }
+                result
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Re-checks an update against the exact set.
+    ///
+    /// Account updates matched by the managed filter but not actually tracked (cuckoo
+    /// false positives, or accounts removed before the server saw the write) lose the
+    /// filter name; the update is dropped if no other filter matched it. All other
+    /// updates pass through unchanged.
+    pub fn retain(&self, mut update: SubscribeUpdate) -> Option<SubscribeUpdate> {
+        let name = &self.options.filter_name;
+        if !update.filters.iter().any(|f| f == name) {
+            return Some(update);
+        }
+        let Some(UpdateOneof::Account(account)) = &update.update_oneof else {
+            return Some(update);
+        };
+        let tracked = account
+            .account
+            .as_ref()
+            .and_then(|info| Pubkey::try_from(info.pubkey.as_slice()).ok())
+            .is_some_and(|pk| self.contains(pk));
+        if tracked {
+            return Some(update);
+        }
+        update.filters.retain(|f| f != name);
+        if update.filters.is_empty() {
+            None
+        } else {
+            Some(update)
+        }
+    }
+
+    /// Applies [`retain`](Self::retain) to every item of a `subscribe` stream.
+    pub fn filter_stream<S>(&self, stream: S) -> impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>>
+    where
+        S: Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+    {
+        let filter = self.clone();
+        stream.filter_map(move |item| {
+            let item = match item {
+                Ok(update) => filter.retain(update).map(Ok),
+                Err(e) => Some(Err(e)),
+            };
+            futures::future::ready(item)
+        })
+    }
+
+    fn insert_locked(&self, state: &mut FilterState, key: Pubkey) -> Result<bool, CuckooBuildError> {
+        loop {
+            match state.set.insert(key) {
+                Ok(inserted) => return Ok(inserted),
+                Err(TableFullError) => self.grow(state)?,
+            }
+        }
+    }
+
+    /// Rebuilds the filter with `growth_factor` times the capacity, re-inserting every
+    /// tracked account. The rebuilt set is marked dirty so the next flush sends it.
+    fn grow(&self, state: &mut FilterState) -> Result<(), CuckooBuildError> {
+        let mut capacity = state.capacity;
+        loop {
+            capacity = capacity
+                .checked_mul(self.options.growth_factor.max(2))
+                .ok_or(CuckooBuildError::CapacityOverflow)?;
+            let mut set = CompressedAccountFilterSet::with_capacity(capacity)?;
+            let refilled = state
+                .set
+                .iter()
+                .all(|bytes| set.insert(Pubkey::new_from_array(*bytes)).is_ok());
+            if refilled {
+                state.set = set;
+                state.capacity = capacity;
+                return Ok(());
+            }
+        }
+    }
+
+    fn notify(&self) {
+        // One wakeup per debounce window, however many changes land in it.
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            let _ = self.changed_tx.send(());
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FilterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
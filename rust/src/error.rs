@@ -38,4 +38,34 @@ pub enum LaserstreamError {
 
     #[error("Connection error: {0}")]
     ConnectionError(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("RPC error: {0}")]
+    Rpc(String),
+
+    #[error("Invalid Anchor IDL: {0}")]
+    InvalidIdl(String),
+
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Invalid recording: {0}")]
+    InvalidRecording(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Incomplete block: {0}")]
+    IncompleteBlock(crate::blocks::IncompleteBlock),
+
+    #[error("Write failed on shards {shards:?}: {error}")]
+    ShardWrite {
+        /// Indexes of the shards that kept their previous request.
+        shards: Vec<usize>,
+        /// Error of the first failed shard.
+        #[source]
+        error: Box<LaserstreamError>,
+    },
 }
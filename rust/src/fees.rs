@@ -0,0 +1,178 @@
+//! Priority fee estimates from streamed transactions.
+//!
+//! [`PriorityFeeEstimator`] records the compute-unit price of every non-vote
+//! transaction it is fed ([`TransactionView::compute_unit_price`], from Compute
+//! Budget instructions or a V1 transaction config) per slot and per writable account,
+//! keeping a rolling window of recent slots. Estimates are percentiles over the most
+//! recent slots of that window, computed locally.
+
+use crate::transaction::TransactionView;
+use laserstream_core_proto::geyser::{SubscribeRequestFilterTransactions, SubscribeUpdate};
+use std::collections::{BTreeMap, HashMap};
+
+/// Default number of slots kept, about one minute.
+const DEFAULT_MAX_SLOTS: u64 = 150;
+
+#[derive(Debug, Default)]
+struct SlotFees {
+    prices: Vec<u64>,
+    by_account: HashMap<Vec<u8>, Vec<u64>>,
+}
+
+/// Compute-unit prices at common percentiles, in micro-lamports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeLevels {
+    pub min: u64,
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+    pub very_high: u64,
+    pub max: u64,
+    /// Number of transactions the levels are computed from.
+    pub samples: usize,
+}
+
+/// Rolling compute-unit prices per slot and per writable account.
+#[derive(Debug)]
+pub struct PriorityFeeEstimator {
+    slots: BTreeMap<u64, SlotFees>,
+    max_slots: u64,
+}
+
+impl Default for PriorityFeeEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriorityFeeEstimator {
+    pub fn new() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            max_slots: DEFAULT_MAX_SLOTS,
+        }
+    }
+
+    /// Number of slots, counted back from the newest slot seen, whose transactions
+    /// are kept. Default: 150
+    pub fn with_max_slots(mut self, max_slots: u64) -> Self {
+        self.max_slots = max_slots.max(1);
+        self
+    }
+
+    /// Transactions filter for non-vote transactions touching `accounts`; add it to
+    /// a `subscribe` request and pass the updates to [`apply`](Self::apply).
+    pub fn transaction_filter(accounts: Vec<String>) -> SubscribeRequestFilterTransactions {
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            account_include: accounts,
+            ..Default::default()
+        }
+    }
+
+    /// Newest slot with recorded transactions.
+    pub fn latest_slot(&self) -> Option<u64> {
+        self.slots.keys().next_back().copied()
+    }
+
+    /// Records the transactions of a transaction or block update.
+    pub fn apply(&mut self, update: &SubscribeUpdate) {
+        for tx in TransactionView::from_update(update) {
+            self.record(&tx);
+        }
+    }
+
+    /// Records one transaction; vote transactions and slots that already left the
+    /// window are ignored.
+    pub fn record(&mut self, tx: &TransactionView<'_>) {
+        if tx.is_vote() {
+            return;
+        }
+        let slot = tx.slot();
+        if self
+            .latest_slot()
+            .is_some_and(|latest| slot + self.max_slots <= latest)
+        {
+            return;
+        }
+        let price = tx.compute_unit_price();
+        let fees = self.slots.entry(slot).or_default();
+        fees.prices.push(price);
+        for (index, key) in tx.account_keys().iter().enumerate() {
+            if tx.is_writable(index) {
+                fees.by_account.entry(key.to_vec()).or_default().push(price);
+            }
+        }
+        let floor = self
+            .latest_slot()
+            .unwrap_or(slot)
+            .saturating_sub(self.max_slots - 1);
+        self.slots = self.slots.split_off(&floor);
+    }
+
+    /// `percentile` (0-100) of the compute-unit prices of every transaction in the
+    /// last `slots` slots; `None` without transactions.
+    pub fn estimate(&self, percentile: u8, slots: u64) -> Option<u64> {
+        percentile_of(self.prices(None, slots), percentile)
+    }
+
+    /// Like [`estimate`](Self::estimate), over transactions that write `account`.
+    pub fn estimate_for_account(&self, account: &[u8], percentile: u8, slots: u64) -> Option<u64> {
+        percentile_of(self.prices(Some(account), slots), percentile)
+    }
+
+    /// `percentile` of the prices in one slot.
+    pub fn slot_estimate(&self, slot: u64, percentile: u8) -> Option<u64> {
+        let fees = self.slots.get(&slot)?;
+        percentile_of(fees.prices.clone(), percentile)
+    }
+
+    /// Min, p25, p50, p75, p95 and max over the last `slots` slots, optionally only
+    /// for transactions that write `account`.
+    pub fn levels(&self, account: Option<&[u8]>, slots: u64) -> Option<PriorityFeeLevels> {
+        let mut prices = self.prices(account, slots);
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort_unstable();
+        Some(PriorityFeeLevels {
+            min: prices[0],
+            low: nearest_rank(&prices, 25),
+            medium: nearest_rank(&prices, 50),
+            high: nearest_rank(&prices, 75),
+            very_high: nearest_rank(&prices, 95),
+            max: prices[prices.len() - 1],
+            samples: prices.len(),
+        })
+    }
+
+    fn prices(&self, account: Option<&[u8]>, slots: u64) -> Vec<u64> {
+        let Some(latest) = self.latest_slot() else {
+            return Vec::new();
+        };
+        let from = (latest + 1).saturating_sub(slots);
+        self.slots
+            .range(from..)
+            .flat_map(|(_, fees)| match account {
+                Some(account) => fees.by_account.get(account).map_or(&[][..], Vec::as_slice),
+                None => fees.prices.as_slice(),
+            })
+            .copied()
+            .collect()
+    }
+}
+
+fn percentile_of(mut prices: Vec<u64>, percentile: u8) -> Option<u64> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_unstable();
+    Some(nearest_rank(&prices, percentile))
+}
+
+/// Nearest-rank percentile of sorted, non-empty `prices`.
+fn nearest_rank(prices: &[u64], percentile: u8) -> u64 {
+    let percentile = usize::from(percentile.min(100));
+    let rank = (percentile * prices.len()).div_ceil(100).max(1);
+    prices[rank - 1]
+}
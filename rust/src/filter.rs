@@ -0,0 +1,459 @@
+//! Client-side evaluation of subscription filters.
+//!
+//! [`RequestMatcher`] applies the filters of a `SubscribeRequest` to updates that
+//! were not filtered by a server, e.g. a recording replayed with a narrower request.
+//! It follows the server's rules for accounts, slots, transactions, transaction
+//! statuses, blocks, block metas and entries, sets `SubscribeUpdate::filters` to the
+//! names that matched, and applies `accounts_data_slice`.
+//!
+//! Not evaluated: cuckoo filters, token-account expansion and the account filters
+//! of `transactions_status` (statuses carry no account keys); `from_slot` is a
+//! property of the source, not a filter. `commitment` only selects the slot
+//! statuses of slot filters with `filter_by_commitment`; which updates reached that
+//! commitment is up to the source.
+
+use crate::LaserstreamError;
+use base64::Engine;
+use laserstream_core_proto::geyser::{
+    subscribe_request_filter_accounts_filter::Filter as AccountFilter,
+    subscribe_request_filter_accounts_filter_lamports::Cmp,
+    subscribe_request_filter_accounts_filter_memcmp::Data, subscribe_update::UpdateOneof,
+    CommitmentLevel, SlotStatus, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks, SubscribeRequestFilterSlots,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdateAccountInfo,
+    SubscribeUpdateTransactionInfo,
+};
+use std::collections::HashSet;
+
+/// Size of an SPL token account.
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Offset of the token account state byte.
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+/// Token-2022 account type byte marking a token account.
+const TOKEN_2022_ACCOUNT_TYPE: u8 = 2;
+
+#[derive(Debug, Clone)]
+enum DataFilter {
+    Memcmp { offset: usize, bytes: Vec<u8> },
+    Datasize(usize),
+    TokenAccountState(bool),
+    Lamports(Cmp),
+}
+
+#[derive(Debug, Clone)]
+struct AccountsMatcher {
+    name: String,
+    accounts: HashSet<Vec<u8>>,
+    owners: HashSet<Vec<u8>>,
+    filters: Vec<DataFilter>,
+    nonempty_txn_signature: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+struct SlotsMatcher {
+    name: String,
+    interslot_updates: bool,
+    filter_by_commitment: bool,
+}
+
+#[derive(Debug, Clone)]
+struct TransactionsMatcher {
+    name: String,
+    vote: Option<bool>,
+    failed: Option<bool>,
+    signature: Option<Vec<u8>>,
+    include: HashSet<Vec<u8>>,
+    exclude: HashSet<Vec<u8>>,
+    required: HashSet<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+struct BlocksMatcher {
+    name: String,
+    include: HashSet<Vec<u8>>,
+    transactions: bool,
+    accounts: bool,
+    entries: bool,
+}
+
+/// Filters of a `SubscribeRequest`, compiled for matching.
+#[derive(Debug, Clone)]
+pub struct RequestMatcher {
+    accounts: Vec<AccountsMatcher>,
+    slots: Vec<SlotsMatcher>,
+    /// Slot status matching the request's commitment.
+    commitment: SlotStatus,
+    transactions: Vec<TransactionsMatcher>,
+    transactions_status: Vec<TransactionsMatcher>,
+    blocks: Vec<BlocksMatcher>,
+    blocks_meta: Vec<String>,
+    entry: Vec<String>,
+    data_slices: Vec<SubscribeRequestAccountsDataSlice>,
+}
+
+impl RequestMatcher {
+    /// Compiles `request`. Fails on pubkeys, signatures or memcmp data that don't
+    /// decode, and on an unknown commitment.
+    pub fn new(request: &SubscribeRequest) -> Result<Self, LaserstreamError> {
+        Ok(Self {
+            accounts: sorted(&request.accounts)
+                .map(|(name, filter)| accounts_matcher(name, filter))
+                .collect::<Result<_, _>>()?,
+            slots: sorted(&request.slots)
+                .map(|(name, filter)| slots_matcher(name, filter))
+                .collect(),
+            commitment: commitment_status(request.commitment)?,
+            transactions: sorted(&request.transactions)
+                .map(|(name, filter)| transactions_matcher(name, filter))
+                .collect::<Result<_, _>>()?,
+            transactions_status: sorted(&request.transactions_status)
+                .map(|(name, filter)| transactions_matcher(name, filter))
+                .collect::<Result<_, _>>()?,
+            blocks: sorted(&request.blocks)
+                .map(|(name, filter)| blocks_matcher(name, filter))
+                .collect::<Result<_, _>>()?,
+            blocks_meta: sorted(&request.blocks_meta)
+                .map(|(name, _)| name.clone())
+                .collect(),
+            entry: sorted(&request.entry)
+                .map(|(name, _)| name.clone())
+                .collect(),
+            data_slices: request.accounts_data_slice.clone(),
+        })
+    }
+
+    /// Names of the filters `update` matches; empty if it would not be delivered.
+    /// Pings and pongs match nothing.
+    pub fn matches(&self, update: &SubscribeUpdate) -> Vec<String> {
+        let names = |matched: Vec<&String>| matched.into_iter().cloned().collect();
+        match &update.update_oneof {
+            Some(UpdateOneof::Account(account)) => match &account.account {
+                Some(info) => names(
+                    self.accounts
+                        .iter()
+                        .filter(|m| m.matches(info))
+                        .map(|m| &m.name)
+                        .collect(),
+                ),
+                None => Vec::new(),
+            },
+            Some(UpdateOneof::Slot(slot)) => {
+                let interslot = !matches!(
+                    SlotStatus::try_from(slot.status),
+                    Ok(SlotStatus::SlotProcessed
+                        | SlotStatus::SlotConfirmed
+                        | SlotStatus::SlotFinalized)
+                );
+                let committed = slot.status == self.commitment as i32;
+                names(
+                    self.slots
+                        .iter()
+                        .filter(|m| m.interslot_updates || !interslot)
+                        .filter(|m| !m.filter_by_commitment || committed)
+                        .map(|m| &m.name)
+                        .collect(),
+                )
+            }
+            Some(UpdateOneof::Transaction(tx)) => match &tx.transaction {
+                Some(info) => names(
+                    self.transactions
+                        .iter()
+                        .filter(|m| m.matches_transaction(info))
+                        .map(|m| &m.name)
+                        .collect(),
+                ),
+                None => Vec::new(),
+            },
+            Some(UpdateOneof::TransactionStatus(status)) => names(
+                self.transactions_status
+                    .iter()
+                    .filter(|m| {
+                        m.matches_status(&status.signature, status.is_vote, status.err.is_some())
+                    })
+                    .map(|m| &m.name)
+                    .collect(),
+            ),
+            // Every block filter gets the block, with its contents narrowed by `filter`.
+            Some(UpdateOneof::Block(_)) => names(self.blocks.iter().map(|m| &m.name).collect()),
+            Some(UpdateOneof::BlockMeta(_)) => self.blocks_meta.clone(),
+            Some(UpdateOneof::Entry(_)) => self.entry.clone(),
+            Some(UpdateOneof::Ping(_)) | Some(UpdateOneof::Pong(_)) | None => Vec::new(),
+        }
+    }
+
+    /// `update` as the server would deliver it for the request: with its matching
+    /// filter names, account data sliced, and block contents the matching block
+    /// filters don't include removed. `None` if nothing matches.
+    ///
+    /// A block keeps the transactions and accounts that some block filter includes:
+    /// one with `include_transactions` / `include_accounts` set whose
+    /// `account_include` is empty or holds one of their keys. The server sends one
+    /// copy of the block per filter; with several block filters this returns a
+    /// single block holding the union of their contents.
+    pub fn filter(&self, mut update: SubscribeUpdate) -> Option<SubscribeUpdate> {
+        let names = self.matches(&update);
+        if names.is_empty() {
+            return None;
+        }
+        match &mut update.update_oneof {
+            Some(UpdateOneof::Account(account)) => {
+                if let Some(info) = &mut account.account {
+                    self.slice(info);
+                }
+            }
+            Some(UpdateOneof::Block(block)) => {
+                let matched: Vec<&BlocksMatcher> = self
+                    .blocks
+                    .iter()
+                    .filter(|m| names.contains(&m.name))
+                    .collect();
+                block
+                    .transactions
+                    .retain(|info| matched.iter().any(|m| m.keeps_transaction(info)));
+                block
+                    .accounts
+                    .retain(|info| matched.iter().any(|m| m.keeps_account(info)));
+                if !matched.iter().any(|m| m.entries) {
+                    block.entries.clear();
+                }
+                for info in &mut block.accounts {
+                    self.slice(info);
+                }
+            }
+            _ => {}
+        }
+        update.filters = names;
+        Some(update)
+    }
+
+    fn slice(&self, info: &mut SubscribeUpdateAccountInfo) {
+        if self.data_slices.is_empty() {
+            return;
+        }
+        let mut data = Vec::new();
+        for slice in &self.data_slices {
+            let start = (slice.offset as usize).min(info.data.len());
+            let end = (slice.offset.saturating_add(slice.length) as usize).min(info.data.len());
+            data.extend_from_slice(&info.data[start..end]);
+        }
+        info.data = data;
+    }
+}
+
+impl AccountsMatcher {
+    fn matches(&self, info: &SubscribeUpdateAccountInfo) -> bool {
+        (self.accounts.is_empty() || self.accounts.contains(&info.pubkey))
+            && (self.owners.is_empty() || self.owners.contains(&info.owner))
+            && self
+                .nonempty_txn_signature
+                .unwrap_or(info.txn_signature.is_some())
+                == info.txn_signature.is_some()
+            && self.filters.iter().all(|filter| filter.matches(info))
+    }
+}
+
+impl DataFilter {
+    fn matches(&self, info: &SubscribeUpdateAccountInfo) -> bool {
+        match self {
+            Self::Memcmp { offset, bytes } => offset
+                .checked_add(bytes.len())
+                .and_then(|end| info.data.get(*offset..end))
+                .is_some_and(|data| data == bytes.as_slice()),
+            Self::Datasize(size) => info.data.len() == *size,
+            Self::TokenAccountState(expected) => is_token_account(&info.data) == *expected,
+            Self::Lamports(cmp) => match *cmp {
+                Cmp::Eq(value) => info.lamports == value,
+                Cmp::Ne(value) => info.lamports != value,
+                Cmp::Lt(value) => info.lamports < value,
+                Cmp::Gt(value) => info.lamports > value,
+            },
+        }
+    }
+}
+
+impl TransactionsMatcher {
+    fn matches_status(&self, signature: &[u8], is_vote: bool, failed: bool) -> bool {
+        self.vote.unwrap_or(is_vote) == is_vote
+            && self.failed.unwrap_or(failed) == failed
+            && self.signature.as_deref().unwrap_or(signature) == signature
+    }
+
+    fn matches_transaction(&self, info: &SubscribeUpdateTransactionInfo) -> bool {
+        let failed = info.meta.as_ref().is_some_and(|meta| meta.err.is_some());
+        if !self.matches_status(&info.signature, info.is_vote, failed) {
+            return false;
+        }
+        if self.include.is_empty() && self.exclude.is_empty() && self.required.is_empty() {
+            return true;
+        }
+        let keys = transaction_keys(info);
+        (self.include.is_empty() || keys.iter().any(|key| self.include.contains(*key)))
+            && !keys.iter().any(|key| self.exclude.contains(*key))
+            && self
+                .required
+                .iter()
+                .all(|required| keys.contains(&required.as_slice()))
+    }
+}
+
+impl BlocksMatcher {
+    fn keeps_transaction(&self, info: &SubscribeUpdateTransactionInfo) -> bool {
+        self.transactions
+            && (self.include.is_empty()
+                || transaction_keys(info)
+                    .iter()
+                    .any(|key| self.include.contains(*key)))
+    }
+
+    fn keeps_account(&self, info: &SubscribeUpdateAccountInfo) -> bool {
+        self.accounts && (self.include.is_empty() || self.include.contains(&info.pubkey))
+    }
+}
+
+fn transaction_keys(info: &SubscribeUpdateTransactionInfo) -> HashSet<&[u8]> {
+    let static_keys = info
+        .transaction
+        .as_ref()
+        .and_then(|tx| tx.message.as_ref())
+        .map(|message| message.account_keys.as_slice())
+        .unwrap_or_default();
+    let loaded = info.meta.as_ref().map(|meta| {
+        meta.loaded_writable_addresses
+            .iter()
+            .chain(&meta.loaded_readonly_addresses)
+    });
+    static_keys
+        .iter()
+        .chain(loaded.into_iter().flatten())
+        .map(Vec::as_slice)
+        .collect()
+}
+
+fn is_token_account(data: &[u8]) -> bool {
+    let initialized = data
+        .get(TOKEN_ACCOUNT_STATE_OFFSET)
+        .is_some_and(|state| *state == 1 || *state == 2);
+    initialized
+        && (data.len() == TOKEN_ACCOUNT_LEN
+            || data.get(TOKEN_ACCOUNT_LEN) == Some(&TOKEN_2022_ACCOUNT_TYPE))
+}
+
+/// Filters in name order, so matched names come out in a stable order.
+fn sorted<T>(
+    filters: &std::collections::HashMap<String, T>,
+) -> impl Iterator<Item = (&String, &T)> {
+    let mut filters: Vec<_> = filters.iter().collect();
+    filters.sort_by_key(|(name, _)| *name);
+    filters.into_iter()
+}
+
+fn invalid(what: &str, value: &str) -> LaserstreamError {
+    LaserstreamError::InvalidFilter(format!("invalid {what}: {value}"))
+}
+
+fn pubkeys(values: &[String]) -> Result<HashSet<Vec<u8>>, LaserstreamError> {
+    values
+        .iter()
+        .map(|value| {
+            bs58::decode(value)
+                .into_vec()
+                .map_err(|_| invalid("pubkey", value))
+        })
+        .collect()
+}
+
+fn accounts_matcher(
+    name: &str,
+    filter: &SubscribeRequestFilterAccounts,
+) -> Result<AccountsMatcher, LaserstreamError> {
+    let filters = filter
+        .filters
+        .iter()
+        .filter_map(|f| f.filter.as_ref())
+        .map(|f| {
+            Ok(match f {
+                AccountFilter::Memcmp(memcmp) => DataFilter::Memcmp {
+                    offset: memcmp.offset as usize,
+                    bytes: match &memcmp.data {
+                        Some(Data::Bytes(bytes)) => bytes.clone(),
+                        Some(Data::Base58(value)) => bs58::decode(value)
+                            .into_vec()
+                            .map_err(|_| invalid("memcmp base58", value))?,
+                        Some(Data::Base64(value)) => base64::engine::general_purpose::STANDARD
+                            .decode(value)
+                            .map_err(|_| invalid("memcmp base64", value))?,
+                        None => Vec::new(),
+                    },
+                },
+                AccountFilter::Datasize(size) => DataFilter::Datasize(*size as usize),
+                AccountFilter::TokenAccountState(state) => DataFilter::TokenAccountState(*state),
+                AccountFilter::Lamports(lamports) => match lamports.cmp {
+                    Some(cmp) => DataFilter::Lamports(cmp),
+                    None => return Err(invalid("lamports filter", "no comparison")),
+                },
+            })
+        })
+        .collect::<Result<_, LaserstreamError>>()?;
+    Ok(AccountsMatcher {
+        name: name.to_string(),
+        accounts: pubkeys(&filter.account)?,
+        owners: pubkeys(&filter.owner)?,
+        filters,
+        nonempty_txn_signature: filter.nonempty_txn_signature,
+    })
+}
+
+fn slots_matcher(name: &str, filter: &SubscribeRequestFilterSlots) -> SlotsMatcher {
+    SlotsMatcher {
+        name: name.to_string(),
+        interslot_updates: filter.interslot_updates.unwrap_or(false),
+        filter_by_commitment: filter.filter_by_commitment.unwrap_or(false),
+    }
+}
+
+/// The slot status a request's commitment corresponds to; processed by default.
+fn commitment_status(commitment: Option<i32>) -> Result<SlotStatus, LaserstreamError> {
+    let commitment = commitment.unwrap_or(CommitmentLevel::Processed as i32);
+    match CommitmentLevel::try_from(commitment) {
+        Ok(CommitmentLevel::Processed) => Ok(SlotStatus::SlotProcessed),
+        Ok(CommitmentLevel::Confirmed) => Ok(SlotStatus::SlotConfirmed),
+        Ok(CommitmentLevel::Finalized) => Ok(SlotStatus::SlotFinalized),
+        Err(_) => Err(invalid("commitment", &commitment.to_string())),
+    }
+}
+
+fn transactions_matcher(
+    name: &str,
+    filter: &SubscribeRequestFilterTransactions,
+) -> Result<TransactionsMatcher, LaserstreamError> {
+    Ok(TransactionsMatcher {
+        name: name.to_string(),
+        vote: filter.vote,
+        failed: filter.failed,
+        signature: filter
+            .signature
+            .as_ref()
+            .map(|s| {
+                bs58::decode(s)
+                    .into_vec()
+                    .map_err(|_| invalid("signature", s))
+            })
+            .transpose()?,
+        include: pubkeys(&filter.account_include)?,
+        exclude: pubkeys(&filter.account_exclude)?,
+        required: pubkeys(&filter.account_required)?,
+    })
+}
+
+fn blocks_matcher(
+    name: &str,
+    filter: &SubscribeRequestFilterBlocks,
+) -> Result<BlocksMatcher, LaserstreamError> {
+    Ok(BlocksMatcher {
+        name: name.to_string(),
+        include: pubkeys(&filter.account_include)?,
+        transactions: filter.include_transactions.unwrap_or(true),
+        accounts: filter.include_accounts.unwrap_or(false),
+        entries: filter.include_entries.unwrap_or(false),
+    })
+}
@@ -0,0 +1,64 @@
+use super::{
+    InstructionDecoder, InstructionNode, ParsedInstruction, ASSOCIATED_TOKEN_PROGRAM_ID,
+};
+
+/// Associated Token Account program instruction. Addresses are base58.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssociatedTokenInstruction {
+    /// `Create`, or `CreateIdempotent` when `idempotent` is set.
+    Create {
+        funder: String,
+        account: String,
+        wallet: String,
+        mint: String,
+        token_program: String,
+        idempotent: bool,
+    },
+    RecoverNested {
+        nested_account: String,
+        nested_mint: String,
+        destination: String,
+        owner_account: String,
+        owner_mint: String,
+        wallet: String,
+    },
+}
+
+/// Decoder for the Associated Token Account program.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AssociatedTokenDecoder;
+
+impl InstructionDecoder for AssociatedTokenDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![ASSOCIATED_TOKEN_PROGRAM_ID.to_string()]
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        "spl-associated-token-account".to_string()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        let account = |index| ix.account(index);
+        // Empty data is the original `Create`.
+        let instruction = match ix.data.first().copied().unwrap_or(0) {
+            tag @ (0 | 1) => AssociatedTokenInstruction::Create {
+                funder: account(0)?,
+                account: account(1)?,
+                wallet: account(2)?,
+                mint: account(3)?,
+                token_program: account(5)?,
+                idempotent: tag == 1,
+            },
+            2 => AssociatedTokenInstruction::RecoverNested {
+                nested_account: account(0)?,
+                nested_mint: account(1)?,
+                destination: account(2)?,
+                owner_account: account(3)?,
+                owner_mint: account(4)?,
+                wallet: account(5)?,
+            },
+            _ => return None,
+        };
+        Some(ParsedInstruction::AssociatedToken(instruction))
+    }
+}
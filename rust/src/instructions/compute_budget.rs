@@ -0,0 +1,52 @@
+use super::{InstructionDecoder, InstructionNode, ParsedInstruction, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::layout::Reader;
+
+/// Compute Budget program instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    /// Deprecated combined limit and fee request.
+    RequestUnits { units: u32, additional_fee: u32 },
+    RequestHeapFrame { bytes: u32 },
+    SetComputeUnitLimit { units: u32 },
+    /// Priority fee in micro-lamports per compute unit.
+    SetComputeUnitPrice { micro_lamports: u64 },
+    SetLoadedAccountsDataSizeLimit { bytes: u32 },
+}
+
+/// Decoder for the Compute Budget program.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComputeBudgetDecoder;
+
+impl InstructionDecoder for ComputeBudgetDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![COMPUTE_BUDGET_PROGRAM_ID.to_string()]
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        "compute-budget".to_string()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        ComputeBudgetInstruction::parse(ix.data).map(ParsedInstruction::ComputeBudget)
+    }
+}
+
+impl ComputeBudgetInstruction {
+    /// Parses Compute Budget instruction data.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut data = Reader::new(data);
+        Some(match data.u8()? {
+            0 => ComputeBudgetInstruction::RequestUnits {
+                units: data.u32()?,
+                additional_fee: data.u32()?,
+            },
+            1 => ComputeBudgetInstruction::RequestHeapFrame { bytes: data.u32()? },
+            2 => ComputeBudgetInstruction::SetComputeUnitLimit { units: data.u32()? },
+            3 => ComputeBudgetInstruction::SetComputeUnitPrice {
+                micro_lamports: data.u64()?,
+            },
+            4 => ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit { bytes: data.u32()? },
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use super::{
+    InstructionDecoder, InstructionNode, ParsedInstruction, MEMO_PROGRAM_ID, MEMO_V1_PROGRAM_ID,
+};
+
+/// Decoder for the Memo program (v1 and v2). Invalid UTF-8 is replaced.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoDecoder;
+
+impl InstructionDecoder for MemoDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![MEMO_PROGRAM_ID.to_string(), MEMO_V1_PROGRAM_ID.to_string()]
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        "spl-memo".to_string()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        Some(ParsedInstruction::Memo(
+            String::from_utf8_lossy(ix.data).into_owned(),
+        ))
+    }
+}
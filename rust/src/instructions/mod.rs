@@ -0,0 +1,255 @@
+//! Instruction decoding for streamed transactions.
+//!
+//! [`InstructionNode::collect`] flattens a transaction's outer and inner instructions
+//! into one list with CPI stack heights and parent links. An [`InstructionRegistry`]
+//! maps program ids to [`InstructionDecoder`]s and turns those nodes into typed
+//! [`ParsedInstruction`]s; [`InstructionRegistry::with_builtins`] covers System, SPL
+//! Token, Token-2022, Associated Token Account, Compute Budget and Memo.
+
+mod associated_token;
+mod compute_budget;
+mod memo;
+mod system;
+mod token;
+
+pub use associated_token::{AssociatedTokenDecoder, AssociatedTokenInstruction};
+pub use compute_budget::{ComputeBudgetDecoder, ComputeBudgetInstruction};
+pub use memo::MemoDecoder;
+pub use system::{SystemDecoder, SystemInstruction};
+pub use token::{TokenDecoder, TokenInstruction};
+
+use crate::transaction::TransactionView;
+use laserstream_core_proto::solana::storage::confirmed_block::InnerInstruction;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+pub const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+pub const MEMO_V1_PROGRAM_ID: &str = "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo";
+pub const STAKE_PROGRAM_ID: &str = "Stake11111111111111111111111111111111111111";
+pub const VOTE_PROGRAM_ID: &str = "Vote111111111111111111111111111111111111111";
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: &str = "AddressLookupTab1e1111111111111111111111111";
+
+/// One instruction of a transaction, outer or inner.
+#[derive(Debug, Clone)]
+pub struct InstructionNode<'a> {
+    /// Index of the outer instruction this belongs to.
+    pub outer_index: usize,
+    /// Position among the inner instructions of `outer_index`; `None` for the outer
+    /// instruction itself.
+    pub inner_index: Option<usize>,
+    /// CPI depth: 1 for outer instructions, 2 for what they invoke, and so on.
+    pub stack_height: u32,
+    /// Position of the invoking instruction in the list returned by
+    /// [`collect`](Self::collect).
+    pub parent: Option<usize>,
+    pub program_id: &'a [u8],
+    /// Instruction accounts, resolved against the full account-key list.
+    pub accounts: Vec<&'a [u8]>,
+    pub data: &'a [u8],
+}
+
+impl<'a> InstructionNode<'a> {
+    /// All instructions of `tx` in execution order: each outer instruction followed by
+    /// its inner instructions.
+    ///
+    /// Nodes whose program or account index is out of range are skipped. Without
+    /// recorded stack heights (old transactions), every inner instruction is treated as
+    /// a direct CPI of its outer instruction.
+    pub fn collect(tx: &TransactionView<'a>) -> Vec<Self> {
+        let meta = tx.meta();
+        let inner_by_outer: HashMap<usize, &'a [InnerInstruction]> = meta
+            .inner_instructions
+            .iter()
+            .map(|inner| (inner.index as usize, inner.instructions.as_slice()))
+            .collect();
+
+        let mut nodes = Vec::new();
+        for (outer_index, ix) in tx.message().instructions.iter().enumerate() {
+            let Some(node) = Self::resolve(
+                tx,
+                outer_index,
+                None,
+                1,
+                None,
+                ix.program_id_index,
+                &ix.accounts,
+                &ix.data,
+            ) else {
+                continue;
+            };
+            let outer_position = nodes.len();
+            nodes.push(node);
+
+            // (stack height, position) of the instructions currently on the CPI stack.
+            let mut stack = vec![(1u32, outer_position)];
+            let inner_instructions = inner_by_outer.get(&outer_index).copied().unwrap_or_default();
+            for (inner_index, inner) in inner_instructions.iter().enumerate() {
+                let height = inner.stack_height.unwrap_or(2);
+                while stack.last().is_some_and(|(h, _)| *h >= height) {
+                    stack.pop();
+                }
+                let parent = stack.last().map_or(outer_position, |(_, position)| *position);
+                let Some(node) = Self::resolve(
+                    tx,
+                    outer_index,
+                    Some(inner_index),
+                    height,
+                    Some(parent),
+                    inner.program_id_index,
+                    &inner.accounts,
+                    &inner.data,
+                ) else {
+                    continue;
+                };
+                stack.push((height, nodes.len()));
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn resolve(
+        tx: &TransactionView<'a>,
+        outer_index: usize,
+        inner_index: Option<usize>,
+        stack_height: u32,
+        parent: Option<usize>,
+        program_id_index: u32,
+        accounts: &'a [u8],
+        data: &'a [u8],
+    ) -> Option<Self> {
+        Some(Self {
+            outer_index,
+            inner_index,
+            stack_height,
+            parent,
+            program_id: tx.account_key(program_id_index as usize)?,
+            accounts: accounts
+                .iter()
+                .map(|index| tx.account_key(*index as usize))
+                .collect::<Option<_>>()?,
+            data,
+        })
+    }
+
+    /// Account at `index` as base58.
+    pub fn account(&self, index: usize) -> Option<String> {
+        self.accounts.get(index).map(|key| bs58::encode(key).into_string())
+    }
+
+    pub fn is_inner(&self) -> bool {
+        self.inner_index.is_some()
+    }
+}
+
+/// Typed result of a decoder.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedInstruction {
+    System(SystemInstruction),
+    /// SPL Token or Token-2022; tell them apart by [`DecodedInstruction::program`].
+    Token(TokenInstruction),
+    AssociatedToken(AssociatedTokenInstruction),
+    ComputeBudget(ComputeBudgetInstruction),
+    Memo(String),
+    /// Output of decoders outside this crate.
+    Other(serde_json::Value),
+}
+
+/// Decodes instructions of one program.
+pub trait InstructionDecoder: Send + Sync {
+    /// Program ids (base58) this decoder handles.
+    fn program_ids(&self) -> Vec<String>;
+
+    /// Short program name, e.g. `"spl-token"`.
+    fn name(&self, program_id: &[u8]) -> String;
+
+    /// Decodes one instruction of a handled program; `None` if the data is not
+    /// recognized.
+    fn decode(&self, instruction: &InstructionNode<'_>) -> Option<ParsedInstruction>;
+}
+
+/// An instruction with its decoded form, if a decoder recognized it.
+#[derive(Debug, Clone)]
+pub struct DecodedInstruction<'a> {
+    pub node: InstructionNode<'a>,
+    /// Name of the decoder's program, e.g. `"spl-token"`; `None` if no decoder is
+    /// registered for the program.
+    pub program: Option<String>,
+    pub parsed: Option<ParsedInstruction>,
+}
+
+/// Decoders keyed by program id.
+#[derive(Clone, Default)]
+pub struct InstructionRegistry {
+    decoders: HashMap<Vec<u8>, Arc<dyn InstructionDecoder>>,
+}
+
+impl InstructionRegistry {
+    /// Registry without any decoders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the decoders for the core programs.
+    pub fn with_builtins() -> Self {
+        Self::new()
+            .with(SystemDecoder)
+            .with(TokenDecoder)
+            .with(AssociatedTokenDecoder)
+            .with(ComputeBudgetDecoder)
+            .with(MemoDecoder)
+    }
+
+    /// Adds `decoder` for all of its program ids, replacing earlier registrations.
+    pub fn register(&mut self, decoder: impl InstructionDecoder + 'static) {
+        let decoder: Arc<dyn InstructionDecoder> = Arc::new(decoder);
+        for program_id in decoder.program_ids() {
+            match bs58::decode(&program_id).into_vec() {
+                Ok(key) => {
+                    self.decoders.insert(key, decoder.clone());
+                }
+                Err(e) => tracing::warn!(%program_id, error = %e, "Ignoring invalid program id"),
+            }
+        }
+    }
+
+    /// Builder form of [`register`](Self::register).
+    pub fn with(mut self, decoder: impl InstructionDecoder + 'static) -> Self {
+        self.register(decoder);
+        self
+    }
+
+    /// Decodes one instruction.
+    pub fn decode(&self, node: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        self.decoders.get(node.program_id)?.decode(node)
+    }
+
+    /// Every instruction of `tx`, outer and inner, in execution order.
+    pub fn decode_transaction<'a>(&self, tx: &TransactionView<'a>) -> Vec<DecodedInstruction<'a>> {
+        InstructionNode::collect(tx)
+            .into_iter()
+            .map(|node| {
+                let decoder = self.decoders.get(node.program_id);
+                DecodedInstruction {
+                    program: decoder.map(|d| d.name(node.program_id)),
+                    parsed: decoder.and_then(|d| d.decode(&node)),
+                    node,
+                }
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for InstructionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstructionRegistry")
+            .field("programs", &self.decoders.len())
+            .finish()
+    }
+}
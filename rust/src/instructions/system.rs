@@ -0,0 +1,181 @@
+use super::{InstructionDecoder, InstructionNode, ParsedInstruction, SYSTEM_PROGRAM_ID};
+use crate::layout::Reader;
+
+/// System program instruction. Addresses are base58; `lamports` in lamports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemInstruction {
+    CreateAccount {
+        from: String,
+        new_account: String,
+        lamports: u64,
+        space: u64,
+        owner: String,
+    },
+    Assign {
+        account: String,
+        owner: String,
+    },
+    Transfer {
+        from: String,
+        to: String,
+        lamports: u64,
+    },
+    CreateAccountWithSeed {
+        from: String,
+        new_account: String,
+        base: String,
+        seed: String,
+        lamports: u64,
+        space: u64,
+        owner: String,
+    },
+    AdvanceNonceAccount {
+        nonce_account: String,
+        authority: String,
+    },
+    WithdrawNonceAccount {
+        nonce_account: String,
+        to: String,
+        authority: String,
+        lamports: u64,
+    },
+    InitializeNonceAccount {
+        nonce_account: String,
+        authority: String,
+    },
+    AuthorizeNonceAccount {
+        nonce_account: String,
+        authority: String,
+        new_authority: String,
+    },
+    Allocate {
+        account: String,
+        space: u64,
+    },
+    AllocateWithSeed {
+        account: String,
+        base: String,
+        seed: String,
+        space: u64,
+        owner: String,
+    },
+    AssignWithSeed {
+        account: String,
+        base: String,
+        seed: String,
+        owner: String,
+    },
+    TransferWithSeed {
+        from: String,
+        base: String,
+        to: String,
+        lamports: u64,
+        from_seed: String,
+        from_owner: String,
+    },
+    UpgradeNonceAccount {
+        nonce_account: String,
+    },
+}
+
+/// Decoder for the System program.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemDecoder;
+
+impl InstructionDecoder for SystemDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![SYSTEM_PROGRAM_ID.to_string()]
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        "system".to_string()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        decode(ix).map(ParsedInstruction::System)
+    }
+}
+
+fn decode(ix: &InstructionNode<'_>) -> Option<SystemInstruction> {
+    let mut data = Reader::new(ix.data);
+    let account = |index| ix.account(index);
+    Some(match data.u32()? {
+        0 => SystemInstruction::CreateAccount {
+            from: account(0)?,
+            new_account: account(1)?,
+            lamports: data.u64()?,
+            space: data.u64()?,
+            owner: data.pubkey()?,
+        },
+        1 => SystemInstruction::Assign {
+            account: account(0)?,
+            owner: data.pubkey()?,
+        },
+        2 => SystemInstruction::Transfer {
+            from: account(0)?,
+            to: account(1)?,
+            lamports: data.u64()?,
+        },
+        3 => {
+            let base = data.pubkey()?;
+            let seed = data.bincode_string()?;
+            SystemInstruction::CreateAccountWithSeed {
+                from: account(0)?,
+                new_account: account(1)?,
+                base,
+                seed,
+                lamports: data.u64()?,
+                space: data.u64()?,
+                owner: data.pubkey()?,
+            }
+        }
+        4 => SystemInstruction::AdvanceNonceAccount {
+            nonce_account: account(0)?,
+            authority: account(2)?,
+        },
+        5 => SystemInstruction::WithdrawNonceAccount {
+            nonce_account: account(0)?,
+            to: account(1)?,
+            authority: account(4)?,
+            lamports: data.u64()?,
+        },
+        6 => SystemInstruction::InitializeNonceAccount {
+            nonce_account: account(0)?,
+            authority: data.pubkey()?,
+        },
+        7 => SystemInstruction::AuthorizeNonceAccount {
+            nonce_account: account(0)?,
+            authority: account(1)?,
+            new_authority: data.pubkey()?,
+        },
+        8 => SystemInstruction::Allocate {
+            account: account(0)?,
+            space: data.u64()?,
+        },
+        9 => SystemInstruction::AllocateWithSeed {
+            account: account(0)?,
+            base: data.pubkey()?,
+            seed: data.bincode_string()?,
+            space: data.u64()?,
+            owner: data.pubkey()?,
+        },
+        10 => SystemInstruction::AssignWithSeed {
+            account: account(0)?,
+            base: data.pubkey()?,
+            seed: data.bincode_string()?,
+            owner: data.pubkey()?,
+        },
+        11 => SystemInstruction::TransferWithSeed {
+            from: account(0)?,
+            base: account(1)?,
+            to: account(2)?,
+            lamports: data.u64()?,
+            from_seed: data.bincode_string()?,
+            from_owner: data.pubkey()?,
+        },
+        12 => SystemInstruction::UpgradeNonceAccount {
+            nonce_account: account(0)?,
+        },
+        _ => return None,
+    })
+}
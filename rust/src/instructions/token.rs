@@ -0,0 +1,302 @@
+use super::{
+    InstructionDecoder, InstructionNode, ParsedInstruction, TOKEN_2022_PROGRAM_ID,
+    TOKEN_PROGRAM_ID,
+};
+use crate::layout::Reader;
+
+/// SPL Token / Token-2022 instruction. Addresses are base58; amounts in base units.
+///
+/// The instructions shared by both programs are decoded fully. Token-2022 extension
+/// instructions (tag 25 and above) are reported as [`TokenInstruction::Extension`]
+/// with their raw data; the legacy program has no such instructions, so those tags
+/// are not decoded for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenInstruction {
+    InitializeMint {
+        mint: String,
+        decimals: u8,
+        mint_authority: String,
+        freeze_authority: Option<String>,
+    },
+    InitializeAccount {
+        account: String,
+        mint: String,
+        owner: String,
+    },
+    InitializeMultisig {
+        multisig: String,
+        signers: Vec<String>,
+        m: u8,
+    },
+    Transfer {
+        source: String,
+        destination: String,
+        authority: String,
+        amount: u64,
+    },
+    Approve {
+        source: String,
+        delegate: String,
+        owner: String,
+        amount: u64,
+    },
+    Revoke {
+        source: String,
+        owner: String,
+    },
+    SetAuthority {
+        account: String,
+        current_authority: String,
+        authority_type: u8,
+        new_authority: Option<String>,
+    },
+    MintTo {
+        mint: String,
+        account: String,
+        authority: String,
+        amount: u64,
+    },
+    Burn {
+        account: String,
+        mint: String,
+        authority: String,
+        amount: u64,
+    },
+    CloseAccount {
+        account: String,
+        destination: String,
+        authority: String,
+    },
+    FreezeAccount {
+        account: String,
+        mint: String,
+        authority: String,
+    },
+    ThawAccount {
+        account: String,
+        mint: String,
+        authority: String,
+    },
+    TransferChecked {
+        source: String,
+        mint: String,
+        destination: String,
+        authority: String,
+        amount: u64,
+        decimals: u8,
+    },
+    ApproveChecked {
+        source: String,
+        mint: String,
+        delegate: String,
+        owner: String,
+        amount: u64,
+        decimals: u8,
+    },
+    MintToChecked {
+        mint: String,
+        account: String,
+        authority: String,
+        amount: u64,
+        decimals: u8,
+    },
+    BurnChecked {
+        account: String,
+        mint: String,
+        authority: String,
+        amount: u64,
+        decimals: u8,
+    },
+    SyncNative {
+        account: String,
+    },
+    GetAccountDataSize {
+        mint: String,
+    },
+    InitializeImmutableOwner {
+        account: String,
+    },
+    AmountToUiAmount {
+        mint: String,
+        amount: u64,
+    },
+    UiAmountToAmount {
+        mint: String,
+        ui_amount: String,
+    },
+    /// Token-2022 extension instruction (`tag` >= 25).
+    Extension {
+        tag: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl TokenInstruction {
+    /// Amount moved by `Transfer` / `TransferChecked`.
+    pub fn transfer_amount(&self) -> Option<u64> {
+        match self {
+            TokenInstruction::Transfer { amount, .. }
+            | TokenInstruction::TransferChecked { amount, .. } => Some(*amount),
+            _ => None,
+        }
+    }
+}
+
+/// Decoder for SPL Token and Token-2022.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenDecoder;
+
+impl InstructionDecoder for TokenDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![TOKEN_PROGRAM_ID.to_string(), TOKEN_2022_PROGRAM_ID.to_string()]
+    }
+
+    fn name(&self, program_id: &[u8]) -> String {
+        if bs58::encode(program_id).into_string() == TOKEN_2022_PROGRAM_ID {
+            "spl-token-2022".to_string()
+        } else {
+            "spl-token".to_string()
+        }
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        decode(ix).map(ParsedInstruction::Token)
+    }
+}
+
+fn decode(ix: &InstructionNode<'_>) -> Option<TokenInstruction> {
+    let mut data = Reader::new(ix.data);
+    let account = |index| ix.account(index);
+    Some(match data.u8()? {
+        // InitializeMint and InitializeMint2 differ only in the rent sysvar account.
+        0 | 20 => TokenInstruction::InitializeMint {
+            mint: account(0)?,
+            decimals: data.u8()?,
+            mint_authority: data.pubkey()?,
+            freeze_authority: data.pubkey_option_u8()?,
+        },
+        1 => TokenInstruction::InitializeAccount {
+            account: account(0)?,
+            mint: account(1)?,
+            owner: account(2)?,
+        },
+        // InitializeAccount2 / 3 pass the owner in data instead of as an account.
+        16 | 18 => TokenInstruction::InitializeAccount {
+            account: account(0)?,
+            mint: account(1)?,
+            owner: data.pubkey()?,
+        },
+        tag @ (2 | 19) => {
+            // InitializeMultisig has the rent sysvar before the signers.
+            let first_signer = if tag == 2 { 2 } else { 1 };
+            TokenInstruction::InitializeMultisig {
+                multisig: account(0)?,
+                signers: (first_signer..ix.accounts.len()).filter_map(account).collect(),
+                m: data.u8()?,
+            }
+        }
+        3 => TokenInstruction::Transfer {
+            source: account(0)?,
+            destination: account(1)?,
+            authority: account(2)?,
+            amount: data.u64()?,
+        },
+        4 => TokenInstruction::Approve {
+            source: account(0)?,
+            delegate: account(1)?,
+            owner: account(2)?,
+            amount: data.u64()?,
+        },
+        5 => TokenInstruction::Revoke {
+            source: account(0)?,
+            owner: account(1)?,
+        },
+        6 => TokenInstruction::SetAuthority {
+            account: account(0)?,
+            current_authority: account(1)?,
+            authority_type: data.u8()?,
+            new_authority: data.pubkey_option_u8()?,
+        },
+        7 => TokenInstruction::MintTo {
+            mint: account(0)?,
+            account: account(1)?,
+            authority: account(2)?,
+            amount: data.u64()?,
+        },
+        8 => TokenInstruction::Burn {
+            account: account(0)?,
+            mint: account(1)?,
+            authority: account(2)?,
+            amount: data.u64()?,
+        },
+        9 => TokenInstruction::CloseAccount {
+            account: account(0)?,
+            destination: account(1)?,
+            authority: account(2)?,
+        },
+        10 => TokenInstruction::FreezeAccount {
+            account: account(0)?,
+            mint: account(1)?,
+            authority: account(2)?,
+        },
+        11 => TokenInstruction::ThawAccount {
+            account: account(0)?,
+            mint: account(1)?,
+            authority: account(2)?,
+        },
+        12 => TokenInstruction::TransferChecked {
+            source: account(0)?,
+            mint: account(1)?,
+            destination: account(2)?,
+            authority: account(3)?,
+            amount: data.u64()?,
+            decimals: data.u8()?,
+        },
+        13 => TokenInstruction::ApproveChecked {
+            source: account(0)?,
+            mint: account(1)?,
+            delegate: account(2)?,
+            owner: account(3)?,
+            amount: data.u64()?,
+            decimals: data.u8()?,
+        },
+        14 => TokenInstruction::MintToChecked {
+            mint: account(0)?,
+            account: account(1)?,
+            authority: account(2)?,
+            amount: data.u64()?,
+            decimals: data.u8()?,
+        },
+        15 => TokenInstruction::BurnChecked {
+            account: account(0)?,
+            mint: account(1)?,
+            authority: account(2)?,
+            amount: data.u64()?,
+            decimals: data.u8()?,
+        },
+        17 => TokenInstruction::SyncNative {
+            account: account(0)?,
+        },
+        21 => TokenInstruction::GetAccountDataSize {
+            mint: account(0)?,
+        },
+        22 => TokenInstruction::InitializeImmutableOwner {
+            account: account(0)?,
+        },
+        23 => TokenInstruction::AmountToUiAmount {
+            mint: account(0)?,
+            amount: data.u64()?,
+        },
+        24 => TokenInstruction::UiAmountToAmount {
+            mint: account(0)?,
+            ui_amount: String::from_utf8_lossy(data.remaining()).into_owned(),
+        },
+        tag @ 25.. if bs58::encode(ix.program_id).into_string() == TOKEN_2022_PROGRAM_ID => {
+            TokenInstruction::Extension {
+                tag,
+                data: data.remaining().to_vec(),
+            }
+        }
+        _ => return None,
+    })
+}
@@ -0,0 +1,102 @@
+//! Little-endian reader for instruction and account layouts.
+
+/// Cursor over a byte slice. Every read returns `None` once the data runs out.
+#[derive(Debug, Clone)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        self.data.get(self.offset..).unwrap_or_default()
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        Some(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Option<bool> {
+        Some(self.u8()? != 0)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn i16(&mut self) -> Option<i16> {
+        Some(i16::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.array()?))
+    }
+
+    /// 32-byte public key as base58.
+    pub(crate) fn pubkey(&mut self) -> Option<String> {
+        Some(bs58::encode(self.bytes(32)?).into_string())
+    }
+
+    /// 32-byte key where all zeros means "none" (`OptionalNonZeroPubkey`).
+    pub(crate) fn nonzero_pubkey(&mut self) -> Option<Option<String>> {
+        let bytes = self.bytes(32)?;
+        Some(bytes.iter().any(|b| *b != 0).then(|| bs58::encode(bytes).into_string()))
+    }
+
+    /// Borsh string: u32 length, then UTF-8 bytes.
+    pub(crate) fn borsh_string(&mut self) -> Option<String> {
+        let len = usize::try_from(self.u32()?).ok()?;
+        Some(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    /// Bincode string: u64 length, then UTF-8 bytes.
+    pub(crate) fn bincode_string(&mut self) -> Option<String> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        Some(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    /// Instruction-style `COption<Pubkey>`: one tag byte, then the key if set.
+    pub(crate) fn pubkey_option_u8(&mut self) -> Option<Option<String>> {
+        match self.u8()? {
+            0 => Some(None),
+            1 => Some(Some(self.pubkey()?)),
+            _ => None,
+        }
+    }
+
+    /// Account-style `COption<Pubkey>`: u32 tag, then 32 bytes that are always present.
+    pub(crate) fn pubkey_option_u32(&mut self) -> Option<Option<String>> {
+        let tag = self.u32()?;
+        let key = self.pubkey()?;
+        Some((tag == 1).then_some(key))
+    }
+
+    /// Account-style `COption<u64>`: u32 tag, then 8 bytes that are always present.
+    pub(crate) fn u64_option_u32(&mut self) -> Option<Option<u64>> {
+        let tag = self.u32()?;
+        let value = self.u64()?;
+        Some((tag == 1).then_some(value))
+    }
+}
@@ -1,51 +1,61 @@
+pub mod accounts;
+pub mod analytics;
+pub mod anchor;
+pub mod blockhash;
+pub mod blocks;
+#[cfg(feature = "testing")]
+pub mod chaos;
 pub mod client;
 pub mod config;
+#[cfg(feature = "cuckoo")]
+pub mod cuckoo;
 pub mod error;
+pub mod fees;
+pub mod filter;
+pub mod instructions;
+mod layout;
+pub mod lifecycle;
+pub mod logs;
+pub mod lookup_tables;
+pub mod promotion;
+pub mod recording;
+pub mod rollback;
+pub mod rpc;
+pub mod sharding;
+pub mod signatures;
+pub mod skips;
+pub mod slots;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transaction;
 
-pub use client::{subscribe, subscribe_preprocessed, StreamHandle, PreprocessedStreamHandle};
+pub use accounts::AccountCache;
+pub use analytics::ProgramAnalytics;
+pub use anchor::AnchorIdl;
+pub use blockhash::BlockhashCache;
+pub use blocks::{assemble_blocks, block_request, BlockAssembler, BlockAssemblyOptions};
+pub use client::{
+    subscribe, subscribe_preprocessed, LaserstreamClient, PreprocessedStreamHandle, StreamHandle,
+};
 pub use config::{ChannelOptions, LaserstreamConfig, CompressionEncoding};
 pub use error::LaserstreamError;
+pub use fees::PriorityFeeEstimator;
+pub use filter::RequestMatcher;
+pub use instructions::{InstructionDecoder, InstructionRegistry};
+pub use lifecycle::{subscribe_lifecycle, LifecycleEvent, LifecycleHandle, LifecycleTracker};
+pub use logs::{ProgramLogs, TransactionLogsExt};
+pub use lookup_tables::{resolve_lookup_tables, LookupTableCache};
+pub use promotion::{subscribe_with_promotions, PromotionEvent, UpdateId};
+pub use recording::{
+    record_stream, replay_from_files, Pacing, Recorder, RecorderOptions, Recording, ReplayOptions,
+};
+pub use rollback::{rollback_aware, ForkEvent, RollbackBuffer};
+pub use sharding::{subscribe_sharded, ShardedStreamHandle, ShardingOptions};
+pub use signatures::{SignatureStatus, SignatureWaitlist};
+pub use skips::{subscribe_skips, LeaderSchedule, SkipDetector, SkipEvent, SkipHandle};
+pub use slots::{SlotEvent, SlotTracker};
+pub use transaction::TransactionView;
 
 // Re-export commonly used types from laserstream-core-proto
 pub use laserstream_core_proto::geyser as grpc;
 pub use laserstream_core_proto::solana;
-
-/// Compressed account (cuckoo) filtering.
-///
-/// Track large pubkey sets without re-uploading an explicit list every request:
-/// build a compact cuckoo filter (~3 bytes/account) and let the server match
-/// against it (no false negatives, <1% false positives — re-check locally with
-/// [`CompressedAccountFilterSet::contains`]).
-///
-/// ```no_run
-/// use helius_laserstream::cuckoo::{CompressedAccountFilterSet, Pubkey};
-/// use helius_laserstream::grpc::SubscribeRequest;
-/// # fn tracked_pubkeys() -> Vec<Pubkey> { vec![] }
-/// # let new_pk = Pubkey::default();
-/// let mut set = CompressedAccountFilterSet::with_capacity(2_000_000).unwrap();
-/// for pk in tracked_pubkeys() { set.insert(pk).unwrap(); }
-///
-/// let mut req = SubscribeRequest::default();
-/// set.insert_into_subscribe_request(&mut req, "tracked_accounts");
-/// // ... subscribe(req) ...
-///
-/// // On change, mutate and re-send on the SAME stream:
-/// set.insert(new_pk).unwrap();
-/// if set.take_dirty() {
-///     set.insert_into_subscribe_request(&mut req, "tracked_accounts");
-///     // handle.write(req).await
-/// }
-/// ```
-///
-/// Also works on transaction subscriptions: put `set.to_proto()` on
-/// `SubscribeRequestFilterTransactions.cuckoo_account_include` (see the
-/// `cuckoo_transaction_filter` example).
-#[cfg(feature = "cuckoo")]
-pub mod cuckoo {
-    pub use laserstream_core_proto::cuckoo::{
-        CompressedAccountFilterSet, CuckooBuildError, CuckooFilter, DEFAULT_HASH_SEED,
-        TableFullError, YellowstoneHasherBuilder,
-    };
-    // Re-exported so callers use the exact Pubkey version the filter API expects.
-    pub use solana_pubkey::Pubkey;
-}
\ No newline at end of file
@@ -0,0 +1,576 @@
+//! Per-signature transaction lifecycles.
+//!
+//! [`LifecycleTracker`] correlates a `subscribe_preprocessed` stream with a PROCESSED
+//! `transactions` / `transactions_status` stream and slot statuses, and records when
+//! each signature was submitted, first seen before execution, processed, confirmed
+//! and finalized, or that it was dropped. [`subscribe_lifecycle`] runs both
+//! subscriptions and yields a [`LifecycleEvent`] for every stage reached; its
+//! [`LifecycleHandle`] registers signatures with the running tracker.
+
+use crate::client::{subscribe_preprocessed, subscribe_with_mode, SlotTrackingMode, StreamItem};
+use crate::slots::{SlotEvent, SlotStage, SlotTracker};
+use crate::{LaserstreamConfig, LaserstreamError, StreamHandle};
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_preprocessed_update, subscribe_update::UpdateOneof, SubscribePreprocessedRequest,
+    SubscribePreprocessedUpdate, SubscribeRequest, SubscribeUpdate, SubscribeUpdateSlot,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::instrument;
+
+/// Default time a signature may go without landing before it counts as dropped,
+/// about the lifetime of a blockhash.
+const DEFAULT_DROP_TIMEOUT: Duration = Duration::from_secs(90);
+/// How often [`subscribe_lifecycle`] checks for dropped signatures.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A point in a transaction's lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Registered with [`LifecycleTracker::watch`].
+    Submitted,
+    /// Seen on the preprocessed stream, before execution.
+    Preprocessed,
+    Processed,
+    Confirmed,
+    Finalized,
+    /// The slot it was processed in was orphaned. It may still land in another slot.
+    Orphaned,
+    /// Didn't land within the drop timeout.
+    Dropped,
+}
+
+/// When a signature reached each stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub signature: Vec<u8>,
+    /// Slot it was processed in; cleared again if that slot is orphaned.
+    pub slot: Option<u64>,
+    /// `Some(false)` once processed with an error.
+    pub succeeded: Option<bool>,
+    pub submitted: Option<Instant>,
+    pub preprocessed: Option<Instant>,
+    pub processed: Option<Instant>,
+    pub confirmed: Option<Instant>,
+    pub finalized: Option<Instant>,
+    /// Last time its slot was orphaned.
+    pub orphaned: Option<Instant>,
+    pub dropped: Option<Instant>,
+}
+
+impl Timeline {
+    fn new(signature: Vec<u8>) -> Self {
+        Self {
+            signature,
+            slot: None,
+            succeeded: None,
+            submitted: None,
+            preprocessed: None,
+            processed: None,
+            confirmed: None,
+            finalized: None,
+            orphaned: None,
+            dropped: None,
+        }
+    }
+
+    pub fn signature_string(&self) -> String {
+        bs58::encode(&self.signature).into_string()
+    }
+
+    /// When `stage` was reached.
+    pub fn at(&self, stage: Stage) -> Option<Instant> {
+        match stage {
+            Stage::Submitted => self.submitted,
+            Stage::Preprocessed => self.preprocessed,
+            Stage::Processed => self.processed,
+            Stage::Confirmed => self.confirmed,
+            Stage::Finalized => self.finalized,
+            Stage::Orphaned => self.orphaned,
+            Stage::Dropped => self.dropped,
+        }
+    }
+
+    /// Time from `from` to `to`; `None` unless both were reached.
+    pub fn latency(&self, from: Stage, to: Stage) -> Option<Duration> {
+        Some(self.at(to)?.saturating_duration_since(self.at(from)?))
+    }
+
+    /// Earliest of submission, preprocessing and processing.
+    pub fn first_seen(&self) -> Option<Instant> {
+        [self.submitted, self.preprocessed, self.processed]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Time from first sight to CONFIRMED.
+    pub fn time_to_confirm(&self) -> Option<Duration> {
+        Some(
+            self.confirmed?
+                .saturating_duration_since(self.first_seen()?),
+        )
+    }
+
+    /// Whether the signature landed and can't change anymore.
+    pub fn is_complete(&self) -> bool {
+        self.finalized.is_some() || self.dropped.is_some()
+    }
+}
+
+/// A signature reached `stage`; `timeline` is its state right after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleEvent {
+    pub stage: Stage,
+    pub timeline: Timeline,
+}
+
+/// Outcome counts over completed timelines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LifecycleStats {
+    /// Finalized signatures.
+    pub landed: u64,
+    pub dropped: u64,
+    /// Finalized signatures whose execution failed (they still paid fees).
+    pub failed: u64,
+    confirmed: u64,
+    total_time_to_confirm: Duration,
+}
+
+impl LifecycleStats {
+    /// Share of completed signatures that landed.
+    pub fn landing_rate(&self) -> Option<f64> {
+        let total = self.landed + self.dropped;
+        (total > 0).then(|| self.landed as f64 / total as f64)
+    }
+
+    /// Mean [`Timeline::time_to_confirm`] over landed signatures.
+    pub fn mean_time_to_confirm(&self) -> Option<Duration> {
+        (self.confirmed > 0).then(|| self.total_time_to_confirm / self.confirmed as u32)
+    }
+}
+
+/// Follows signatures through their lifecycle.
+///
+/// The transaction stream must be at PROCESSED commitment and the tracker needs every
+/// slot status (see [`SlotTracker::slot_filter`]) to promote processed signatures.
+/// Timelines are dropped from the tracker once finalized or dropped.
+#[derive(Debug)]
+pub struct LifecycleTracker {
+    slots: SlotTracker,
+    timelines: HashMap<Vec<u8>, Timeline>,
+    /// Signatures processed in each unsettled slot.
+    by_slot: BTreeMap<u64, Vec<Vec<u8>>>,
+    drop_timeout: Duration,
+    watched_only: bool,
+    stats: LifecycleStats,
+}
+
+impl Default for LifecycleTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LifecycleTracker {
+    /// Tracker following every signature it sees.
+    pub fn new() -> Self {
+        Self {
+            slots: SlotTracker::new(),
+            timelines: HashMap::new(),
+            by_slot: BTreeMap::new(),
+            drop_timeout: DEFAULT_DROP_TIMEOUT,
+            watched_only: false,
+            stats: LifecycleStats::default(),
+        }
+    }
+
+    /// Only follows signatures registered with [`watch`](Self::watch), such as
+    /// your own submissions.
+    pub fn watched_only(mut self) -> Self {
+        self.watched_only = true;
+        self
+    }
+
+    /// Time a signature may go without landing, counted from when it was first seen
+    /// or its slot was orphaned, before it is reported dropped. Default: 90s
+    pub fn with_drop_timeout(mut self, timeout: Duration) -> Self {
+        self.drop_timeout = timeout;
+        self
+    }
+
+    pub fn stats(&self) -> LifecycleStats {
+        self.stats
+    }
+
+    /// Timeline of a signature still being followed.
+    pub fn get(&self, signature: &[u8]) -> Option<&Timeline> {
+        self.timelines.get(signature)
+    }
+
+    /// Number of signatures being followed.
+    pub fn len(&self) -> usize {
+        self.timelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timelines.is_empty()
+    }
+
+    /// Starts following `signature` as submitted now.
+    pub fn watch(&mut self, signature: &[u8]) -> Vec<LifecycleEvent> {
+        self.watch_at(signature, Instant::now())
+    }
+
+    /// Like [`watch`](Self::watch) with an explicit submission time.
+    pub fn watch_at(&mut self, signature: &[u8], at: Instant) -> Vec<LifecycleEvent> {
+        let timeline = self
+            .timelines
+            .entry(signature.to_vec())
+            .or_insert_with(|| Timeline::new(signature.to_vec()));
+        if timeline.submitted.is_some() {
+            return Vec::new();
+        }
+        timeline.submitted = Some(at);
+        vec![LifecycleEvent {
+            stage: Stage::Submitted,
+            timeline: timeline.clone(),
+        }]
+    }
+
+    /// Records a preprocessed update.
+    pub fn preprocessed(&mut self, update: &SubscribePreprocessedUpdate) -> Vec<LifecycleEvent> {
+        self.preprocessed_at(update, Instant::now())
+    }
+
+    /// Like [`preprocessed`](Self::preprocessed) with an explicit timestamp.
+    pub fn preprocessed_at(
+        &mut self,
+        update: &SubscribePreprocessedUpdate,
+        at: Instant,
+    ) -> Vec<LifecycleEvent> {
+        let Some(subscribe_preprocessed_update::UpdateOneof::Transaction(tx)) =
+            &update.update_oneof
+        else {
+            return Vec::new();
+        };
+        let Some(info) = &tx.transaction else {
+            return Vec::new();
+        };
+        let Some(timeline) = self.timeline(&info.signature) else {
+            return Vec::new();
+        };
+        if timeline.preprocessed.is_some() || timeline.processed.is_some() {
+            return Vec::new();
+        }
+        timeline.preprocessed = Some(at);
+        vec![LifecycleEvent {
+            stage: Stage::Preprocessed,
+            timeline: timeline.clone(),
+        }]
+    }
+
+    /// Records a transaction, transaction status or slot update.
+    pub fn apply(&mut self, update: &SubscribeUpdate) -> Vec<LifecycleEvent> {
+        self.apply_at(update, Instant::now())
+    }
+
+    /// Like [`apply`](Self::apply) with an explicit timestamp.
+    pub fn apply_at(&mut self, update: &SubscribeUpdate, at: Instant) -> Vec<LifecycleEvent> {
+        match &update.update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => match &tx.transaction {
+                Some(info) => {
+                    let succeeded = info.meta.as_ref().map(|meta| meta.err.is_none());
+                    self.processed(&info.signature, tx.slot, succeeded, at)
+                }
+                None => Vec::new(),
+            },
+            Some(UpdateOneof::TransactionStatus(status)) => self.processed(
+                &status.signature,
+                status.slot,
+                Some(status.err.is_none()),
+                at,
+            ),
+            Some(UpdateOneof::Slot(slot)) => self.slot_at(slot, at),
+            Some(UpdateOneof::BlockMeta(_)) => {
+                self.slots.apply(update);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Records a slot status.
+    pub fn slot(&mut self, update: &SubscribeUpdateSlot) -> Vec<LifecycleEvent> {
+        self.slot_at(update, Instant::now())
+    }
+
+    /// Like [`slot`](Self::slot) with an explicit timestamp.
+    pub fn slot_at(&mut self, update: &SubscribeUpdateSlot, at: Instant) -> Vec<LifecycleEvent> {
+        let mut events = Vec::new();
+        for event in self.slots.update_at(update, at) {
+            match event {
+                SlotEvent::Stage {
+                    slot,
+                    stage: stage @ (SlotStage::Confirmed | SlotStage::Finalized),
+                } => {
+                    let stage = if stage == SlotStage::Confirmed {
+                        Stage::Confirmed
+                    } else {
+                        Stage::Finalized
+                    };
+                    let signatures = self.by_slot.get(&slot).cloned().unwrap_or_default();
+                    for signature in signatures {
+                        // A finalized slot counts as confirmed even if that status was missed.
+                        events.extend(self.promote(&signature, Stage::Confirmed, at));
+                        if stage == Stage::Finalized {
+                            events.extend(self.promote(&signature, stage, at));
+                        }
+                    }
+                    if stage == Stage::Finalized {
+                        self.by_slot.remove(&slot);
+                    }
+                }
+                SlotEvent::Orphaned { slot, .. } => {
+                    for signature in self.by_slot.remove(&slot).unwrap_or_default() {
+                        let Some(timeline) = self.timelines.get_mut(&signature) else {
+                            continue;
+                        };
+                        if timeline.slot != Some(slot) {
+                            continue;
+                        }
+                        timeline.slot = None;
+                        timeline.processed = None;
+                        timeline.confirmed = None;
+                        timeline.succeeded = None;
+                        timeline.orphaned = Some(at);
+                        events.push(LifecycleEvent {
+                            stage: Stage::Orphaned,
+                            timeline: timeline.clone(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// Reports signatures that haven't landed within the drop timeout as dropped.
+    pub fn expire(&mut self) -> Vec<LifecycleEvent> {
+        self.expire_at(Instant::now())
+    }
+
+    /// Like [`expire`](Self::expire) with an explicit current time.
+    pub fn expire_at(&mut self, now: Instant) -> Vec<LifecycleEvent> {
+        let timeout = self.drop_timeout;
+        let expired: Vec<Vec<u8>> = self
+            .timelines
+            .values()
+            .filter(|timeline| timeline.processed.is_none())
+            .filter(|timeline| {
+                timeline
+                    .orphaned
+                    .max(timeline.first_seen())
+                    .is_some_and(|since| now.saturating_duration_since(since) >= timeout)
+            })
+            .map(|timeline| timeline.signature.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|signature| {
+                let mut timeline = self.timelines.remove(&signature)?;
+                timeline.dropped = Some(now);
+                self.stats.dropped += 1;
+                Some(LifecycleEvent {
+                    stage: Stage::Dropped,
+                    timeline,
+                })
+            })
+            .collect()
+    }
+
+    /// Timeline to update for `signature`, created unless only watched signatures
+    /// are followed.
+    fn timeline(&mut self, signature: &[u8]) -> Option<&mut Timeline> {
+        if self.watched_only {
+            return self.timelines.get_mut(signature);
+        }
+        Some(
+            self.timelines
+                .entry(signature.to_vec())
+                .or_insert_with(|| Timeline::new(signature.to_vec())),
+        )
+    }
+
+    fn processed(
+        &mut self,
+        signature: &[u8],
+        slot: u64,
+        succeeded: Option<bool>,
+        at: Instant,
+    ) -> Vec<LifecycleEvent> {
+        if self.slots.get(slot).is_some_and(|info| info.is_orphaned()) {
+            return Vec::new();
+        }
+        let Some(timeline) = self.timeline(signature) else {
+            return Vec::new();
+        };
+        if timeline.processed.is_some() {
+            return Vec::new();
+        }
+        timeline.processed = Some(at);
+        timeline.slot = Some(slot);
+        timeline.succeeded = succeeded;
+        let mut events = vec![LifecycleEvent {
+            stage: Stage::Processed,
+            timeline: timeline.clone(),
+        }];
+        self.by_slot
+            .entry(slot)
+            .or_default()
+            .push(signature.to_vec());
+
+        // Slot statuses can arrive before the transaction.
+        let reached = self.slots.get(slot);
+        let confirmed = reached.is_some_and(|info| info.reached(SlotStage::Confirmed).is_some());
+        let finalized = reached.is_some_and(|info| info.reached(SlotStage::Finalized).is_some())
+            || self.slots.finalized_slot() >= Some(slot);
+        if confirmed || finalized {
+            events.extend(self.promote(signature, Stage::Confirmed, at));
+        }
+        if finalized {
+            events.extend(self.promote(signature, Stage::Finalized, at));
+        }
+        events
+    }
+
+    fn promote(&mut self, signature: &[u8], stage: Stage, at: Instant) -> Option<LifecycleEvent> {
+        let timeline = self.timelines.get_mut(signature)?;
+        timeline.processed?;
+        match stage {
+            Stage::Confirmed if timeline.confirmed.is_none() => {
+                timeline.confirmed = Some(at);
+                Some(LifecycleEvent {
+                    stage,
+                    timeline: timeline.clone(),
+                })
+            }
+            Stage::Finalized => {
+                let mut timeline = self.timelines.remove(signature)?;
+                timeline.finalized = Some(at);
+                self.stats.landed += 1;
+                if timeline.succeeded == Some(false) {
+                    self.stats.failed += 1;
+                }
+                if let Some(latency) = timeline.time_to_confirm() {
+                    self.stats.confirmed += 1;
+                    self.stats.total_time_to_confirm += latency;
+                }
+                Some(LifecycleEvent { stage, timeline })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Handle to a running [`subscribe_lifecycle`] stream. Cheap to clone.
+#[derive(Clone)]
+pub struct LifecycleHandle {
+    stream: StreamHandle,
+    watches: mpsc::UnboundedSender<(Vec<u8>, Instant)>,
+    stats: Arc<RwLock<LifecycleStats>>,
+}
+
+impl LifecycleHandle {
+    /// Starts following `signature` as submitted now. Call it right before sending
+    /// the transaction so the preprocessed and processed stages aren't missed.
+    pub fn watch(&self, signature: &[u8]) -> Result<(), LaserstreamError> {
+        self.watches
+            .send((signature.to_vec(), Instant::now()))
+            .map_err(|_| LaserstreamError::ConnectionError("Lifecycle stream closed".to_string()))
+    }
+
+    /// Outcome counts so far.
+    pub fn stats(&self) -> LifecycleStats {
+        *self.stats.read().unwrap()
+    }
+
+    /// Replaces the transaction subscription; see [`StreamHandle::write`].
+    pub async fn write(&self, request: SubscribeRequest) -> Result<(), LaserstreamError> {
+        self.stream.write(request).await
+    }
+}
+
+/// Subscribes with `request` at PROCESSED and `preprocessed_request` side by side
+/// and follows every signature they deliver through `tracker`.
+///
+/// `request` should carry the `transactions` or `transactions_status` filters to
+/// follow; slot statuses are subscribed internally. To measure your own
+/// transactions, use a tracker built with [`LifecycleTracker::watched_only`] and
+/// register each signature with [`LifecycleHandle::watch`] before submitting it.
+/// Watches take effect while the returned stream is being polled.
+#[instrument(skip(config, request, preprocessed_request, tracker))]
+pub fn subscribe_lifecycle(
+    config: LaserstreamConfig,
+    request: SubscribeRequest,
+    preprocessed_request: SubscribePreprocessedRequest,
+    mut tracker: LifecycleTracker,
+) -> (
+    impl Stream<Item = Result<LifecycleEvent, LaserstreamError>>,
+    LifecycleHandle,
+) {
+    let (updates, stream_handle) =
+        subscribe_with_mode(config.clone(), request, SlotTrackingMode::AllStatuses);
+    let (preprocessed, _) = subscribe_preprocessed(config, preprocessed_request);
+    let (watch_tx, mut watches) = mpsc::unbounded_channel();
+    let stats = Arc::new(RwLock::new(tracker.stats()));
+    let handle = LifecycleHandle {
+        stream: stream_handle,
+        watches: watch_tx,
+        stats: stats.clone(),
+    };
+    let events = stream! {
+        futures::pin_mut!(updates);
+        futures::pin_mut!(preprocessed);
+        let mut expiry = tokio::time::interval(EXPIRE_INTERVAL);
+        let mut preprocessed_open = true;
+        loop {
+            let events = tokio::select! {
+                // Watches first, so a signature is registered before its updates.
+                biased;
+                Some((signature, at)) = watches.recv() => tracker.watch_at(&signature, at),
+                item = updates.next() => match item {
+                    Some(Ok(StreamItem::Update(update))) => tracker.apply(&update),
+                    Some(Ok(StreamItem::InternalSlot(slot))) => tracker.slot(&slot),
+                    Some(Err(e)) => {
+                        yield Err(e);
+                        continue;
+                    }
+                    None => break,
+                },
+                item = preprocessed.next(), if preprocessed_open => match item {
+                    Some(Ok(update)) => tracker.preprocessed(&update),
+                    Some(Err(e)) => {
+                        yield Err(e);
+                        continue;
+                    }
+                    None => {
+                        preprocessed_open = false;
+                        continue;
+                    }
+                },
+                _ = expiry.tick() => tracker.expire(),
+            };
+            *stats.write().unwrap() = tracker.stats();
+            for event in events {
+                yield Ok(event);
+            }
+        }
+    };
+    (events, handle)
+}
@@ -0,0 +1,295 @@
+//! Program log parsing.
+//!
+//! [`ProgramLogs::parse`] rebuilds the invocation tree from the flat
+//! `TransactionStatusMeta.log_messages` lines: which program invoked which, the
+//! compute units each invocation consumed, how it ended, and what it logged. The
+//! [`TransactionLogsExt`] trait adds [`program_logs`](TransactionLogsExt::program_logs)
+//! to the transaction updates of a `subscribe` stream.
+
+use base64::Engine;
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateTransaction,
+    SubscribeUpdateTransactionInfo,
+};
+
+/// Line the runtime writes once a transaction exceeds its log limit.
+const TRUNCATED: &str = "Log truncated";
+
+/// How an invocation ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvocationStatus {
+    Success,
+    /// `Program <id> failed: <error>`; holds the error text.
+    Failed(String),
+    /// The logs end before the invocation does (truncated logs).
+    Unknown,
+}
+
+/// A line logged inside an invocation, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEntry {
+    /// `Program log: <message>`.
+    Log(String),
+    /// `Program data: <base64> ...`, one element per base64 field. Fields that
+    /// don't decode are left out.
+    Data(Vec<Vec<u8>>),
+    /// A nested invocation, as an index into [`ProgramLogs::invocations`].
+    Invoke(usize),
+    /// Any other line, such as raw `sol_log` output.
+    Other(String),
+}
+
+/// One program invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// Program id (base58).
+    pub program_id: String,
+    /// Invocation depth from `invoke [n]`: 1 for outer instructions.
+    pub depth: u32,
+    /// Index of the invoking invocation.
+    pub parent: Option<usize>,
+    pub entries: Vec<LogEntry>,
+    /// From `consumed N of M compute units`.
+    pub compute_units_consumed: Option<u64>,
+    /// Compute units available when the invocation started (`M` above).
+    pub compute_units_limit: Option<u64>,
+    /// `Program return: <id> <base64>`.
+    pub return_data: Option<Vec<u8>>,
+    pub status: InvocationStatus,
+}
+
+impl Invocation {
+    fn new(program_id: &str, depth: u32, parent: Option<usize>) -> Self {
+        Self {
+            program_id: program_id.to_string(),
+            depth,
+            parent,
+            entries: Vec::new(),
+            compute_units_consumed: None,
+            compute_units_limit: None,
+            return_data: None,
+            status: InvocationStatus::Unknown,
+        }
+    }
+
+    /// `Program log:` messages.
+    pub fn logs(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().filter_map(|entry| match entry {
+            LogEntry::Log(message) => Some(message.as_str()),
+            _ => None,
+        })
+    }
+
+    /// `Program data:` fields, one slice per line.
+    pub fn data(&self) -> impl Iterator<Item = &[Vec<u8>]> {
+        self.entries.iter().filter_map(|entry| match entry {
+            LogEntry::Data(fields) => Some(fields.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Indexes of the invocations this one made, in order.
+    pub fn children(&self) -> impl Iterator<Item = usize> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            LogEntry::Invoke(index) => Some(*index),
+            _ => None,
+        })
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, InvocationStatus::Failed(_))
+    }
+
+    /// Error text of a failed invocation.
+    pub fn error(&self) -> Option<&str> {
+        match &self.status {
+            InvocationStatus::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The invocation tree of one transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramLogs {
+    invocations: Vec<Invocation>,
+    truncated: bool,
+    unattributed: Vec<String>,
+}
+
+impl ProgramLogs {
+    /// Parses log lines. Never fails: lines it doesn't recognize are kept as
+    /// [`LogEntry::Other`], or in [`unattributed`](Self::unattributed) when no
+    /// invocation is open.
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Self {
+        let mut logs = Self::default();
+        // Open invocations, innermost last.
+        let mut stack: Vec<usize> = Vec::new();
+        for line in lines {
+            let line = line.as_ref();
+            if line == TRUNCATED {
+                logs.truncated = true;
+                break;
+            }
+            let current = stack.last().copied();
+
+            if let Some(message) = line.strip_prefix("Program log: ") {
+                logs.push_entry(current, LogEntry::Log(message.to_string()), line);
+            } else if let Some(data) = line.strip_prefix("Program data: ") {
+                let fields = data
+                    .split_whitespace()
+                    .filter_map(|field| {
+                        base64::engine::general_purpose::STANDARD.decode(field).ok()
+                    })
+                    .collect();
+                logs.push_entry(current, LogEntry::Data(fields), line);
+            } else if let Some(rest) = line.strip_prefix("Program return: ") {
+                let data = rest.split_once(' ').and_then(|(_, data)| {
+                    base64::engine::general_purpose::STANDARD.decode(data).ok()
+                });
+                match (current, data) {
+                    (Some(index), Some(data)) => logs.invocations[index].return_data = Some(data),
+                    _ => logs.push_entry(current, LogEntry::Other(line.to_string()), line),
+                }
+            } else if let Some((program_id, event)) = line
+                .strip_prefix("Program ")
+                .and_then(|rest| rest.split_once(' '))
+            {
+                if !logs.program_line(&mut stack, program_id, event) {
+                    logs.push_entry(current, LogEntry::Other(line.to_string()), line);
+                }
+            } else {
+                logs.push_entry(current, LogEntry::Other(line.to_string()), line);
+            }
+        }
+        logs
+    }
+
+    /// Handles `Program <id> <event>` lines; `false` if the line isn't one of them.
+    fn program_line(&mut self, stack: &mut Vec<usize>, program_id: &str, event: &str) -> bool {
+        if let Some(depth) = event
+            .strip_prefix("invoke [")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|depth| depth.parse().ok())
+        {
+            let parent = stack.last().copied();
+            let index = self.invocations.len();
+            self.invocations
+                .push(Invocation::new(program_id, depth, parent));
+            if let Some(parent) = parent {
+                self.invocations[parent]
+                    .entries
+                    .push(LogEntry::Invoke(index));
+            }
+            stack.push(index);
+            return true;
+        }
+
+        // Remaining events close or annotate an open invocation of this program.
+        let Some(position) = stack
+            .iter()
+            .rposition(|index| self.invocations[*index].program_id == program_id)
+        else {
+            return false;
+        };
+        let index = stack[position];
+        if let Some(units) = event.strip_prefix("consumed ") {
+            let mut parts = units.split_whitespace();
+            let consumed = parts.next().and_then(|n| n.parse().ok());
+            let limit = parts.nth(1).and_then(|n| n.parse().ok());
+            let invocation = &mut self.invocations[index];
+            invocation.compute_units_consumed = consumed;
+            invocation.compute_units_limit = limit;
+            return consumed.is_some();
+        }
+        let status = if event == "success" {
+            InvocationStatus::Success
+        } else if let Some(error) = event.strip_prefix("failed: ") {
+            InvocationStatus::Failed(error.to_string())
+        } else {
+            return false;
+        };
+        self.invocations[index].status = status;
+        stack.truncate(position);
+        true
+    }
+
+    fn push_entry(&mut self, current: Option<usize>, entry: LogEntry, line: &str) {
+        match current {
+            Some(index) => self.invocations[index].entries.push(entry),
+            None => self.unattributed.push(line.to_string()),
+        }
+    }
+
+    /// All invocations in the order they started.
+    pub fn invocations(&self) -> &[Invocation] {
+        &self.invocations
+    }
+
+    /// Outer-instruction invocations.
+    pub fn roots(&self) -> impl Iterator<Item = &Invocation> {
+        self.invocations
+            .iter()
+            .filter(|invocation| invocation.parent.is_none())
+    }
+
+    /// Whether the runtime cut the logs off (`Log truncated`). Invocations still
+    /// open at that point have [`InvocationStatus::Unknown`].
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Lines logged outside any invocation.
+    pub fn unattributed(&self) -> &[String] {
+        &self.unattributed
+    }
+
+    /// Failed invocations, callers included.
+    pub fn failed(&self) -> impl Iterator<Item = &Invocation> {
+        self.invocations
+            .iter()
+            .filter(|invocation| invocation.is_failed())
+    }
+
+    /// The invocation the failure started in: a failed invocation none of whose
+    /// children failed.
+    pub fn failure_origin(&self) -> Option<&Invocation> {
+        self.failed().find(|invocation| {
+            !invocation
+                .children()
+                .any(|child| self.invocations[child].is_failed())
+        })
+    }
+}
+
+/// [`ProgramLogs`] for transaction updates.
+pub trait TransactionLogsExt {
+    /// The parsed logs; `None` when the update isn't a transaction or the node
+    /// didn't record logs.
+    fn program_logs(&self) -> Option<ProgramLogs>;
+}
+
+impl TransactionLogsExt for SubscribeUpdateTransactionInfo {
+    fn program_logs(&self) -> Option<ProgramLogs> {
+        let meta = self.meta.as_ref()?;
+        if meta.log_messages_none {
+            return None;
+        }
+        Some(ProgramLogs::parse(&meta.log_messages))
+    }
+}
+
+impl TransactionLogsExt for SubscribeUpdateTransaction {
+    fn program_logs(&self) -> Option<ProgramLogs> {
+        self.transaction.as_ref()?.program_logs()
+    }
+}
+
+impl TransactionLogsExt for SubscribeUpdate {
+    fn program_logs(&self) -> Option<ProgramLogs> {
+        match self.update_oneof.as_ref()? {
+            UpdateOneof::Transaction(tx) => tx.program_logs(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! Address lookup table resolution.
+//!
+//! Preprocessed transactions come without status meta, so a v0 message only carries
+//! its static keys and the `(table, index)` pairs of its lookups. [`LookupTableCache`]
+//! keeps table contents current from account updates of the Address Lookup Table
+//! program, optionally loading unknown tables through JSON-RPC, and
+//! [`resolve_lookup_tables`] attaches the resolved addresses to every transaction of
+//! a `subscribe_preprocessed` stream.
+//!
+//! RPC loads run in the background: concurrent lookups of the same table share one
+//! request, the stream waits at most the load timeout for them, and tables the RPC
+//! doesn't return aren't requested again until the missing TTL has passed.
+
+use crate::error::LaserstreamError;
+use crate::instructions::ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+use crate::layout::Reader;
+use crate::rpc::RpcClient;
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_preprocessed_update, subscribe_update::UpdateOneof, CommitmentLevel,
+    SubscribePreprocessedUpdate, SubscribeRequestFilterAccounts, SubscribeUpdate,
+    SubscribeUpdateAccountInfo,
+};
+use laserstream_core_proto::solana::storage::confirmed_block::Message;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Size of the table header; addresses follow it.
+const LOOKUP_TABLE_META_LEN: usize = 56;
+/// `ProgramState::LookupTable` discriminant.
+const LOOKUP_TABLE_TYPE: u32 = 1;
+/// Default for [`LookupTableCache::with_missing_ttl`].
+const DEFAULT_MISSING_TTL: Duration = Duration::from_secs(30);
+/// Default for [`LookupTableCache::with_load_timeout`].
+const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Contents of one address lookup table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    pub key: Vec<u8>,
+    /// Slot of the account update this state was read from.
+    pub slot: u64,
+    /// `u64::MAX` while the table is active.
+    pub deactivation_slot: u64,
+    pub last_extended_slot: u64,
+    pub authority: Option<Vec<u8>>,
+    pub addresses: Vec<Vec<u8>>,
+}
+
+impl LookupTable {
+    /// Parses lookup table account data; `None` for uninitialized or malformed tables.
+    pub fn parse(key: Vec<u8>, slot: u64, data: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(data);
+        if r.u32()? != LOOKUP_TABLE_TYPE {
+            return None;
+        }
+        let deactivation_slot = r.u64()?;
+        let last_extended_slot = r.u64()?;
+        let _last_extended_start_index = r.u8()?;
+        let has_authority = r.bool()?;
+        let authority = r.bytes(32)?;
+        let addresses = data.get(LOOKUP_TABLE_META_LEN..)?;
+        if addresses.len() % 32 != 0 {
+            return None;
+        }
+        Some(Self {
+            key,
+            slot,
+            deactivation_slot,
+            last_extended_slot,
+            authority: has_authority.then(|| authority.to_vec()),
+            addresses: addresses.chunks(32).map(<[u8]>::to_vec).collect(),
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot == u64::MAX
+    }
+}
+
+/// Addresses a message loads from lookup tables, in the order the runtime appends
+/// them to the account keys: all writable addresses, then all readonly ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadedAddresses {
+    pub writable: Vec<Vec<u8>>,
+    pub readonly: Vec<Vec<u8>>,
+    /// Tables that are unknown, or too short for the requested indexes. Their
+    /// addresses are left out of `writable` and `readonly`.
+    pub missing_tables: Vec<Vec<u8>>,
+}
+
+impl LoadedAddresses {
+    /// Whether every lookup was resolved.
+    pub fn is_complete(&self) -> bool {
+        self.missing_tables.is_empty()
+    }
+
+    /// Static keys followed by the loaded addresses: the full account-key list of
+    /// the transaction when [`is_complete`](Self::is_complete).
+    pub fn account_keys<'a>(&'a self, message: &'a Message) -> Vec<&'a [u8]> {
+        message
+            .account_keys
+            .iter()
+            .chain(&self.writable)
+            .chain(&self.readonly)
+            .map(Vec::as_slice)
+            .collect()
+    }
+}
+
+/// RPC loads of tables the cache doesn't have.
+#[derive(Debug, Default)]
+struct Loads {
+    /// Tables being loaded; the sender is dropped once the load is done.
+    in_flight: HashMap<Vec<u8>, watch::Receiver<()>>,
+    /// Tables the last load didn't return, and when it finished.
+    missing: HashMap<Vec<u8>, Instant>,
+}
+
+/// Lookup table contents shared between the account stream that updates them and
+/// the transaction stream that reads them. Clones share the same tables.
+#[derive(Debug, Clone)]
+pub struct LookupTableCache {
+    tables: Arc<RwLock<HashMap<Vec<u8>, Arc<LookupTable>>>>,
+    rpc: Option<Arc<RpcClient>>,
+    loads: Arc<Mutex<Loads>>,
+    missing_ttl: Duration,
+    load_timeout: Duration,
+}
+
+impl Default for LookupTableCache {
+    fn default() -> Self {
+        Self {
+            tables: Arc::default(),
+            rpc: None,
+            loads: Arc::default(),
+            missing_ttl: DEFAULT_MISSING_TTL,
+            load_timeout: DEFAULT_LOAD_TIMEOUT,
+        }
+    }
+}
+
+impl LookupTableCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads tables that transactions reference but the cache doesn't have (or has
+    /// an outdated copy of) through `rpc` in [`resolve_lookup_tables`].
+    pub fn with_rpc(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(Arc::new(rpc));
+        self
+    }
+
+    /// How long a table the RPC didn't return (or failed to load) is reported
+    /// missing without asking again. Defaults to 30s.
+    pub fn with_missing_ttl(mut self, ttl: Duration) -> Self {
+        self.missing_ttl = ttl;
+        self
+    }
+
+    /// Longest a transaction waits for its tables to load; the load goes on in the
+    /// background and serves later transactions. Defaults to 500ms.
+    pub fn with_load_timeout(mut self, timeout: Duration) -> Self {
+        self.load_timeout = timeout;
+        self
+    }
+
+    /// Accounts filter for every lookup table; add it to a `subscribe` request and
+    /// pass the updates to [`apply`](Self::apply).
+    pub fn account_filter() -> SubscribeRequestFilterAccounts {
+        SubscribeRequestFilterAccounts {
+            owner: vec![ADDRESS_LOOKUP_TABLE_PROGRAM_ID.to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// Applies an account update; other updates are ignored. Returns whether the
+    /// cache changed.
+    pub fn apply(&self, update: &SubscribeUpdate) -> bool {
+        match &update.update_oneof {
+            Some(UpdateOneof::Account(account)) => account
+                .account
+                .as_ref()
+                .is_some_and(|info| self.insert(account.slot, info)),
+            _ => false,
+        }
+    }
+
+    /// Stores the table in `info` unless a newer state is cached. Closed tables and
+    /// accounts of other programs are removed or ignored.
+    pub fn insert(&self, slot: u64, info: &SubscribeUpdateAccountInfo) -> bool {
+        let owner_matches =
+            bs58::encode(&info.owner).into_string() == ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+        let mut tables = self.tables.write().unwrap();
+        if tables
+            .get(&info.pubkey)
+            .is_some_and(|table| table.slot > slot)
+        {
+            return false;
+        }
+        match LookupTable::parse(info.pubkey.clone(), slot, &info.data) {
+            Some(table) if owner_matches && info.lamports > 0 => {
+                tables.insert(info.pubkey.clone(), Arc::new(table));
+                true
+            }
+            _ => tables.remove(&info.pubkey).is_some(),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Arc<LookupTable>> {
+        self.tables.read().unwrap().get(key).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Resolves the lookups of `message` from the cached tables.
+    pub fn resolve(&self, message: &Message) -> LoadedAddresses {
+        let tables = self.tables.read().unwrap();
+        let mut loaded = LoadedAddresses::default();
+        let mut readonly = Vec::new();
+        for lookup in &message.address_table_lookups {
+            let addresses = tables.get(&lookup.account_key).and_then(|table| {
+                let pick = |indexes: &[u8]| {
+                    indexes
+                        .iter()
+                        .map(|index| table.addresses.get(*index as usize).cloned())
+                        .collect::<Option<Vec<_>>>()
+                };
+                Some((
+                    pick(&lookup.writable_indexes)?,
+                    pick(&lookup.readonly_indexes)?,
+                ))
+            });
+            match addresses {
+                Some((writable, ro)) => {
+                    loaded.writable.extend(writable);
+                    readonly.extend(ro);
+                }
+                None => loaded.missing_tables.push(lookup.account_key.clone()),
+            }
+        }
+        loaded.readonly = readonly;
+        loaded
+    }
+
+    /// Fetches `keys` (base58) through JSON-RPC and stores the tables that exist.
+    /// Returns how many were stored.
+    pub async fn load(&self, rpc: &RpcClient, keys: &[String]) -> Result<usize, LaserstreamError> {
+        let result = rpc
+            .get_multiple_accounts(keys, CommitmentLevel::Processed, None)
+            .await?;
+        Ok(result
+            .value
+            .iter()
+            .flatten()
+            .filter(|info| self.insert(result.slot, info))
+            .count())
+    }
+
+    /// [`resolve`](Self::resolve), first loading missing tables through the
+    /// configured RPC client, if any, for up to the load timeout.
+    async fn resolve_or_load(&self, message: &Message) -> LoadedAddresses {
+        let loaded = self.resolve(message);
+        let Some(rpc) = &self.rpc else {
+            return loaded;
+        };
+        if loaded.is_complete() {
+            return loaded;
+        }
+        let mut waits = Vec::new();
+        let mut keys = HashSet::new();
+        {
+            let mut loads = self.loads.lock().unwrap_or_else(|e| e.into_inner());
+            let ttl = self.missing_ttl;
+            loads.missing.retain(|_, at| at.elapsed() < ttl);
+            for key in &loaded.missing_tables {
+                if loads.missing.contains_key(key) {
+                    continue;
+                }
+                match loads.in_flight.get(key) {
+                    Some(done) => waits.push(done.clone()),
+                    None => {
+                        keys.insert(key.clone());
+                    }
+                }
+            }
+            if !keys.is_empty() {
+                let (finished, done) = watch::channel(());
+                for key in &keys {
+                    loads.in_flight.insert(key.clone(), done.clone());
+                }
+                waits.push(done);
+                let cache = self.clone();
+                let rpc = rpc.clone();
+                tokio::spawn(async move {
+                    cache.load_missing(&rpc, keys).await;
+                    drop(finished);
+                });
+            }
+        }
+        if waits.is_empty() {
+            return loaded;
+        }
+        let all_done = futures::future::join_all(waits.iter_mut().map(|done| done.changed()));
+        if tokio::time::timeout(self.load_timeout, all_done)
+            .await
+            .is_err()
+        {
+            return loaded;
+        }
+        self.resolve(message)
+    }
+
+    /// Loads `keys` and remembers the ones that aren't tables as missing.
+    async fn load_missing(&self, rpc: &RpcClient, keys: HashSet<Vec<u8>>) {
+        let encoded: Vec<String> = keys
+            .iter()
+            .map(|key| bs58::encode(key).into_string())
+            .collect();
+        if let Err(e) = self.load(rpc, &encoded).await {
+            warn!(error = %e, tables = keys.len(), "Failed to load lookup tables");
+        }
+        let tables = self.tables.read().unwrap();
+        let mut loads = self.loads.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        for key in keys {
+            loads.in_flight.remove(&key);
+            if !tables.contains_key(&key) {
+                loads.missing.insert(key, now);
+            }
+        }
+    }
+}
+
+/// A preprocessed update with the addresses its transaction loads from lookup
+/// tables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedPreprocessedUpdate {
+    pub update: SubscribePreprocessedUpdate,
+    /// `None` for pings and pongs; empty for transactions without lookups.
+    pub loaded: Option<LoadedAddresses>,
+}
+
+/// Attaches resolved lookup-table addresses to each transaction of a
+/// `subscribe_preprocessed` stream. Keep `cache` updated from an accounts
+/// subscription with [`LookupTableCache::account_filter`], give it an RPC client
+/// with [`LookupTableCache::with_rpc`], or both.
+pub fn resolve_lookup_tables<S>(
+    stream: S,
+    cache: LookupTableCache,
+) -> impl Stream<Item = Result<ResolvedPreprocessedUpdate, LaserstreamError>>
+where
+    S: Stream<Item = Result<SubscribePreprocessedUpdate, LaserstreamError>>,
+{
+    stream! {
+        futures::pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            let update = match item {
+                Ok(update) => update,
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+            let loaded = match &update.update_oneof {
+                Some(subscribe_preprocessed_update::UpdateOneof::Transaction(tx)) => {
+                    let message = tx
+                        .transaction
+                        .as_ref()
+                        .and_then(|info| info.transaction.as_ref())
+                        .and_then(|tx| tx.message.as_ref());
+                    Some(match message {
+                        Some(message) => cache.resolve_or_load(message).await,
+                        None => LoadedAddresses::default(),
+                    })
+                }
+                _ => None,
+            };
+            yield Ok(ResolvedPreprocessedUpdate { update, loaded });
+        }
+    }
+}
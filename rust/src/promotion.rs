@@ -0,0 +1,244 @@
+//! Commitment promotions on a single PROCESSED stream.
+//!
+//! [`subscribe_with_promotions`] delivers data at PROCESSED and follows every slot
+//! through CONFIRMED and FINALIZED with an internal slot subscription, so one stream
+//! gives processed latency plus a [`PromotionEvent::Promoted`] for each commitment
+//! level a slot with delivered updates reaches.
+
+use crate::client::{subscribe_with_mode, SlotTrackingMode, StreamItem};
+use crate::slots::{update_slot, SlotEvent, SlotStage, SlotTracker};
+use crate::{LaserstreamConfig, LaserstreamError, StreamHandle};
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeUpdate,
+    SubscribeUpdateSlot,
+};
+use std::collections::BTreeMap;
+use tracing::{instrument, warn};
+
+/// Default number of unsettled slots tracked before the oldest is forgotten.
+const DEFAULT_MAX_PENDING_SLOTS: usize = 1024;
+
+/// Output of [`subscribe_with_promotions`].
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum PromotionEvent {
+    /// An update at PROCESSED commitment.
+    Update(SubscribeUpdate),
+    /// `slot` reached `commitment` (`Confirmed`, then `Finalized`). `updates`
+    /// identifies the updates delivered for it so far, in delivery order.
+    Promoted {
+        slot: u64,
+        commitment: CommitmentLevel,
+        updates: Vec<UpdateId>,
+    },
+    /// `slot` died or ended up off the finalized chain; its `updates` will never be
+    /// confirmed.
+    Dropped { slot: u64, updates: Vec<UpdateId> },
+}
+
+/// Identifies a delivered update within its slot.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UpdateId {
+    /// An account write.
+    Account { pubkey: Vec<u8>, write_version: u64 },
+    /// A transaction or transaction status.
+    Transaction { signature: Vec<u8> },
+    /// An entry, by its index in the slot.
+    Entry { index: u64 },
+    Block,
+    BlockMeta,
+}
+
+impl UpdateId {
+    /// Identifier of `update`; `None` for slot statuses, pings and pongs.
+    pub fn of(update: &SubscribeUpdate) -> Option<Self> {
+        Some(match update.update_oneof.as_ref()? {
+            UpdateOneof::Account(account) => {
+                let info = account.account.as_ref()?;
+                Self::Account {
+                    pubkey: info.pubkey.clone(),
+                    write_version: info.write_version,
+                }
+            }
+            UpdateOneof::Transaction(tx) => Self::Transaction {
+                signature: tx.transaction.as_ref()?.signature.clone(),
+            },
+            UpdateOneof::TransactionStatus(status) => Self::Transaction {
+                signature: status.signature.clone(),
+            },
+            UpdateOneof::Entry(entry) => Self::Entry { index: entry.index },
+            UpdateOneof::Block(_) => Self::Block,
+            UpdateOneof::BlockMeta(_) => Self::BlockMeta,
+            UpdateOneof::Slot(_) | UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => return None,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingSlot {
+    updates: Vec<UpdateId>,
+    confirmed: bool,
+}
+
+/// Collects the updates delivered per slot and turns slot statuses into promotions.
+///
+/// Only slots with at least one delivered update produce events. Slot updates matched
+/// by the request's own slot filters are passed through but not counted. A slot whose
+/// first update arrives after it was already confirmed gets its `Confirmed` promotion
+/// together with `Finalized`.
+#[derive(Debug)]
+pub struct PromotionTracker {
+    tracker: SlotTracker,
+    pending: BTreeMap<u64, PendingSlot>,
+    max_pending_slots: usize,
+}
+
+impl Default for PromotionTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PromotionTracker {
+    pub fn new() -> Self {
+        Self {
+            tracker: SlotTracker::new(),
+            pending: BTreeMap::new(),
+            max_pending_slots: DEFAULT_MAX_PENDING_SLOTS,
+        }
+    }
+
+    /// Caps how many unsettled slots are tracked. Past the cap the oldest slot is
+    /// forgotten without an event. Default: 1024
+    pub fn with_max_pending_slots(mut self, max: usize) -> Self {
+        self.max_pending_slots = max.max(1);
+        self
+    }
+
+    /// The fork tree fed by the slot statuses.
+    pub fn tracker(&self) -> &SlotTracker {
+        &self.tracker
+    }
+
+    /// Updates counted for `slot` that are not finalized or dropped yet.
+    pub fn pending(&self, slot: u64) -> usize {
+        self.pending.get(&slot).map_or(0, |p| p.updates.len())
+    }
+
+    /// Counts a delivered update against its slot.
+    pub fn record(&mut self, update: &SubscribeUpdate) {
+        if matches!(update.update_oneof, Some(UpdateOneof::Slot(_))) {
+            return;
+        }
+        if let Some(UpdateOneof::BlockMeta(_)) = &update.update_oneof {
+            self.tracker.apply(update);
+        }
+        let (Some(slot), Some(id)) = (update_slot(update), UpdateId::of(update)) else {
+            return;
+        };
+        // Data for a slot that is already settled has nothing left to wait for.
+        if let Some(info) = self.tracker.get(slot) {
+            if info.is_orphaned() || info.finalized.is_some() {
+                return;
+            }
+        }
+        self.pending.entry(slot).or_default().updates.push(id);
+        while self.pending.len() > self.max_pending_slots {
+            if let Some((slot, pending)) = self.pending.pop_first() {
+                warn!(slot, updates = pending.updates.len(), "Forgetting unsettled slot; are slot statuses arriving?");
+            }
+        }
+    }
+
+    /// Applies a slot status and returns the resulting promotions.
+    pub fn slot(&mut self, update: &SubscribeUpdateSlot) -> Vec<PromotionEvent> {
+        let mut events = Vec::new();
+        for event in self.tracker.update(update) {
+            match event {
+                SlotEvent::Stage {
+                    slot,
+                    stage: SlotStage::Confirmed,
+                } => {
+                    if let Some(pending) = self.pending.get_mut(&slot) {
+                        if !pending.confirmed {
+                            pending.confirmed = true;
+                            events.push(PromotionEvent::Promoted {
+                                slot,
+                                commitment: CommitmentLevel::Confirmed,
+                                updates: pending.updates.clone(),
+                            });
+                        }
+                    }
+                }
+                SlotEvent::Stage {
+                    slot,
+                    stage: SlotStage::Finalized,
+                } => {
+                    if let Some(pending) = self.pending.remove(&slot) {
+                        if !pending.confirmed {
+                            events.push(PromotionEvent::Promoted {
+                                slot,
+                                commitment: CommitmentLevel::Confirmed,
+                                updates: pending.updates.clone(),
+                            });
+                        }
+                        events.push(PromotionEvent::Promoted {
+                            slot,
+                            commitment: CommitmentLevel::Finalized,
+                            updates: pending.updates,
+                        });
+                    }
+                }
+                SlotEvent::Orphaned { slot, .. } => {
+                    if let Some(pending) = self.pending.remove(&slot) {
+                        events.push(PromotionEvent::Dropped {
+                            slot,
+                            updates: pending.updates,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Like [`subscribe`](crate::subscribe), but at PROCESSED commitment with promotion
+/// events for every slot that had updates.
+///
+/// The request's `commitment` is overridden. Each update is yielded as
+/// [`PromotionEvent::Update`] as soon as it arrives; `Promoted` follows when its slot
+/// is confirmed and again when it is finalized, or `Dropped` if the slot is abandoned.
+/// With replay enabled, updates re-delivered after a reconnect are counted again.
+#[instrument(skip(config, request))]
+pub fn subscribe_with_promotions(
+    config: LaserstreamConfig,
+    request: SubscribeRequest,
+) -> (
+    impl Stream<Item = Result<PromotionEvent, LaserstreamError>>,
+    StreamHandle,
+) {
+    let (inner, handle) = subscribe_with_mode(config, request, SlotTrackingMode::AllStatuses);
+    let events = stream! {
+        let mut promotions = PromotionTracker::new();
+        futures::pin_mut!(inner);
+        while let Some(item) = inner.next().await {
+            match item {
+                Ok(StreamItem::Update(update)) => {
+                    promotions.record(&update);
+                    yield Ok(PromotionEvent::Update(update));
+                }
+                Ok(StreamItem::InternalSlot(slot)) => {
+                    for event in promotions.slot(&slot) {
+                        yield Ok(event);
+                    }
+                }
+                Err(e) => yield Err(e),
+            }
+        }
+    };
+    (events, handle)
+}
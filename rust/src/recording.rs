@@ -0,0 +1,738 @@
+//! Recording subscription output to disk.
+//!
+//! A recording is a directory of segment files plus `manifest.json`. Each record in
+//! a segment is the receive time (unix microseconds, `u64` little endian) followed by
+//! the length-delimited protobuf `SubscribeUpdate`; segments are optionally zstd
+//! compressed as a whole. The manifest lists the segments with their slot ranges and
+//! the requests that produced them, so a recording can be read back with
+//! [`Recording`] and matched to what was subscribed.
+//!
+//! [`replay_from_files`] plays a recording back as a `subscribe` stream, optionally
+//! limited to a slot range, paced like the original and filtered by a request.
+
+use crate::client::StreamHandle;
+use crate::filter::RequestMatcher;
+use crate::slots::update_slot;
+use crate::LaserstreamError;
+use async_stream::stream;
+use base64::Engine;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{SubscribeRequest, SubscribeUpdate};
+use laserstream_core_proto::prost::Message;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+const MANIFEST: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+/// Records [`replay_from_files`] reads ahead of the consumer.
+const READ_AHEAD: usize = 1024;
+
+/// Options for [`Recorder`].
+#[derive(Debug, Clone)]
+pub struct RecorderOptions {
+    /// Uncompressed bytes written to a segment before the next one starts.
+    /// Default: 64 MiB
+    pub segment_bytes: u64,
+    /// zstd level segments are compressed with; `None` writes them uncompressed.
+    /// Default: `None`
+    pub zstd_level: Option<i32>,
+}
+
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self {
+            segment_bytes: 64 * 1024 * 1024,
+            zstd_level: None,
+        }
+    }
+}
+
+impl RecorderOptions {
+    /// Sets the segment size.
+    pub fn with_segment_bytes(mut self, bytes: u64) -> Self {
+        self.segment_bytes = bytes.max(1);
+        self
+    }
+
+    /// Compresses segments with zstd at `level`.
+    pub fn with_zstd(mut self, level: i32) -> Self {
+        self.zstd_level = Some(level);
+        self
+    }
+}
+
+/// One segment file of a recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub file: String,
+    /// Index of the segment's first record within the recording.
+    pub first_record: u64,
+    pub records: u64,
+    /// Uncompressed size.
+    pub bytes: u64,
+    /// Lowest and highest slot of the segment's updates; `None` if none had a slot.
+    pub min_slot: Option<u64>,
+    pub max_slot: Option<u64>,
+}
+
+/// A subscription request and the first record it applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub first_record: u64,
+    /// Protobuf encoding of the `SubscribeRequest`, base64.
+    pub request: String,
+}
+
+impl RecordedRequest {
+    pub fn decode(&self) -> Result<SubscribeRequest, LaserstreamError> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&self.request)
+            .map_err(|e| LaserstreamError::InvalidRecording(format!("request: {e}")))?;
+        SubscribeRequest::decode(bytes.as_slice())
+            .map_err(|e| LaserstreamError::InvalidRecording(format!("request: {e}")))
+    }
+}
+
+/// Contents of `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// `"zstd"` when segments are compressed.
+    pub compression: Option<String>,
+    pub requests: Vec<RecordedRequest>,
+    pub segments: Vec<SegmentInfo>,
+}
+
+impl Manifest {
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, LaserstreamError> {
+        let raw = fs::read(dir.as_ref().join(MANIFEST))?;
+        let manifest: Self = serde_json::from_slice(&raw)
+            .map_err(|e| LaserstreamError::InvalidRecording(format!("manifest: {e}")))?;
+        if manifest.version != FORMAT_VERSION {
+            return Err(LaserstreamError::InvalidRecording(format!(
+                "unsupported version {}",
+                manifest.version
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Total number of records.
+    pub fn records(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.records).sum()
+    }
+
+    /// Segments that may hold updates for slots in `from..=to`.
+    pub fn segments_for_slots(&self, from: u64, to: u64) -> impl Iterator<Item = &SegmentInfo> {
+        self.segments
+            .iter()
+            .filter(move |segment| match (segment.min_slot, segment.max_slot) {
+                (Some(min), Some(max)) => min <= to && max >= from,
+                _ => false,
+            })
+    }
+
+    fn save(&self, dir: &Path) -> io::Result<()> {
+        let tmp = dir.join(format!("{MANIFEST}.tmp"));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp, dir.join(MANIFEST))
+    }
+}
+
+enum SegmentWriter {
+    Plain(BufWriter<File>),
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+impl SegmentWriter {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(w) => w,
+            Self::Zstd(w) => w,
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        let mut file = match self {
+            Self::Plain(w) => w,
+            Self::Zstd(w) => w.finish()?,
+        };
+        file.flush()?;
+        file.get_ref().sync_all()
+    }
+}
+
+/// Writes updates into a recording directory.
+///
+/// The manifest is rewritten whenever a segment is closed, so a recording cut short
+/// by a crash still lists its earlier segments. Dropping the recorder finishes it
+/// like [`finish`](Self::finish), ignoring errors.
+pub struct Recorder {
+    dir: PathBuf,
+    options: RecorderOptions,
+    manifest: Manifest,
+    segment: Option<SegmentWriter>,
+    next_record: u64,
+}
+
+impl Recorder {
+    /// Starts a recording in `dir` (created if missing) for `request`.
+    pub fn create(
+        dir: impl AsRef<Path>,
+        request: &SubscribeRequest,
+        options: RecorderOptions,
+    ) -> Result<Self, LaserstreamError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        if dir.join(MANIFEST).exists() {
+            return Err(LaserstreamError::InvalidRecording(format!(
+                "{} already holds a recording",
+                dir.display()
+            )));
+        }
+        let mut recorder = Self {
+            dir,
+            manifest: Manifest {
+                version: FORMAT_VERSION,
+                compression: options.zstd_level.map(|_| "zstd".to_string()),
+                requests: Vec::new(),
+                segments: Vec::new(),
+            },
+            options,
+            segment: None,
+            next_record: 0,
+        };
+        recorder.set_request(request);
+        recorder.manifest.save(&recorder.dir)?;
+        Ok(recorder)
+    }
+
+    /// Records that `request` replaced the subscription (see
+    /// [`StreamHandle::write`](crate::StreamHandle::write)) from the next record on.
+    pub fn set_request(&mut self, request: &SubscribeRequest) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(request.encode_to_vec());
+        self.manifest.requests.push(RecordedRequest {
+            first_record: self.next_record,
+            request: encoded,
+        });
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Records `update` as received now.
+    pub fn record(&mut self, update: &SubscribeUpdate) -> Result<(), LaserstreamError> {
+        self.record_at(update, SystemTime::now())
+    }
+
+    pub fn record_at(
+        &mut self,
+        update: &SubscribeUpdate,
+        received_at: SystemTime,
+    ) -> Result<(), LaserstreamError> {
+        self.write(update_slot(update), &update.encode_to_vec(), received_at)
+    }
+
+    /// Records an already encoded `SubscribeUpdate` frame as received.
+    pub fn record_raw(
+        &mut self,
+        slot: Option<u64>,
+        frame: &[u8],
+        received_at: SystemTime,
+    ) -> Result<(), LaserstreamError> {
+        self.write(slot, frame, received_at)
+    }
+
+    /// Closes the current segment and writes the final manifest.
+    pub fn finish(mut self) -> Result<Manifest, LaserstreamError> {
+        self.close_segment()?;
+        self.manifest.save(&self.dir)?;
+        Ok(self.manifest.clone())
+    }
+
+    fn write(
+        &mut self,
+        slot: Option<u64>,
+        frame: &[u8],
+        received_at: SystemTime,
+    ) -> Result<(), LaserstreamError> {
+        if self.segment.is_none() {
+            self.open_segment()?;
+        }
+        let micros = received_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let mut record = Vec::with_capacity(frame.len() + 18);
+        record.extend_from_slice(&micros.to_le_bytes());
+        encode_varint(frame.len() as u64, &mut record);
+        record.extend_from_slice(frame);
+
+        let segment = self.segment.as_mut().expect("opened above");
+        segment.writer().write_all(&record)?;
+        let info = self.manifest.segments.last_mut().expect("opened above");
+        info.records += 1;
+        info.bytes += record.len() as u64;
+        if let Some(slot) = slot {
+            info.min_slot = Some(info.min_slot.map_or(slot, |min| min.min(slot)));
+            info.max_slot = Some(info.max_slot.map_or(slot, |max| max.max(slot)));
+        }
+        self.next_record += 1;
+        if info.bytes >= self.options.segment_bytes {
+            self.close_segment()?;
+            self.manifest.save(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    fn open_segment(&mut self) -> Result<(), LaserstreamError> {
+        let extension = if self.options.zstd_level.is_some() {
+            "bin.zst"
+        } else {
+            "bin"
+        };
+        let file = format!("segment-{:06}.{extension}", self.manifest.segments.len());
+        let writer = BufWriter::new(File::create(self.dir.join(&file))?);
+        self.segment = Some(match self.options.zstd_level {
+            Some(level) => SegmentWriter::Zstd(zstd::stream::write::Encoder::new(writer, level)?),
+            None => SegmentWriter::Plain(writer),
+        });
+        self.manifest.segments.push(SegmentInfo {
+            file,
+            first_record: self.next_record,
+            records: 0,
+            bytes: 0,
+            min_slot: None,
+            max_slot: None,
+        });
+        Ok(())
+    }
+
+    fn close_segment(&mut self) -> Result<(), LaserstreamError> {
+        if let Some(segment) = self.segment.take() {
+            segment.finish()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.close_segment().is_ok() {
+            let _ = self.manifest.save(&self.dir);
+        }
+    }
+}
+
+/// What [`record_stream`] hands its writer thread.
+#[allow(clippy::large_enum_variant)]
+enum RecorderOp {
+    Record(SubscribeUpdate, SystemTime),
+    Request(SubscribeRequest),
+}
+
+/// Passes a `subscribe` stream through unchanged while recording every update.
+///
+/// Returns the stream and a handle to use in place of `handle`: requests written to
+/// it are forwarded to the subscription and recorded with
+/// [`Recorder::set_request`]. Encoding, compression and file writes run on a
+/// blocking thread; updates wait in memory while it falls behind.
+///
+/// Every update is yielded. If a write fails, recording stops and the error is
+/// yielded once between updates; the stream itself goes on. An error finishing the
+/// recording is yielded when the stream ends.
+pub fn record_stream<S>(
+    stream: S,
+    handle: StreamHandle,
+    recorder: Recorder,
+) -> (
+    impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+    StreamHandle,
+)
+where
+    S: Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+{
+    let (recording_handle, mut requests) = StreamHandle::channel();
+    let stream = stream! {
+        let (ops, mut pending) = mpsc::unbounded_channel::<RecorderOp>();
+        let (failed_tx, mut failed) = mpsc::unbounded_channel();
+        let writer = tokio::task::spawn_blocking(move || {
+            let mut recorder = recorder;
+            while let Some(op) = pending.blocking_recv() {
+                let result = match op {
+                    RecorderOp::Record(update, received_at) => recorder.record_at(&update, received_at),
+                    RecorderOp::Request(request) => {
+                        recorder.set_request(&request);
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    let _ = failed_tx.send(e);
+                    return Ok(());
+                }
+            }
+            recorder.finish().map(|_| ())
+        });
+        let mut ops = Some(ops);
+
+        futures::pin_mut!(stream);
+        loop {
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(Ok(update)) => {
+                        if let Some(sender) = &ops {
+                            let _ = sender.send(RecorderOp::Record(update.clone(), SystemTime::now()));
+                        }
+                        yield Ok(update);
+                    }
+                    Some(Err(e)) => yield Err(e),
+                    None => break,
+                },
+                Some(request) = requests.recv() => {
+                    if let Some(sender) = &ops {
+                        let _ = sender.send(RecorderOp::Request(request.clone()));
+                    }
+                    if let Err(e) = handle.send(request) {
+                        yield Err(e);
+                    }
+                }
+            }
+            if let Ok(e) = failed.try_recv() {
+                ops = None;
+                yield Err(e);
+            }
+        }
+        drop(ops);
+        match writer.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => yield Err(e),
+            Err(e) => yield Err(LaserstreamError::Io(io::Error::other(e))),
+        }
+        if let Ok(e) = failed.try_recv() {
+            yield Err(e);
+        }
+    };
+    (stream, recording_handle)
+}
+
+/// A recorded update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub received_at: SystemTime,
+    pub update: SubscribeUpdate,
+}
+
+/// A recording opened for reading.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl Recording {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, LaserstreamError> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest = Manifest::load(&dir)?;
+        Ok(Self { dir, manifest })
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Every record, in recording order.
+    pub fn records(&self) -> impl Iterator<Item = Result<Record, LaserstreamError>> + '_ {
+        self.read_segments(self.manifest.segments.iter())
+    }
+
+    /// Records of the segments that may hold slots in `from..=to`; other slots in
+    /// those segments are included.
+    pub fn records_for_slots(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> impl Iterator<Item = Result<Record, LaserstreamError>> + '_ {
+        self.read_segments(self.manifest.segments_for_slots(from, to))
+    }
+
+    fn read_segments<'a>(
+        &'a self,
+        segments: impl Iterator<Item = &'a SegmentInfo> + 'a,
+    ) -> impl Iterator<Item = Result<Record, LaserstreamError>> + 'a {
+        segments.flat_map(move |segment| match self.open_segment(segment) {
+            Ok(reader) => SegmentRecords::new(reader, segment),
+            Err(e) => SegmentRecords::failed(e),
+        })
+    }
+
+    fn open_segment(
+        &self,
+        segment: &SegmentInfo,
+    ) -> Result<Box<dyn Read + Send>, LaserstreamError> {
+        let file = BufReader::new(File::open(self.dir.join(&segment.file))?);
+        Ok(match self.manifest.compression.as_deref() {
+            Some("zstd") => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+            Some(other) => {
+                return Err(LaserstreamError::InvalidRecording(format!(
+                    "unknown compression {other}"
+                )))
+            }
+            None => Box::new(file),
+        })
+    }
+}
+
+/// How fast [`replay_from_files`] yields records.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Pacing {
+    /// No delays.
+    #[default]
+    AsFastAsPossible,
+    /// Keep the gaps between receive times.
+    RealTime,
+    /// Gaps divided by the factor: `Speed(10.0)` plays ten times faster. A gap
+    /// too long to wait for is never reached.
+    Speed(f64),
+}
+
+/// Options for [`replay_from_files`].
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// First slot to yield; updates of earlier slots are skipped.
+    pub start_slot: Option<u64>,
+    /// Last slot to yield.
+    pub end_slot: Option<u64>,
+    pub pacing: Pacing,
+    /// Filters applied client-side, as the server would; `None` yields every
+    /// recorded update unchanged.
+    pub request: Option<SubscribeRequest>,
+}
+
+impl ReplayOptions {
+    pub fn with_start_slot(mut self, slot: u64) -> Self {
+        self.start_slot = Some(slot);
+        self
+    }
+
+    pub fn with_end_slot(mut self, slot: u64) -> Self {
+        self.end_slot = Some(slot);
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    pub fn with_request(mut self, request: SubscribeRequest) -> Self {
+        self.request = Some(request);
+        self
+    }
+}
+
+/// Plays the recording in `dir` back as a stream of the same type `subscribe`
+/// returns, so code written against `subscribe` runs unchanged offline.
+///
+/// Updates without a slot (pings, pongs) are not range-checked. A request written
+/// to the handle replaces the filter from then on. Errors opening the recording, an
+/// invalid request, or a corrupt record are yielded as errors; a corrupt record ends
+/// its segment.
+pub fn replay_from_files(
+    dir: impl AsRef<Path>,
+    options: ReplayOptions,
+) -> (
+    impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+    StreamHandle,
+) {
+    let dir = dir.as_ref().to_path_buf();
+    let (handle, mut requests) = StreamHandle::channel();
+    let stream = stream! {
+        let ranged = options.start_slot.is_some() || options.end_slot.is_some();
+        let (start, end) = (options.start_slot.unwrap_or(0), options.end_slot.unwrap_or(u64::MAX));
+        // File reads and decompression run on a blocking thread, which stops once
+        // the stream is dropped.
+        let (records_tx, mut records) = mpsc::channel(READ_AHEAD);
+        tokio::task::spawn_blocking(move || {
+            let recording = match Recording::open(&dir) {
+                Ok(recording) => recording,
+                Err(e) => {
+                    let _ = records_tx.blocking_send(Err(e));
+                    return;
+                }
+            };
+            // Segments without slotted updates still hold pings and pongs.
+            let records: Box<dyn Iterator<Item = _>> = if ranged {
+                Box::new(recording.records_for_slots(start, end))
+            } else {
+                Box::new(recording.records())
+            };
+            for record in records {
+                if records_tx.blocking_send(record).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut matcher = match options.request.as_ref().map(RequestMatcher::new).transpose() {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                yield Err(e);
+                return;
+            }
+        };
+        let speed = match options.pacing {
+            Pacing::AsFastAsPossible => None,
+            Pacing::RealTime => Some(1.0),
+            Pacing::Speed(speed) => Some(speed).filter(|speed| *speed > 0.0),
+        };
+        // Receive time of the first yielded record and when it was yielded.
+        let mut origin: Option<(SystemTime, Instant)> = None;
+
+        while let Some(record) = records.recv().await {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+            if update_slot(&record.update).is_some_and(|slot| slot < start || slot > end) {
+                continue;
+            }
+            while let Ok(request) = requests.try_recv() {
+                match RequestMatcher::new(&request) {
+                    Ok(new) => matcher = Some(new),
+                    Err(e) => yield Err(e),
+                }
+            }
+            let update = match &matcher {
+                Some(matcher) => match matcher.filter(record.update) {
+                    Some(update) => update,
+                    None => continue,
+                },
+                None => record.update,
+            };
+
+            if let Some(speed) = speed {
+                match origin {
+                    Some((recorded, started)) => {
+                        let gap = record.received_at.duration_since(recorded).unwrap_or_default();
+                        let deadline = Duration::try_from_secs_f64(gap.as_secs_f64() / speed)
+                            .ok()
+                            .and_then(|delay| started.checked_add(delay));
+                        match deadline {
+                            Some(deadline) => tokio::time::sleep_until(deadline).await,
+                            None => std::future::pending().await,
+                        }
+                    }
+                    None => origin = Some((record.received_at, Instant::now())),
+                }
+            }
+            yield Ok(update);
+        }
+    };
+    (stream, handle)
+}
+
+/// Reads the records of one segment.
+struct SegmentRecords {
+    reader: Option<Box<dyn Read + Send>>,
+    remaining: u64,
+    /// Uncompressed bytes the manifest says are left; bounds each record's length.
+    bytes: u64,
+    error: Option<LaserstreamError>,
+}
+
+impl SegmentRecords {
+    fn new(reader: Box<dyn Read + Send>, segment: &SegmentInfo) -> Self {
+        Self {
+            reader: Some(reader),
+            remaining: segment.records,
+            bytes: segment.bytes,
+            error: None,
+        }
+    }
+
+    fn failed(error: LaserstreamError) -> Self {
+        Self {
+            reader: None,
+            remaining: 0,
+            bytes: 0,
+            error: Some(error),
+        }
+    }
+
+    fn read(reader: &mut dyn Read, bytes: &mut u64) -> Result<Record, LaserstreamError> {
+        let mut micros = [0u8; 8];
+        reader.read_exact(&mut micros)?;
+        let len = decode_varint(reader)?;
+        // Don't let a corrupt length allocate more than the segment can hold.
+        if len > *bytes {
+            return Err(LaserstreamError::InvalidRecording(format!(
+                "record of {len} bytes with {} left in its segment",
+                *bytes
+            )));
+        }
+        let mut frame = Vec::new();
+        reader.take(len).read_to_end(&mut frame)?;
+        if frame.len() as u64 != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        *bytes = bytes.saturating_sub(len + 8);
+        let update = SubscribeUpdate::decode(frame.as_slice())
+            .map_err(|e| LaserstreamError::InvalidRecording(format!("update: {e}")))?;
+        Ok(Record {
+            received_at: UNIX_EPOCH + Duration::from_micros(u64::from_le_bytes(micros)),
+            update,
+        })
+    }
+}
+
+impl Iterator for SegmentRecords {
+    type Item = Result<Record, LaserstreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        if self.remaining == 0 {
+            return None;
+        }
+        let reader = self.reader.as_mut()?;
+        self.remaining -= 1;
+        let record = Self::read(reader.as_mut(), &mut self.bytes);
+        if record.is_err() {
+            // A torn or corrupt record ends the segment.
+            self.remaining = 0;
+        }
+        Some(record)
+    }
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_varint(reader: &mut dyn Read) -> Result<u64, LaserstreamError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(LaserstreamError::InvalidRecording(
+        "varint too long".to_string(),
+    ))
+}
@@ -0,0 +1,182 @@
+//! Rollback-aware consumption of processed-commitment streams.
+//!
+//! [`RollbackBuffer`] passes every update through immediately, so it can be applied
+//! optimistically, and keeps a reference to it until its slot is settled. When the
+//! slot dies or ends up off the finalized chain, the buffered updates come back in a
+//! [`ForkEvent::Rollback`] so they can be retracted; when it finalizes,
+//! [`ForkEvent::Rooted`] says they are final.
+//!
+//! The stream must carry slot updates for every stage: add
+//! [`SlotTracker::slot_filter`] to the request.
+
+use crate::slots::{update_slot, SlotEvent, SlotStage, SlotTracker};
+use crate::LaserstreamError;
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdate};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default number of unsettled slots buffered before the oldest is dropped.
+const DEFAULT_MAX_BUFFERED_SLOTS: usize = 1024;
+
+/// Output of [`RollbackBuffer`].
+#[derive(Debug, Clone)]
+pub enum ForkEvent {
+    /// An update as it arrived. `slot` is `None` only for updates without one.
+    Update {
+        slot: Option<u64>,
+        update: Arc<SubscribeUpdate>,
+    },
+    /// The processed tip switched forks. Advisory: the abandoned slots may still come
+    /// back; a [`ForkEvent::Rollback`] follows once one of them is definitely dead.
+    Reorg {
+        previous_tip: u64,
+        new_tip: u64,
+        abandoned: Vec<u64>,
+    },
+    /// `slot` died or was orphaned by finalization. `updates` are the updates already
+    /// delivered for it, in delivery order.
+    Rollback {
+        slot: u64,
+        updates: Vec<Arc<SubscribeUpdate>>,
+    },
+    /// `slot` is finalized; its updates are final and no longer buffered.
+    Rooted { slot: u64 },
+}
+
+/// Buffers delivered updates per slot until the slot is rooted or rolled back.
+#[derive(Debug)]
+pub struct RollbackBuffer {
+    tracker: SlotTracker,
+    pending: BTreeMap<u64, Vec<Arc<SubscribeUpdate>>>,
+    max_buffered_slots: usize,
+}
+
+impl Default for RollbackBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RollbackBuffer {
+    pub fn new() -> Self {
+        Self {
+            tracker: SlotTracker::new(),
+            pending: BTreeMap::new(),
+            max_buffered_slots: DEFAULT_MAX_BUFFERED_SLOTS,
+        }
+    }
+
+    /// Caps how many unsettled slots are buffered. Past the cap the oldest slot's
+    /// updates are dropped without a rollback, which only happens if slot statuses are
+    /// missing from the stream. Default: 1024
+    pub fn with_max_buffered_slots(mut self, max: usize) -> Self {
+        self.max_buffered_slots = max.max(1);
+        self
+    }
+
+    /// The fork tree fed by this buffer.
+    pub fn tracker(&self) -> &SlotTracker {
+        &self.tracker
+    }
+
+    /// Updates delivered for `slot` that are not settled yet.
+    pub fn buffered(&self, slot: u64) -> &[Arc<SubscribeUpdate>] {
+        self.pending.get(&slot).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Feeds one update and returns the resulting events, the update itself first.
+    pub fn push(&mut self, update: SubscribeUpdate) -> Vec<ForkEvent> {
+        let slot = update_slot(&update);
+        let update = Arc::new(update);
+        let mut events = vec![ForkEvent::Update {
+            slot,
+            update: update.clone(),
+        }];
+
+        if matches!(update.update_oneof, Some(UpdateOneof::Slot(_))) {
+            for event in self.tracker.apply(&update) {
+                self.settle(event, &mut events);
+            }
+            return events;
+        }
+        let Some(slot) = slot else {
+            return events;
+        };
+
+        match self.tracker.get(slot) {
+            Some(info) if info.is_orphaned() => {
+                // Late data for a slot that is already gone.
+                events.push(ForkEvent::Rollback {
+                    slot,
+                    updates: vec![update],
+                });
+            }
+            Some(info) if info.finalized.is_some() => {}
+            _ if self.tracker.is_on_finalized_chain(slot) == Some(true) => {}
+            _ => {
+                self.pending.entry(slot).or_default().push(update);
+                self.enforce_cap();
+            }
+        }
+        events
+    }
+
+    fn settle(&mut self, event: SlotEvent, events: &mut Vec<ForkEvent>) {
+        match event {
+            SlotEvent::Stage {
+                slot,
+                stage: SlotStage::Finalized,
+            } => {
+                self.pending.remove(&slot);
+                events.push(ForkEvent::Rooted { slot });
+            }
+            SlotEvent::Orphaned { slot, .. } => {
+                let updates = self.pending.remove(&slot).unwrap_or_default();
+                events.push(ForkEvent::Rollback { slot, updates });
+            }
+            SlotEvent::Reorg {
+                previous_tip,
+                new_tip,
+                abandoned,
+                ..
+            } => events.push(ForkEvent::Reorg {
+                previous_tip,
+                new_tip,
+                abandoned,
+            }),
+            SlotEvent::Stage { .. } => {}
+        }
+    }
+
+    fn enforce_cap(&mut self) {
+        while self.pending.len() > self.max_buffered_slots {
+            if let Some((slot, updates)) = self.pending.pop_first() {
+                warn!(slot, updates = updates.len(), "Dropping unsettled slot buffer; are slot updates subscribed?");
+            }
+        }
+    }
+}
+
+/// Wraps a `subscribe` stream in a [`RollbackBuffer`].
+pub fn rollback_aware<S>(stream: S) -> impl Stream<Item = Result<ForkEvent, LaserstreamError>>
+where
+    S: Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+{
+    stream! {
+        let mut buffer = RollbackBuffer::new();
+        futures::pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(update) => {
+                    for event in buffer.push(update) {
+                        yield Ok(event);
+                    }
+                }
+                Err(e) => yield Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Minimal Solana JSON-RPC client for the calls the SDK needs alongside the stream.
+
+use crate::LaserstreamError;
+use base64::Engine;
+use laserstream_core_proto::geyser::{CommitmentLevel, SubscribeUpdateAccountInfo};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Largest number of keys `getMultipleAccounts` accepts per call.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Result of a call made with `withContext`: the slot the node answered at.
+#[derive(Debug, Clone)]
+pub struct WithContext<T> {
+    pub slot: u64,
+    pub value: T,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Context {
+    slot: u64,
+}
+
+#[derive(Deserialize)]
+struct ContextResult<T> {
+    context: Context,
+    value: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount {
+    lamports: u64,
+    owner: String,
+    data: (String, String),
+    executable: bool,
+    rent_epoch: u64,
+}
+
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: UiAccount,
+}
+
+/// JSON-RPC client over HTTP.
+#[derive(Debug)]
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: String,
+    next_id: AtomicU64,
+}
+
+impl RpcClient {
+    /// Client for `url`, e.g. `https://mainnet.helius-rpc.com/?api-key=...`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Client reusing an existing `reqwest` client (timeouts, proxies, ...).
+    pub fn with_client(http: reqwest::Client, url: impl Into<String>) -> Self {
+        Self {
+            http,
+            url: url.into(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// `getSlot` at `commitment`.
+    pub async fn get_slot(&self, commitment: CommitmentLevel) -> Result<u64, LaserstreamError> {
+        self.call("getSlot", json!([{ "commitment": commitment_name(commitment) }]))
+            .await
+    }
+
+    /// `getProgramAccounts` with `withContext`, base64 data and the given filters
+    /// (`memcmp` / `dataSize` objects as the RPC expects them).
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &str,
+        filters: &[Value],
+        commitment: CommitmentLevel,
+        min_context_slot: Option<u64>,
+    ) -> Result<WithContext<Vec<SubscribeUpdateAccountInfo>>, LaserstreamError> {
+        let mut config = json!({
+            "encoding": "base64",
+            "commitment": commitment_name(commitment),
+            "withContext": true,
+        });
+        if !filters.is_empty() {
+            config["filters"] = Value::Array(filters.to_vec());
+        }
+        if let Some(slot) = min_context_slot {
+            config["minContextSlot"] = json!(slot);
+        }
+        let result: ContextResult<Vec<KeyedAccount>> = self
+            .call("getProgramAccounts", json!([program_id, config]))
+            .await?;
+        let value = result
+            .value
+            .into_iter()
+            .map(|keyed| account_info(&keyed.pubkey, keyed.account))
+            .collect::<Result<_, _>>()?;
+        Ok(WithContext {
+            slot: result.context.slot,
+            value,
+        })
+    }
+
+    /// `getMultipleAccounts` for any number of keys, batched. `None` for accounts that
+    /// don't exist. The returned slot is the lowest any batch was answered at.
+    pub async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[String],
+        commitment: CommitmentLevel,
+        min_context_slot: Option<u64>,
+    ) -> Result<WithContext<Vec<Option<SubscribeUpdateAccountInfo>>>, LaserstreamError> {
+        let mut slot = u64::MAX;
+        let mut value = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let mut config = json!({
+                "encoding": "base64",
+                "commitment": commitment_name(commitment),
+            });
+            if let Some(slot) = min_context_slot {
+                config["minContextSlot"] = json!(slot);
+            }
+            let result: ContextResult<Vec<Option<UiAccount>>> = self
+                .call("getMultipleAccounts", json!([chunk, config]))
+                .await?;
+            slot = slot.min(result.context.slot);
+            for (pubkey, account) in chunk.iter().zip(result.value) {
+                value.push(account.map(|a| account_info(pubkey, a)).transpose()?);
+            }
+        }
+        if pubkeys.is_empty() {
+            slot = min_context_slot.unwrap_or(0);
+        }
+        Ok(WithContext { slot, value })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, LaserstreamError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: Response<T> = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(LaserstreamError::Rpc(format!(
+                "{method} failed ({}): {}",
+                error.code, error.message
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(LaserstreamError::Rpc(format!("{method} returned no result"))),
+        }
+    }
+}
+
+fn commitment_name(commitment: CommitmentLevel) -> &'static str {
+    match commitment {
+        CommitmentLevel::Processed => "processed",
+        CommitmentLevel::Confirmed => "confirmed",
+        CommitmentLevel::Finalized => "finalized",
+    }
+}
+
+fn decode_pubkey(value: &str) -> Result<Vec<u8>, LaserstreamError> {
+    bs58::decode(value)
+        .into_vec()
+        .map_err(|e| LaserstreamError::Rpc(format!("invalid pubkey {value}: {e}")))
+}
+
+/// Converts an RPC account to the stream's account type. `write_version` is left at 0;
+/// the RPC has no equivalent.
+fn account_info(pubkey: &str, account: UiAccount) -> Result<SubscribeUpdateAccountInfo, LaserstreamError> {
+    let (data, encoding) = account.data;
+    if encoding != "base64" {
+        return Err(LaserstreamError::Rpc(format!("unexpected account encoding {encoding}")));
+    }
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| LaserstreamError::Rpc(format!("invalid account data for {pubkey}: {e}")))?;
+    Ok(SubscribeUpdateAccountInfo {
+        pubkey: decode_pubkey(pubkey)?,
+        lamports: account.lamports,
+        owner: decode_pubkey(&account.owner)?,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data,
+        write_version: 0,
+        txn_signature: None,
+    })
+}
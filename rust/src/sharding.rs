@@ -0,0 +1,383 @@
+//! Sharded subscriptions for account sets too large for a single filter or stream.
+//!
+//! Explicit `account` / `account_include` lists are partitioned across several
+//! connections with rendezvous hashing, so a pubkey always lands on the same shard
+//! and growing the shard count only moves the keys the new shards take over.
+//! Filter names are kept as-is on every shard, so routing by `update.filters`
+//! works exactly as with a single [`subscribe`] call.
+
+use crate::{subscribe, LaserstreamConfig, LaserstreamError, StreamHandle};
+use async_stream::stream;
+use futures::stream::SelectAll;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeUpdate,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, instrument, warn};
+
+type ShardStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, LaserstreamError>> + Send>>;
+
+/// Options for [`subscribe_sharded`].
+#[derive(Debug, Clone)]
+pub struct ShardingOptions {
+    /// Largest explicit pubkey list a single filter may carry on one connection.
+    /// Match it to the endpoint's per-filter limit. Default: 10_000
+    pub max_accounts_per_filter: usize,
+    /// Minimum number of connections, even if the lists would fit in fewer. Default: 1
+    pub min_shards: usize,
+    /// Number of recent account/transaction updates remembered to suppress duplicates
+    /// delivered by more than one shard. Default: 100_000
+    pub dedup_window: usize,
+}
+
+impl Default for ShardingOptions {
+    fn default() -> Self {
+        Self {
+            max_accounts_per_filter: 10_000,
+            min_shards: 1,
+            dedup_window: 100_000,
+        }
+    }
+}
+
+impl ShardingOptions {
+    /// Sets the per-filter pubkey limit.
+    pub fn with_max_accounts_per_filter(mut self, max: usize) -> Self {
+        self.max_accounts_per_filter = max;
+        self
+    }
+
+    /// Sets the minimum number of connections.
+    pub fn with_min_shards(mut self, shards: usize) -> Self {
+        self.min_shards = shards;
+        self
+    }
+
+    /// Sets the duplicate-suppression window.
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.dedup_window = window;
+        self
+    }
+}
+
+/// Splits `request` into one request per shard.
+///
+/// Filters with an explicit `account` (accounts) or `account_include` (transactions,
+/// transactions_status) list are split by pubkey; every other field of those filters
+/// (owners, memcmp, `account_required`, ...) is copied to each shard that receives part
+/// of the list. A shard only gets a filter if at least one of its pubkeys landed there,
+/// because an empty list would match everything. Filters without a list, and all slot,
+/// block, block-meta and entry filters, go to shard 0 only.
+///
+/// Returns at least `min_shards` requests, adding shards until no list exceeds
+/// `max_accounts_per_filter`.
+pub fn split_request(
+    request: &SubscribeRequest,
+    options: &ShardingOptions,
+) -> Vec<SubscribeRequest> {
+    let max = options.max_accounts_per_filter.max(1);
+    let largest = request
+        .accounts
+        .values()
+        .map(|f| f.account.len())
+        .chain(request.transactions.values().map(|f| f.account_include.len()))
+        .chain(request.transactions_status.values().map(|f| f.account_include.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut shards = options.min_shards.max(largest.div_ceil(max)).max(1);
+    loop {
+        let split = split_into(request, shards);
+        let fits = split.iter().all(|r| {
+            r.accounts.values().all(|f| f.account.len() <= max)
+                && r.transactions.values().all(|f| f.account_include.len() <= max)
+                && r.transactions_status.values().all(|f| f.account_include.len() <= max)
+        });
+        if fits {
+            return split;
+        }
+        shards += 1;
+    }
+}
+
+fn split_into(request: &SubscribeRequest, shards: usize) -> Vec<SubscribeRequest> {
+    let mut out: Vec<SubscribeRequest> = (0..shards)
+        .map(|_| SubscribeRequest {
+            commitment: request.commitment,
+            accounts_data_slice: request.accounts_data_slice.clone(),
+            from_slot: request.from_slot,
+            ..Default::default()
+        })
+        .collect();
+
+    for (name, filter) in &request.accounts {
+        if filter.account.is_empty() {
+            out[0].accounts.insert(name.clone(), filter.clone());
+            continue;
+        }
+        for (shard, keys) in partition(&filter.account, shards) {
+            let mut part = filter.clone();
+            part.account = keys;
+            out[shard].accounts.insert(name.clone(), part);
+        }
+    }
+    for (name, filter) in &request.transactions {
+        if filter.account_include.is_empty() {
+            out[0].transactions.insert(name.clone(), filter.clone());
+            continue;
+        }
+        for (shard, keys) in partition(&filter.account_include, shards) {
+            let mut part = filter.clone();
+            part.account_include = keys;
+            out[shard].transactions.insert(name.clone(), part);
+        }
+    }
+    for (name, filter) in &request.transactions_status {
+        if filter.account_include.is_empty() {
+            out[0].transactions_status.insert(name.clone(), filter.clone());
+            continue;
+        }
+        for (shard, keys) in partition(&filter.account_include, shards) {
+            let mut part = filter.clone();
+            part.account_include = keys;
+            out[shard].transactions_status.insert(name.clone(), part);
+        }
+    }
+
+    out[0].slots = request.slots.clone();
+    out[0].blocks = request.blocks.clone();
+    out[0].blocks_meta = request.blocks_meta.clone();
+    out[0].entry = request.entry.clone();
+    out
+}
+
+/// Groups keys by shard, dropping duplicates and shards that got no keys.
+fn partition(keys: &[String], shards: usize) -> Vec<(usize, Vec<String>)> {
+    let mut by_shard: HashMap<usize, BTreeSet<&String>> = HashMap::new();
+    for key in keys {
+        by_shard.entry(shard_for(key, shards)).or_default().insert(key);
+    }
+    by_shard
+        .into_iter()
+        .map(|(shard, keys)| (shard, keys.into_iter().cloned().collect()))
+        .collect()
+}
+
+/// Rendezvous (highest random weight) hashing: stable per key, and adding a shard only
+/// moves the keys that now score highest on it.
+fn shard_for(key: &str, shards: usize) -> usize {
+    (0..shards)
+        .max_by_key(|shard| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            shard.hash(&mut hasher);
+            hasher.finish()
+        })
+        .unwrap_or(0)
+}
+
+#[derive(Hash, PartialEq, Eq, Clone)]
+enum UpdateKey {
+    Account { pubkey: Vec<u8>, slot: u64, write_version: u64 },
+    Transaction { signature: Vec<u8>, slot: u64 },
+    TransactionStatus { signature: Vec<u8>, slot: u64 },
+}
+
+impl UpdateKey {
+    fn of(update: &SubscribeUpdate) -> Option<Self> {
+        match update.update_oneof.as_ref()? {
+            UpdateOneof::Account(a) => {
+                let info = a.account.as_ref()?;
+                Some(Self::Account {
+                    pubkey: info.pubkey.clone(),
+                    slot: a.slot,
+                    write_version: info.write_version,
+                })
+            }
+            UpdateOneof::Transaction(t) => Some(Self::Transaction {
+                signature: t.transaction.as_ref()?.signature.clone(),
+                slot: t.slot,
+            }),
+            UpdateOneof::TransactionStatus(t) => Some(Self::TransactionStatus {
+                signature: t.signature.clone(),
+                slot: t.slot,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Suppresses updates already delivered by another shard. A repeat that matched filter
+/// names not seen before is let through carrying only those names.
+struct Deduplicator {
+    seen: HashMap<UpdateKey, HashSet<String>>,
+    order: VecDeque<UpdateKey>,
+    window: usize,
+}
+
+impl Deduplicator {
+    fn new(window: usize) -> Self {
+        Self {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            window: window.max(1),
+        }
+    }
+
+    fn admit(&mut self, mut update: SubscribeUpdate) -> Option<SubscribeUpdate> {
+        let Some(key) = UpdateKey::of(&update) else {
+            return Some(update);
+        };
+        match self.seen.get_mut(&key) {
+            Some(delivered) => {
+                update.filters.retain(|f| !delivered.contains(f));
+                if update.filters.is_empty() {
+                    return None;
+                }
+                delivered.extend(update.filters.iter().cloned());
+            }
+            None => {
+                self.seen
+                    .insert(key.clone(), update.filters.iter().cloned().collect());
+                self.order.push_back(key);
+                while self.order.len() > self.window {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.seen.remove(&oldest);
+                    }
+                }
+            }
+        }
+        Some(update)
+    }
+}
+
+struct ShardSet {
+    config: LaserstreamConfig,
+    options: ShardingOptions,
+    handles: Vec<StreamHandle>,
+    new_streams: mpsc::UnboundedSender<ShardStream>,
+}
+
+impl ShardSet {
+    fn spawn_shard(&mut self, request: SubscribeRequest) {
+        let (stream, handle) = subscribe(self.config.clone(), request);
+        self.handles.push(handle);
+        let _ = self.new_streams.send(Box::pin(stream));
+    }
+}
+
+/// Handle for a sharded subscription.
+#[derive(Clone)]
+pub struct ShardedStreamHandle {
+    shards: Arc<Mutex<ShardSet>>,
+}
+
+impl ShardedStreamHandle {
+    /// Replaces the subscription on every shard, re-partitioning the pubkey lists.
+    ///
+    /// Opens additional connections if the new lists need more shards; existing shards
+    /// are never closed, they just receive a smaller share. Keys that move to a newly
+    /// opened shard start streaming from the current slot on that connection.
+    ///
+    /// Every shard is written even if an earlier one fails, so a failure leaves only
+    /// the failed shards on their previous request. They are listed in
+    /// [`LaserstreamError::ShardWrite`]; a shard fails only once its stream has ended.
+    pub async fn write(&self, request: SubscribeRequest) -> Result<(), LaserstreamError> {
+        let mut shards = self.shards.lock().await;
+        // Existing shards stay open, so the split never uses fewer.
+        let options = shards.options.clone().with_min_shards(shards.handles.len());
+        let split = split_request(&request, &options);
+        if split.len() > shards.handles.len() {
+            info!(from = shards.handles.len(), to = split.len(), "Growing sharded subscription");
+        }
+        let mut failed = Vec::new();
+        let mut first_error = None;
+        for (index, shard_request) in split.into_iter().enumerate() {
+            match shards.handles.get(index) {
+                Some(handle) => {
+                    if let Err(e) = handle.write(shard_request).await {
+                        warn!(shard = index, error = %e, "Sharded write failed");
+                        failed.push(index);
+                        first_error.get_or_insert(e);
+                    }
+                }
+                None => shards.spawn_shard(shard_request),
+            }
+        }
+        match first_error {
+            Some(error) => Err(LaserstreamError::ShardWrite {
+                shards: failed,
+                error: Box::new(error),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of connections currently open.
+    pub async fn shard_count(&self) -> usize {
+        self.shards.lock().await.handles.len()
+    }
+}
+
+/// Like [`subscribe`], but spreads large explicit pubkey lists over several connections
+/// (see [`split_request`]) and merges the shards back into one stream.
+///
+/// Each shard reconnects and replays independently. Account and transaction updates
+/// delivered by more than one shard (a transaction touching keys on two shards, or an
+/// account matched by filters on different shards) are yielded once per filter name.
+#[instrument(skip(config, request, options))]
+pub fn subscribe_sharded(
+    config: LaserstreamConfig,
+    request: SubscribeRequest,
+    options: ShardingOptions,
+) -> (
+    impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+    ShardedStreamHandle,
+) {
+    let (new_streams, mut new_streams_rx) = mpsc::unbounded_channel();
+    let mut dedup = Deduplicator::new(options.dedup_window);
+    let mut shards = ShardSet {
+        config,
+        options,
+        handles: Vec::new(),
+        new_streams,
+    };
+    for shard_request in split_request(&request, &shards.options) {
+        shards.spawn_shard(shard_request);
+    }
+    info!(shards = shards.handles.len(), "Starting sharded subscription");
+
+    let handle = ShardedStreamHandle {
+        shards: Arc::new(Mutex::new(shards)),
+    };
+    let merged = stream! {
+        let mut active: SelectAll<ShardStream> = SelectAll::new();
+        let mut accepting = true;
+        loop {
+            tokio::select! {
+                shard = new_streams_rx.recv(), if accepting => match shard {
+                    Some(shard) => active.push(shard),
+                    None => accepting = false,
+                },
+                item = active.next(), if !active.is_empty() => match item {
+                    Some(Ok(update)) => {
+                        if let Some(update) = dedup.admit(update) {
+                            yield Ok(update);
+                        }
+                    }
+                    Some(Err(e)) => yield Err(e),
+                    None => {}
+                },
+                else => break,
+            }
+        }
+    };
+
+    (merged, handle)
+}
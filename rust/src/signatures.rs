@@ -0,0 +1,374 @@
+//! Signature confirmation over a shared `transactions_status` subscription.
+//!
+//! [`SignatureWaitlist`] keeps one `transactions_status` filter per awaited signature
+//! and resolves waiters once the signature's slot reaches the commitment they asked
+//! for. [`LaserstreamClient::await_signature`](crate::LaserstreamClient::await_signature)
+//! runs it on a single PROCESSED subscription shared by all concurrent waits, adding
+//! and removing filters through [`StreamHandle`] as waits start and end. Changes
+//! made while a write is pending go out with it.
+
+use crate::client::{subscribe_with_mode, SlotTrackingMode, StreamItem};
+use crate::slots::{SlotEvent, SlotStage, SlotTracker};
+use crate::transaction::TransactionFailure;
+use crate::{LaserstreamConfig, StreamHandle};
+use futures::StreamExt;
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdateSlot,
+};
+use laserstream_core_proto::solana::storage::confirmed_block::TransactionError;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// Prefix of the filter names the waitlist adds.
+const FILTER_PREFIX: &str = "await-signature:";
+
+/// Where an awaited signature landed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureStatus {
+    pub signature: Vec<u8>,
+    pub slot: u64,
+    /// Position of the transaction in its block.
+    pub index: u64,
+    pub err: Option<TransactionError>,
+    /// Commitment the slot had reached when the wait resolved.
+    pub commitment: CommitmentLevel,
+}
+
+impl SignatureStatus {
+    pub fn is_success(&self) -> bool {
+        self.err.is_none()
+    }
+
+    pub fn error(&self) -> Option<TransactionFailure<'_>> {
+        self.err
+            .as_ref()
+            .map(|err| TransactionFailure { raw: &err.err })
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    commitment: CommitmentLevel,
+    sender: oneshot::Sender<SignatureStatus>,
+}
+
+#[derive(Debug, Clone)]
+struct Landed {
+    slot: u64,
+    index: u64,
+    err: Option<TransactionError>,
+}
+
+/// Awaited signatures and where they landed.
+///
+/// Feed it the updates of a PROCESSED subscription that carries [`request`](Self::request)
+/// and every slot status. A signature whose slot is orphaned goes back to waiting.
+#[derive(Debug, Default)]
+pub struct SignatureWaitlist {
+    waiters: HashMap<Vec<u8>, Vec<Waiter>>,
+    landed: HashMap<Vec<u8>, Landed>,
+    by_slot: BTreeMap<u64, Vec<Vec<u8>>>,
+    slots: SlotTracker,
+    next_id: u64,
+}
+
+impl SignatureWaitlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of signatures being awaited.
+    pub fn len(&self) -> usize {
+        self.waiters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    /// Adds a waiter. Returns its id (for [`cancel`](Self::cancel)), the receiver
+    /// it resolves through, and whether the signature is new, i.e. the request
+    /// changed.
+    pub fn register(
+        &mut self,
+        signature: &[u8],
+        commitment: CommitmentLevel,
+    ) -> (u64, oneshot::Receiver<SignatureStatus>, bool) {
+        let (sender, receiver) = oneshot::channel();
+        let id = self.next_id;
+        self.next_id += 1;
+        let waiters = self.waiters.entry(signature.to_vec()).or_default();
+        let added = waiters.is_empty();
+        waiters.push(Waiter {
+            id,
+            commitment,
+            sender,
+        });
+        self.resolve(signature);
+        (id, receiver, added)
+    }
+
+    /// Removes a waiter that gave up. Returns whether the signature is no longer
+    /// awaited, i.e. the request changed.
+    pub fn cancel(&mut self, signature: &[u8], id: u64) -> bool {
+        let Some(waiters) = self.waiters.get_mut(signature) else {
+            return false;
+        };
+        waiters.retain(|waiter| waiter.id != id);
+        if !waiters.is_empty() {
+            return false;
+        }
+        self.forget(signature);
+        true
+    }
+
+    /// Request with one `transactions_status` filter per awaited signature.
+    pub fn request(&self) -> SubscribeRequest {
+        let mut request = SubscribeRequest {
+            commitment: Some(CommitmentLevel::Processed as i32),
+            ..Default::default()
+        };
+        for signature in self.waiters.keys() {
+            let signature = bs58::encode(signature).into_string();
+            request.transactions_status.insert(
+                format!("{FILTER_PREFIX}{signature}"),
+                SubscribeRequestFilterTransactions {
+                    signature: Some(signature),
+                    ..Default::default()
+                },
+            );
+        }
+        request
+    }
+
+    /// Records transaction statuses (and `transactions` updates) of awaited
+    /// signatures and slot updates.
+    pub fn apply(&mut self, update: &SubscribeUpdate) {
+        match &update.update_oneof {
+            Some(UpdateOneof::TransactionStatus(status)) => self.landed_in(
+                &status.signature,
+                status.slot,
+                status.index,
+                status.err.clone(),
+            ),
+            Some(UpdateOneof::Transaction(tx)) => {
+                if let Some(info) = &tx.transaction {
+                    let err = info.meta.as_ref().and_then(|meta| meta.err.clone());
+                    self.landed_in(&info.signature, tx.slot, info.index, err);
+                }
+            }
+            Some(UpdateOneof::Slot(slot)) => self.slot(slot),
+            _ => {}
+        }
+    }
+
+    /// Records a slot status.
+    pub fn slot(&mut self, update: &SubscribeUpdateSlot) {
+        for event in self.slots.update(update) {
+            match event {
+                SlotEvent::Stage {
+                    slot,
+                    stage: SlotStage::Confirmed | SlotStage::Finalized,
+                } => {
+                    for signature in self.by_slot.get(&slot).cloned().unwrap_or_default() {
+                        self.resolve(&signature);
+                    }
+                }
+                SlotEvent::Orphaned { slot, .. } => {
+                    for signature in self.by_slot.remove(&slot).unwrap_or_default() {
+                        if self.landed.get(&signature).is_some_and(|l| l.slot == slot) {
+                            self.landed.remove(&signature);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(finalized) = self.slots.finalized_slot() {
+            self.by_slot = self.by_slot.split_off(&finalized);
+        }
+    }
+
+    fn landed_in(
+        &mut self,
+        signature: &[u8],
+        slot: u64,
+        index: u64,
+        err: Option<TransactionError>,
+    ) {
+        if !self.waiters.contains_key(signature)
+            || self.slots.get(slot).is_some_and(|info| info.is_orphaned())
+        {
+            return;
+        }
+        self.landed
+            .insert(signature.to_vec(), Landed { slot, index, err });
+        self.by_slot
+            .entry(slot)
+            .or_default()
+            .push(signature.to_vec());
+        self.resolve(signature);
+    }
+
+    /// Commitment the slot of a landed signature has reached.
+    fn commitment(&self, slot: u64) -> CommitmentLevel {
+        let info = self.slots.get(slot);
+        if info.is_some_and(|info| info.reached(SlotStage::Finalized).is_some())
+            || (info.is_none() && self.slots.finalized_slot() >= Some(slot))
+        {
+            CommitmentLevel::Finalized
+        } else if info.is_some_and(|info| info.reached(SlotStage::Confirmed).is_some()) {
+            CommitmentLevel::Confirmed
+        } else {
+            CommitmentLevel::Processed
+        }
+    }
+
+    /// Resolves the waiters of `signature` whose commitment has been reached.
+    fn resolve(&mut self, signature: &[u8]) {
+        let Some(landed) = self.landed.get(signature).cloned() else {
+            return;
+        };
+        let reached = self.commitment(landed.slot);
+        let Some(waiters) = self.waiters.get_mut(signature) else {
+            return;
+        };
+        let (ready, waiting): (Vec<Waiter>, Vec<Waiter>) = waiters
+            .drain(..)
+            .partition(|waiter| rank(waiter.commitment) <= rank(reached));
+        *waiters = waiting;
+        for waiter in ready {
+            let _ = waiter.sender.send(SignatureStatus {
+                signature: signature.to_vec(),
+                slot: landed.slot,
+                index: landed.index,
+                err: landed.err.clone(),
+                commitment: reached,
+            });
+        }
+        if waiters.is_empty() {
+            self.forget(signature);
+        }
+    }
+
+    fn forget(&mut self, signature: &[u8]) {
+        self.waiters.remove(signature);
+        self.landed.remove(signature);
+    }
+}
+
+fn rank(commitment: CommitmentLevel) -> u8 {
+    match commitment {
+        CommitmentLevel::Processed => 0,
+        CommitmentLevel::Confirmed => 1,
+        CommitmentLevel::Finalized => 2,
+    }
+}
+
+/// The shared subscription behind `await_signature`.
+pub(crate) struct SignatureSubscription {
+    waitlist: Arc<Mutex<SignatureWaitlist>>,
+    writes: WriteScheduler,
+    /// Dropped with the subscription, which ends the task driving the stream.
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl SignatureSubscription {
+    /// Starts the subscription on the current Tokio runtime.
+    pub(crate) fn start(config: LaserstreamConfig) -> Self {
+        let waitlist = Arc::new(Mutex::new(SignatureWaitlist::new()));
+        let request = waitlist.lock().unwrap().request();
+        let (stream, handle) = subscribe_with_mode(config, request, SlotTrackingMode::AllStatuses);
+        let (shutdown, mut stopped) = oneshot::channel::<()>();
+        let writes = WriteScheduler::start(handle, waitlist.clone());
+        let driven = waitlist.clone();
+        let writer = writes.clone();
+        tokio::spawn(async move {
+            futures::pin_mut!(stream);
+            loop {
+                let item = tokio::select! {
+                    item = stream.next() => item,
+                    _ = &mut stopped => break,
+                };
+                let mut waitlist = driven.lock().unwrap();
+                let awaited = waitlist.len();
+                match item {
+                    Some(Ok(StreamItem::Update(update))) => waitlist.apply(&update),
+                    Some(Ok(StreamItem::InternalSlot(slot))) => waitlist.slot(&slot),
+                    Some(Err(e)) => warn!(error = %e, "Signature subscription error"),
+                    None => break,
+                }
+                // Drop the filters of signatures that resolved.
+                if waitlist.len() != awaited {
+                    writer.schedule();
+                }
+            }
+        });
+        Self {
+            waitlist,
+            writes,
+            _shutdown: shutdown,
+        }
+    }
+
+    pub(crate) fn register(
+        &self,
+        signature: &[u8],
+        commitment: CommitmentLevel,
+    ) -> (u64, oneshot::Receiver<SignatureStatus>) {
+        let mut waitlist = self.waitlist.lock().unwrap();
+        let (id, receiver, added) = waitlist.register(signature, commitment);
+        if added {
+            self.writes.schedule();
+        }
+        (id, receiver)
+    }
+
+    pub(crate) fn cancel(&self, signature: &[u8], id: u64) {
+        let mut waitlist = self.waitlist.lock().unwrap();
+        if waitlist.cancel(signature, id) {
+            self.writes.schedule();
+        }
+    }
+}
+
+/// Sends the waitlist's filters from a background task. A write takes every change
+/// made up to when it runs, so a burst of waits costs one request, not one each.
+#[derive(Clone)]
+struct WriteScheduler {
+    /// Whether a write is already scheduled.
+    pending: Arc<AtomicBool>,
+    /// Wakes the writer, which ends once every sender is dropped.
+    changed: mpsc::UnboundedSender<()>,
+}
+
+impl WriteScheduler {
+    fn start(handle: StreamHandle, waitlist: Arc<Mutex<SignatureWaitlist>>) -> Self {
+        let (changed, mut changes) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicBool::new(false));
+        let scheduled = pending.clone();
+        tokio::spawn(async move {
+            while changes.recv().await.is_some() {
+                // Let the other waits of a burst register first.
+                tokio::task::yield_now().await;
+                scheduled.store(false, Ordering::Release);
+                let request = waitlist.lock().unwrap().request();
+                if let Err(e) = handle.send(request) {
+                    warn!(error = %e, "Failed to update signature filters");
+                }
+            }
+        });
+        Self { pending, changed }
+    }
+
+    fn schedule(&self) {
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            let _ = self.changed.send(());
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Skipped slots and forks from the slot stream.
+//!
+//! Each finalized slot names its parent, so every slot between the two was skipped:
+//! its leader produced no block, or produced one on a fork that was abandoned.
+//! [`SkipDetector`] turns the parent links of slot updates and block metas into
+//! [`SkipEvent::SlotSkipped`] once the slot after the gap is finalized, measures
+//! the depth of every fork switch, and with a [`LeaderSchedule`] attributes produced
+//! and skipped slots to validators. [`subscribe_skips`] runs it on its own
+//! subscription.
+
+use crate::client::{subscribe_with_mode, SlotTrackingMode, StreamItem};
+use crate::slots::{SlotEvent, SlotStage, SlotTracker};
+use crate::{LaserstreamConfig, LaserstreamError};
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeUpdate, SubscribeUpdateSlot,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::instrument;
+
+/// Output of [`SkipDetector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipEvent {
+    /// `slot` has no block on the finalized chain. `leader` is its scheduled leader
+    /// when a [`LeaderSchedule`] covers it.
+    SlotSkipped { slot: u64, leader: Option<String> },
+    /// The processed tip switched forks, abandoning `depth` slots above
+    /// `common_ancestor`.
+    Fork { common_ancestor: u64, depth: usize },
+}
+
+/// Leader of each slot, e.g. from `getLeaderSchedule`.
+#[derive(Debug, Clone, Default)]
+pub struct LeaderSchedule {
+    leaders: BTreeMap<u64, String>,
+}
+
+impl LeaderSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule of an epoch in the shape `getLeaderSchedule` returns it: slot indices
+    /// relative to `first_slot` per validator identity.
+    pub fn from_epoch(
+        first_slot: u64,
+        schedule: impl IntoIterator<Item = (String, Vec<u64>)>,
+    ) -> Self {
+        let mut leaders = Self::new();
+        leaders.extend_epoch(first_slot, schedule);
+        leaders
+    }
+
+    /// Adds another epoch, see [`from_epoch`](Self::from_epoch).
+    pub fn extend_epoch(
+        &mut self,
+        first_slot: u64,
+        schedule: impl IntoIterator<Item = (String, Vec<u64>)>,
+    ) {
+        for (identity, indices) in schedule {
+            for index in indices {
+                self.leaders.insert(first_slot + index, identity.clone());
+            }
+        }
+    }
+
+    pub fn insert(&mut self, slot: u64, identity: impl Into<String>) {
+        self.leaders.insert(slot, identity.into());
+    }
+
+    pub fn leader(&self, slot: u64) -> Option<&str> {
+        self.leaders.get(&slot).map(String::as_str)
+    }
+
+    /// Forgets slots below `slot`.
+    pub fn prune_below(&mut self, slot: u64) {
+        self.leaders = self.leaders.split_off(&slot);
+    }
+}
+
+/// Produced and skipped slots of one leader.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LeaderStats {
+    pub produced: u64,
+    pub skipped: u64,
+}
+
+impl LeaderStats {
+    /// Share of the leader's settled slots that were skipped.
+    pub fn skip_rate(&self) -> f64 {
+        let total = self.produced + self.skipped;
+        if total == 0 {
+            0.0
+        } else {
+            self.skipped as f64 / total as f64
+        }
+    }
+}
+
+/// Totals over everything a [`SkipDetector`] has seen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SkipStats {
+    /// Finalized slots with a block.
+    pub produced: u64,
+    pub skipped: u64,
+    pub forks: u64,
+    pub max_fork_depth: usize,
+    total_fork_depth: u64,
+}
+
+impl SkipStats {
+    /// Share of settled slots that were skipped.
+    pub fn skip_rate(&self) -> f64 {
+        LeaderStats {
+            produced: self.produced,
+            skipped: self.skipped,
+        }
+        .skip_rate()
+    }
+
+    /// Mean number of slots abandoned per fork switch.
+    pub fn mean_fork_depth(&self) -> Option<f64> {
+        (self.forks > 0).then(|| self.total_fork_depth as f64 / self.forks as f64)
+    }
+}
+
+/// Finds skipped slots from finalized parent links.
+///
+/// Feed it every slot status (and optionally block metas, whose `parent_slot` fills
+/// parent links slot updates lack). A finalized slot whose parent is unknown is
+/// counted as produced but reports no skips for the gap below it.
+#[derive(Debug, Default)]
+pub struct SkipDetector {
+    tracker: SlotTracker,
+    schedule: Option<LeaderSchedule>,
+    last_finalized: Option<u64>,
+    stats: SkipStats,
+    leaders: HashMap<String, LeaderStats>,
+}
+
+impl SkipDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attributes slots to the leaders in `schedule`.
+    pub fn with_leader_schedule(mut self, schedule: LeaderSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Schedule used for attribution, e.g. to add the next epoch.
+    pub fn leader_schedule_mut(&mut self) -> &mut LeaderSchedule {
+        self.schedule.get_or_insert_with(LeaderSchedule::new)
+    }
+
+    pub fn stats(&self) -> SkipStats {
+        self.stats
+    }
+
+    /// Produced and skipped slots of `identity`; needs a leader schedule.
+    pub fn leader_stats(&self, identity: &str) -> Option<LeaderStats> {
+        self.leaders.get(identity).copied()
+    }
+
+    /// Every leader with settled slots and its stats.
+    pub fn leaders(&self) -> impl Iterator<Item = (&str, LeaderStats)> + '_ {
+        self.leaders
+            .iter()
+            .map(|(identity, stats)| (identity.as_str(), *stats))
+    }
+
+    /// Applies slot updates and block metas; other updates are ignored.
+    pub fn apply(&mut self, update: &SubscribeUpdate) -> Vec<SkipEvent> {
+        match &update.update_oneof {
+            Some(UpdateOneof::Slot(slot)) => self.slot(slot),
+            Some(UpdateOneof::BlockMeta(_)) => {
+                self.tracker.apply(update);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Applies a slot status and returns the skips and forks it revealed.
+    pub fn slot(&mut self, update: &SubscribeUpdateSlot) -> Vec<SkipEvent> {
+        let mut events = Vec::new();
+        let mut finalized = Vec::new();
+        for event in self.tracker.update(update) {
+            match event {
+                SlotEvent::Stage {
+                    slot,
+                    stage: SlotStage::Finalized,
+                } => finalized.push(slot),
+                SlotEvent::Reorg {
+                    common_ancestor,
+                    abandoned,
+                    ..
+                } => {
+                    let depth = abandoned.len();
+                    self.stats.forks += 1;
+                    self.stats.total_fork_depth += depth as u64;
+                    self.stats.max_fork_depth = self.stats.max_fork_depth.max(depth);
+                    events.push(SkipEvent::Fork {
+                        common_ancestor,
+                        depth,
+                    });
+                }
+                _ => {}
+            }
+        }
+        // A finalized slot is reported before the ancestors it finalizes.
+        finalized.sort_unstable();
+        for slot in finalized {
+            self.finalized(slot, &mut events);
+        }
+        events
+    }
+
+    fn finalized(&mut self, slot: u64, events: &mut Vec<SkipEvent>) {
+        if self.last_finalized >= Some(slot) {
+            return;
+        }
+        self.stats.produced += 1;
+        self.attribute(slot, false);
+        // The first finalized slot has no known predecessor to measure a gap from.
+        if let (Some(_), Some(parent)) = (self.last_finalized, self.tracker.parent(slot)) {
+            for skipped in parent + 1..slot {
+                self.stats.skipped += 1;
+                let leader = self.attribute(skipped, true);
+                events.push(SkipEvent::SlotSkipped {
+                    slot: skipped,
+                    leader,
+                });
+            }
+        }
+        self.last_finalized = Some(slot);
+        if let Some(schedule) = &mut self.schedule {
+            schedule.prune_below(slot + 1);
+        }
+    }
+
+    /// Counts `slot` for its leader and returns the leader.
+    fn attribute(&mut self, slot: u64, skipped: bool) -> Option<String> {
+        let leader = self.schedule.as_ref()?.leader(slot)?.to_string();
+        let stats = self.leaders.entry(leader.clone()).or_default();
+        if skipped {
+            stats.skipped += 1;
+        } else {
+            stats.produced += 1;
+        }
+        Some(leader)
+    }
+}
+
+/// Handle to a running [`subscribe_skips`] stream, sharing its detector. Cheap to
+/// clone.
+#[derive(Debug, Clone)]
+pub struct SkipHandle {
+    detector: Arc<Mutex<SkipDetector>>,
+}
+
+impl SkipHandle {
+    /// See [`SkipDetector::stats`].
+    pub fn stats(&self) -> SkipStats {
+        self.lock().stats()
+    }
+
+    /// See [`SkipDetector::leader_stats`].
+    pub fn leader_stats(&self, identity: &str) -> Option<LeaderStats> {
+        self.lock().leader_stats(identity)
+    }
+
+    /// Every leader with settled slots and its stats.
+    pub fn leaders(&self) -> Vec<(String, LeaderStats)> {
+        self.lock()
+            .leaders()
+            .map(|(identity, stats)| (identity.to_string(), stats))
+            .collect()
+    }
+
+    /// Adds an epoch to the running detector's schedule, see
+    /// [`LeaderSchedule::extend_epoch`]. Add each epoch before it starts.
+    pub fn extend_epoch(
+        &self,
+        first_slot: u64,
+        schedule: impl IntoIterator<Item = (String, Vec<u64>)>,
+    ) {
+        self.lock()
+            .leader_schedule_mut()
+            .extend_epoch(first_slot, schedule);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SkipDetector> {
+        self.detector.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Runs a [`SkipDetector`] on a subscription of every slot status and block meta.
+///
+/// The returned [`SkipHandle`] reads the detector's stats and extends its leader
+/// schedule while the stream runs.
+#[instrument(skip(config, detector))]
+pub fn subscribe_skips(
+    config: LaserstreamConfig,
+    detector: SkipDetector,
+) -> (
+    impl Stream<Item = Result<SkipEvent, LaserstreamError>>,
+    SkipHandle,
+) {
+    let mut request = SubscribeRequest {
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ..Default::default()
+    };
+    request.blocks_meta.insert(
+        "skip-detector".to_string(),
+        SubscribeRequestFilterBlocksMeta::default(),
+    );
+    // The internal stream handle is dropped: writes would replace the filters
+    // the detector depends on.
+    let (inner, _) = subscribe_with_mode(config, request, SlotTrackingMode::AllStatuses);
+    let handle = SkipHandle {
+        detector: Arc::new(Mutex::new(detector)),
+    };
+    let shared = handle.clone();
+    let events = stream! {
+        futures::pin_mut!(inner);
+        while let Some(item) = inner.next().await {
+            let events = match item {
+                Ok(StreamItem::Update(update)) => shared.lock().apply(&update),
+                Ok(StreamItem::InternalSlot(slot)) => shared.lock().slot(&slot),
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+            for event in events {
+                yield Ok(event);
+            }
+        }
+    };
+    (events, handle)
+}
@@ -0,0 +1,450 @@
+//! Slot lifecycle and fork tracking.
+//!
+//! [`SlotTracker`] builds a fork tree from `SubscribeUpdateSlot` messages, records when
+//! each slot reached each stage, and reports reorgs and slots that can no longer be
+//! finalized. Subscribe with [`SlotTracker::slot_filter`] to receive every stage.
+
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, SlotStatus, SubscribeRequestFilterSlots, SubscribeUpdate,
+    SubscribeUpdateSlot,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Instant;
+
+/// Default number of slots kept below the highest finalized slot.
+const DEFAULT_RETENTION_SLOTS: u64 = 512;
+
+/// Slot an update belongs to; `None` for pings and pongs.
+pub fn update_slot(update: &SubscribeUpdate) -> Option<u64> {
+    match update.update_oneof.as_ref()? {
+        UpdateOneof::Account(account) => Some(account.slot),
+        UpdateOneof::Slot(slot) => Some(slot.slot),
+        UpdateOneof::Transaction(tx) => Some(tx.slot),
+        UpdateOneof::TransactionStatus(status) => Some(status.slot),
+        UpdateOneof::Block(block) => Some(block.slot),
+        UpdateOneof::BlockMeta(meta) => Some(meta.slot),
+        UpdateOneof::Entry(entry) => Some(entry.slot),
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => None,
+    }
+}
+
+/// Stage a slot has reached, in the order the validator reports them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SlotStage {
+    FirstShredReceived,
+    CreatedBank,
+    Completed,
+    Processed,
+    Confirmed,
+    Finalized,
+    Dead,
+}
+
+impl SlotStage {
+    /// Maps a raw `SubscribeUpdateSlot.status`; `None` for unknown values.
+    pub fn from_status(status: i32) -> Option<Self> {
+        Some(match SlotStatus::try_from(status).ok()? {
+            SlotStatus::SlotFirstShredReceived => Self::FirstShredReceived,
+            SlotStatus::SlotCreatedBank => Self::CreatedBank,
+            SlotStatus::SlotCompleted => Self::Completed,
+            SlotStatus::SlotProcessed => Self::Processed,
+            SlotStatus::SlotConfirmed => Self::Confirmed,
+            SlotStatus::SlotFinalized => Self::Finalized,
+            SlotStatus::SlotDead => Self::Dead,
+        })
+    }
+}
+
+/// Everything known about one slot.
+#[derive(Debug, Clone)]
+pub struct SlotInfo {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub first_shred_received: Option<Instant>,
+    pub created_bank: Option<Instant>,
+    pub completed: Option<Instant>,
+    pub processed: Option<Instant>,
+    pub confirmed: Option<Instant>,
+    pub finalized: Option<Instant>,
+    pub dead: Option<Instant>,
+    pub dead_error: Option<String>,
+    orphaned: bool,
+}
+
+impl SlotInfo {
+    fn new(slot: u64) -> Self {
+        Self {
+            slot,
+            parent: None,
+            first_shred_received: None,
+            created_bank: None,
+            completed: None,
+            processed: None,
+            confirmed: None,
+            finalized: None,
+            dead: None,
+            dead_error: None,
+            orphaned: false,
+        }
+    }
+
+    /// When the slot reached `stage`, if it has.
+    pub fn reached(&self, stage: SlotStage) -> Option<Instant> {
+        match stage {
+            SlotStage::FirstShredReceived => self.first_shred_received,
+            SlotStage::CreatedBank => self.created_bank,
+            SlotStage::Completed => self.completed,
+            SlotStage::Processed => self.processed,
+            SlotStage::Confirmed => self.confirmed,
+            SlotStage::Finalized => self.finalized,
+            SlotStage::Dead => self.dead,
+        }
+    }
+
+    /// The furthest stage reached so far.
+    pub fn stage(&self) -> Option<SlotStage> {
+        [
+            SlotStage::Dead,
+            SlotStage::Finalized,
+            SlotStage::Confirmed,
+            SlotStage::Processed,
+            SlotStage::Completed,
+            SlotStage::CreatedBank,
+            SlotStage::FirstShredReceived,
+        ]
+        .into_iter()
+        .find(|stage| self.reached(*stage).is_some())
+    }
+
+    /// `true` once the slot is dead or known not to be on the finalized chain.
+    pub fn is_orphaned(&self) -> bool {
+        self.orphaned
+    }
+
+    fn mark(&mut self, stage: SlotStage, at: Instant) -> bool {
+        let field = match stage {
+            SlotStage::FirstShredReceived => &mut self.first_shred_received,
+            SlotStage::CreatedBank => &mut self.created_bank,
+            SlotStage::Completed => &mut self.completed,
+            SlotStage::Processed => &mut self.processed,
+            SlotStage::Confirmed => &mut self.confirmed,
+            SlotStage::Finalized => &mut self.finalized,
+            SlotStage::Dead => &mut self.dead,
+        };
+        if field.is_some() {
+            return false;
+        }
+        *field = Some(at);
+        true
+    }
+}
+
+/// Why a slot will never be finalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrphanReason {
+    /// The validator marked the slot dead.
+    Dead(Option<String>),
+    /// A later slot was finalized on a different fork.
+    NotOnFinalizedChain,
+}
+
+/// Change reported by [`SlotTracker::update`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotEvent {
+    /// A slot reached a stage. Confirming or finalizing a slot also reports the
+    /// stage for ancestors that had not reached it yet, oldest first.
+    Stage { slot: u64, stage: SlotStage },
+    /// The processed tip moved to a different fork; `abandoned` lists the slots of the
+    /// old branch above `common_ancestor`, newest first.
+    Reorg {
+        previous_tip: u64,
+        new_tip: u64,
+        common_ancestor: u64,
+        abandoned: Vec<u64>,
+    },
+    /// A slot can no longer be finalized. Reported once per slot.
+    Orphaned { slot: u64, reason: OrphanReason },
+}
+
+/// Fork tree built from slot updates.
+///
+/// ```no_run
+/// use helius_laserstream::slots::{SlotEvent, SlotTracker};
+/// # fn run(updates: Vec<helius_laserstream::grpc::SubscribeUpdate>) {
+/// let mut tracker = SlotTracker::new();
+/// for update in updates {
+///     for event in tracker.apply(&update) {
+///         if let SlotEvent::Reorg { abandoned, .. } = event {
+///             println!("fork switch, abandoned {abandoned:?}");
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlotTracker {
+    slots: BTreeMap<u64, SlotInfo>,
+    processed_tip: Option<u64>,
+    confirmed: Option<u64>,
+    finalized: Option<u64>,
+    retention: u64,
+}
+
+impl Default for SlotTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlotTracker {
+    /// Tracker keeping 512 slots of history below the finalized slot.
+    pub fn new() -> Self {
+        Self::with_retention(DEFAULT_RETENTION_SLOTS)
+    }
+
+    /// Tracker keeping `retention` slots of history below the finalized slot.
+    pub fn with_retention(retention: u64) -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            processed_tip: None,
+            confirmed: None,
+            finalized: None,
+            retention,
+        }
+    }
+
+    /// Slot filter that delivers every stage of every slot, which the tracker needs
+    /// to see forks and lifecycle timings.
+    pub fn slot_filter() -> SubscribeRequestFilterSlots {
+        SubscribeRequestFilterSlots {
+            filter_by_commitment: Some(false),
+            interslot_updates: Some(true),
+        }
+    }
+
+    /// Feeds any update; only slot updates (and block-meta parent links) are used.
+    pub fn apply(&mut self, update: &SubscribeUpdate) -> Vec<SlotEvent> {
+        match &update.update_oneof {
+            Some(UpdateOneof::Slot(slot)) => self.update(slot),
+            Some(UpdateOneof::BlockMeta(meta)) => {
+                self.set_parent(meta.slot, meta.parent_slot);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Records a slot update and returns what changed.
+    pub fn update(&mut self, update: &SubscribeUpdateSlot) -> Vec<SlotEvent> {
+        self.update_at(update, Instant::now())
+    }
+
+    /// Like [`update`](Self::update) with an explicit timestamp.
+    pub fn update_at(&mut self, update: &SubscribeUpdateSlot, at: Instant) -> Vec<SlotEvent> {
+        let mut events = Vec::new();
+        let slot = update.slot;
+        if slot < self.floor() {
+            return events;
+        }
+        if let Some(parent) = update.parent {
+            self.set_parent(slot, parent);
+        }
+        let Some(stage) = SlotStage::from_status(update.status) else {
+            return events;
+        };
+
+        let info = self.slots.entry(slot).or_insert_with(|| SlotInfo::new(slot));
+        if stage == SlotStage::Dead && info.dead_error.is_none() {
+            info.dead_error = update.dead_error.clone();
+        }
+        if !info.mark(stage, at) {
+            return events;
+        }
+        events.push(SlotEvent::Stage { slot, stage });
+
+        match stage {
+            SlotStage::Processed => self.advance_processed(slot, &mut events),
+            SlotStage::Confirmed => {
+                self.confirmed = self.confirmed.max(Some(slot));
+                self.mark_ancestors(slot, SlotStage::Confirmed, at, &mut events);
+            }
+            SlotStage::Finalized => {
+                self.confirmed = self.confirmed.max(Some(slot));
+                self.mark_ancestors(slot, SlotStage::Confirmed, at, &mut events);
+                self.mark_ancestors(slot, SlotStage::Finalized, at, &mut events);
+                if Some(slot) > self.finalized {
+                    self.finalized = Some(slot);
+                    self.orphan_off_chain(slot, &mut events);
+                    self.prune();
+                }
+            }
+            SlotStage::Dead => {
+                let info = self.slots.get_mut(&slot).expect("inserted above");
+                if !info.orphaned {
+                    info.orphaned = true;
+                    events.push(SlotEvent::Orphaned {
+                        slot,
+                        reason: OrphanReason::Dead(info.dead_error.clone()),
+                    });
+                }
+            }
+            _ => {}
+        }
+        events
+    }
+
+    /// Everything known about `slot`.
+    pub fn get(&self, slot: u64) -> Option<&SlotInfo> {
+        self.slots.get(&slot)
+    }
+
+    /// Parent of `slot`, if known.
+    pub fn parent(&self, slot: u64) -> Option<u64> {
+        self.slots.get(&slot).and_then(|info| info.parent)
+    }
+
+    /// Known ancestors of `slot`, nearest first, down to the retention floor.
+    pub fn ancestors(&self, slot: u64) -> impl Iterator<Item = u64> + '_ {
+        let mut current = slot;
+        std::iter::from_fn(move || {
+            current = self.parent(current)?;
+            Some(current)
+        })
+    }
+
+    /// Most recent slot reported as processed on the current fork.
+    pub fn processed_tip(&self) -> Option<u64> {
+        self.processed_tip
+    }
+
+    /// Highest confirmed slot.
+    pub fn confirmed_slot(&self) -> Option<u64> {
+        self.confirmed
+    }
+
+    /// Highest finalized slot.
+    pub fn finalized_slot(&self) -> Option<u64> {
+        self.finalized
+    }
+
+    /// Whether `slot` is on the finalized chain.
+    ///
+    /// `None` if that is not known yet: `slot` is above the finalized slot, below the
+    /// retention window, or the parent links between them are incomplete.
+    pub fn is_on_finalized_chain(&self, slot: u64) -> Option<bool> {
+        let finalized = self.finalized?;
+        if slot > finalized || slot < self.floor() {
+            return None;
+        }
+        let mut current = finalized;
+        while current > slot {
+            current = self.parent(current)?;
+        }
+        Some(current == slot)
+    }
+
+    fn floor(&self) -> u64 {
+        self.finalized
+            .map(|f| f.saturating_sub(self.retention))
+            .unwrap_or(0)
+    }
+
+    fn set_parent(&mut self, slot: u64, parent: u64) {
+        if slot < self.floor() {
+            return;
+        }
+        self.slots
+            .entry(slot)
+            .or_insert_with(|| SlotInfo::new(slot))
+            .parent = Some(parent);
+    }
+
+    fn advance_processed(&mut self, slot: u64, events: &mut Vec<SlotEvent>) {
+        let Some(tip) = self.processed_tip else {
+            self.processed_tip = Some(slot);
+            return;
+        };
+        if tip == slot {
+            return;
+        }
+
+        let lineage: HashSet<u64> = std::iter::once(slot).chain(self.ancestors(slot)).collect();
+        if lineage.contains(&tip) {
+            // Same fork, tip moved forward.
+            self.processed_tip = Some(slot);
+            return;
+        }
+
+        let mut abandoned = Vec::new();
+        let mut current = tip;
+        loop {
+            if current == slot {
+                // A late notification for an ancestor of the tip; not a fork switch.
+                return;
+            }
+            if lineage.contains(&current) {
+                events.push(SlotEvent::Reorg {
+                    previous_tip: tip,
+                    new_tip: slot,
+                    common_ancestor: current,
+                    abandoned,
+                });
+                self.processed_tip = Some(slot);
+                return;
+            }
+            abandoned.push(current);
+            match self.parent(current) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        // Parent links are incomplete, so a fork switch can't be proven.
+        if slot > tip {
+            self.processed_tip = Some(slot);
+        }
+    }
+
+    fn mark_ancestors(&mut self, slot: u64, stage: SlotStage, at: Instant, events: &mut Vec<SlotEvent>) {
+        let mut newly = Vec::new();
+        let mut current = slot;
+        while let Some(parent) = self.parent(current) {
+            let Some(info) = self.slots.get_mut(&parent) else { break };
+            if !info.mark(stage, at) {
+                break;
+            }
+            newly.push(parent);
+            current = parent;
+        }
+        events.extend(newly.into_iter().rev().map(|slot| SlotEvent::Stage { slot, stage }));
+    }
+
+    /// Orphans every tracked slot at or below `finalized` that is not on its chain, as
+    /// far down as the parent links reach.
+    fn orphan_off_chain(&mut self, finalized: u64, events: &mut Vec<SlotEvent>) {
+        let mut chain = HashSet::from([finalized]);
+        let mut current = finalized;
+        let mut reach = finalized;
+        while let Some(parent) = self.parent(current) {
+            chain.insert(parent);
+            current = parent;
+            reach = parent;
+            if !self.slots.contains_key(&parent) {
+                break;
+            }
+        }
+        for (slot, info) in self.slots.range_mut(reach..=finalized) {
+            if !chain.contains(slot) && !info.orphaned {
+                info.orphaned = true;
+                events.push(SlotEvent::Orphaned {
+                    slot: *slot,
+                    reason: OrphanReason::NotOnFinalizedChain,
+                });
+            }
+        }
+    }
+
+    fn prune(&mut self) {
+        let floor = self.floor();
+        self.slots = self.slots.split_off(&floor);
+    }
+}
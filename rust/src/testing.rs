@@ -0,0 +1,553 @@
+//! In-process Geyser server for tests (`testing` feature).
+//!
+//! [`MockGeyser`] serves the Geyser gRPC service on a local port, so the real client
+//! (reconnects, replay, `write()`, pings, unary calls) can be exercised under
+//! `cargo test` without an endpoint. Updates pushed to it are kept as the chain
+//! history and sent to every open subscription, filtered by that subscription's
+//! current request with [`RequestMatcher`]. A subscription that sets `from_slot` is
+//! first replayed the history from that slot, and each non-ping request written to
+//! it replaces its filters, as on a real node.
+//!
+//! Failures are injected with [`MockGeyser::disconnect`], [`MockGeyser::fail`],
+//! [`MockGeyser::reject_connections`] and [`MockGeyser::set_latency`], or in order
+//! with the rest of a script via [`MockGeyser::play`]. Commitment levels are not
+//! simulated: updates are delivered as pushed. Preprocessed subscriptions receive
+//! every update sent with [`MockGeyser::push_preprocessed`], unfiltered.
+
+use crate::blockhash::MAX_PROCESSING_AGE;
+use crate::filter::RequestMatcher;
+use crate::slots::update_slot;
+use crate::{LaserstreamConfig, LaserstreamError};
+use laserstream_core_proto::geyser::geyser_server::{Geyser, GeyserServer};
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, GetBlockHeightRequest, GetBlockHeightResponse,
+    GetLatestBlockhashRequest, GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse,
+    GetVersionRequest, GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse,
+    PingRequest, PongResponse, SubscribePreprocessedRequest, SubscribePreprocessedUpdate,
+    SubscribeReplayInfoRequest, SubscribeReplayInfoResponse, SubscribeRequest, SubscribeUpdate,
+    SubscribeUpdatePong,
+};
+use laserstream_core_proto::tonic::{
+    self, transport::Server, Request, Response, Status, Streaming,
+};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::Stream;
+
+/// Updates buffered per subscription before it falls behind and is failed.
+const SUBSCRIPTION_BUFFER: usize = 4096;
+
+/// One step of a [`MockGeyser::play`] script.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum MockEvent {
+    /// [`MockGeyser::push`].
+    Update(SubscribeUpdate),
+    /// Pause before the next step.
+    Delay(Duration),
+    /// [`MockGeyser::disconnect`].
+    Disconnect,
+    /// [`MockGeyser::fail`].
+    Error(Status),
+}
+
+/// What open subscriptions are told.
+#[derive(Debug, Clone)]
+enum Broadcast {
+    Update(Arc<SubscribeUpdate>),
+    Disconnect,
+    Error(Status),
+}
+
+/// A block meta, for the unary blockhash calls.
+#[derive(Debug, Clone)]
+struct Block {
+    slot: u64,
+    blockhash: String,
+    block_height: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    history: Vec<SubscribeUpdate>,
+    requests: Vec<SubscribeRequest>,
+    preprocessed_requests: Vec<SubscribePreprocessedRequest>,
+    connections: usize,
+    rejections: VecDeque<Status>,
+    latency: Duration,
+    blocks: Vec<Block>,
+    slot: u64,
+}
+
+impl State {
+    fn latest_block(&self) -> Result<&Block, Status> {
+        self.blocks
+            .last()
+            .ok_or_else(|| Status::unavailable("no block meta pushed yet"))
+    }
+}
+
+#[derive(Clone)]
+struct Service {
+    state: Arc<Mutex<State>>,
+    events: broadcast::Sender<Broadcast>,
+    preprocessed: broadcast::Sender<Arc<SubscribePreprocessedUpdate>>,
+    requested: Arc<Notify>,
+}
+
+/// A Geyser server on `127.0.0.1`, stopped when dropped.
+pub struct MockGeyser {
+    addr: SocketAddr,
+    service: Service,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockGeyser {
+    /// Starts serving on a free local port.
+    pub async fn start() -> Result<Self, LaserstreamError> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| LaserstreamError::ConnectionError(e.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| LaserstreamError::ConnectionError(e.to_string()))?;
+        let (events, _) = broadcast::channel(SUBSCRIPTION_BUFFER);
+        let (preprocessed, _) = broadcast::channel(SUBSCRIPTION_BUFFER);
+        let service = Service {
+            state: Arc::default(),
+            events,
+            preprocessed,
+            requested: Arc::default(),
+        };
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = Server::builder()
+            .add_service(GeyserServer::new(service.clone()))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = stopped.await;
+            });
+        tokio::spawn(server);
+        Ok(Self {
+            addr,
+            service,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `http://` URL of the server.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Client configuration pointing at the server.
+    pub fn config(&self) -> LaserstreamConfig {
+        LaserstreamConfig::new(self.endpoint(), "mock-api-key".to_string())
+    }
+
+    /// Appends `update` to the history and sends it to every open subscription
+    /// whose filters match. Pings and pongs are sent unfiltered.
+    pub fn push(&self, update: SubscribeUpdate) {
+        let mut state = self.service.state.lock().unwrap();
+        if let Some(slot) = update_slot(&update) {
+            state.slot = state.slot.max(slot);
+        }
+        if let Some(UpdateOneof::BlockMeta(meta)) = &update.update_oneof {
+            state.blocks.push(Block {
+                slot: meta.slot,
+                blockhash: meta.blockhash.clone(),
+                block_height: meta.block_height.map_or(0, |height| height.block_height),
+            });
+        }
+        state.history.push(update.clone());
+        // Sent under the lock, so a subscription sees each update once: either in
+        // its history replay or live.
+        let _ = self
+            .service
+            .events
+            .send(Broadcast::Update(Arc::new(update)));
+    }
+
+    /// Sends `update` to every open preprocessed subscription. Not kept in the
+    /// history.
+    pub fn push_preprocessed(&self, update: SubscribePreprocessedUpdate) {
+        let _ = self.service.preprocessed.send(Arc::new(update));
+    }
+
+    /// Ends every open subscription as if the connection dropped. New
+    /// subscriptions are accepted as usual.
+    pub fn disconnect(&self) {
+        let _ = self.service.events.send(Broadcast::Disconnect);
+    }
+
+    /// Ends every open subscription with `status`.
+    pub fn fail(&self, status: Status) {
+        let _ = self.service.events.send(Broadcast::Error(status));
+    }
+
+    /// Rejects the next subscribe calls with `statuses`, one per call.
+    pub fn reject_connections(&self, statuses: impl IntoIterator<Item = Status>) {
+        self.service
+            .state
+            .lock()
+            .unwrap()
+            .rejections
+            .extend(statuses);
+    }
+
+    /// Delay before each update is sent to a subscription.
+    pub fn set_latency(&self, latency: Duration) {
+        self.service.state.lock().unwrap().latency = latency;
+    }
+
+    /// Runs `events` in order.
+    pub async fn play(&self, events: impl IntoIterator<Item = MockEvent>) {
+        for event in events {
+            match event {
+                MockEvent::Update(update) => self.push(update),
+                MockEvent::Delay(delay) => tokio::time::sleep(delay).await,
+                MockEvent::Disconnect => self.disconnect(),
+                MockEvent::Error(status) => self.fail(status),
+            }
+        }
+    }
+
+    /// Every subscribe request received, in order: the first request of each
+    /// subscription and every write after it. Pings are left out.
+    pub fn requests(&self) -> Vec<SubscribeRequest> {
+        self.service.state.lock().unwrap().requests.clone()
+    }
+
+    /// First request of every preprocessed subscription, in order.
+    pub fn preprocessed_requests(&self) -> Vec<SubscribePreprocessedRequest> {
+        self.service
+            .state
+            .lock()
+            .unwrap()
+            .preprocessed_requests
+            .clone()
+    }
+
+    /// Number of subscriptions accepted so far.
+    pub fn connections(&self) -> usize {
+        self.service.state.lock().unwrap().connections
+    }
+
+    /// Number of subscriptions currently open, preprocessed ones included.
+    pub fn open_subscriptions(&self) -> usize {
+        self.service.events.receiver_count()
+    }
+
+    /// Waits until at least `count` requests have been received and applied.
+    pub async fn wait_for_requests(&self, count: usize) {
+        self.wait_until(|state| state.requests.len() >= count).await
+    }
+
+    /// Waits until at least `count` preprocessed subscriptions have been opened.
+    pub async fn wait_for_preprocessed_requests(&self, count: usize) {
+        self.wait_until(|state| state.preprocessed_requests.len() >= count)
+            .await
+    }
+
+    async fn wait_until(&self, done: impl Fn(&State) -> bool) {
+        loop {
+            let notified = self.service.requested.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if done(&self.service.state.lock().unwrap()) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for MockGeyser {
+    fn drop(&mut self) {
+        self.disconnect();
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl std::fmt::Debug for MockGeyser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockGeyser")
+            .field("addr", &self.addr)
+            .field("connections", &self.connections())
+            .finish()
+    }
+}
+
+impl Service {
+    fn record(&self, request: &SubscribeRequest) {
+        self.state.lock().unwrap().requests.push(request.clone());
+        self.requested.notify_waiters();
+    }
+
+    /// Runs one subscription until the client goes away or the mock ends it.
+    async fn serve(
+        self,
+        mut requests: Streaming<SubscribeRequest>,
+        mut matcher: RequestMatcher,
+        replay: Vec<SubscribeUpdate>,
+        mut events: broadcast::Receiver<Broadcast>,
+        tx: mpsc::Sender<Result<SubscribeUpdate, Status>>,
+    ) {
+        for update in replay {
+            if !self.send(&matcher, update, &tx).await {
+                return;
+            }
+        }
+        loop {
+            tokio::select! {
+                request = requests.message() => match request {
+                    Ok(Some(request)) => {
+                        if let Some(ping) = request.ping {
+                            let pong = SubscribeUpdate {
+                                update_oneof: Some(UpdateOneof::Pong(SubscribeUpdatePong { id: ping.id })),
+                                ..Default::default()
+                            };
+                            if tx.send(Ok(pong)).await.is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        match RequestMatcher::new(&request) {
+                            Ok(new) => matcher = new,
+                            Err(e) => {
+                                let _ = tx.send(Err(Status::invalid_argument(e.to_string()))).await;
+                                return;
+                            }
+                        }
+                        self.record(&request);
+                    }
+                    Ok(None) | Err(_) => return,
+                },
+                event = events.recv() => match event {
+                    Ok(Broadcast::Update(update)) => {
+                        if !self.send(&matcher, (*update).clone(), &tx).await {
+                            return;
+                        }
+                    }
+                    Ok(Broadcast::Disconnect) | Err(broadcast::error::RecvError::Closed) => return,
+                    Ok(Broadcast::Error(status)) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        let status = Status::data_loss(format!("subscription lagged by {missed} updates"));
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Sends `update` if it matches; `false` once the client is gone.
+    async fn send(
+        &self,
+        matcher: &RequestMatcher,
+        update: SubscribeUpdate,
+        tx: &mpsc::Sender<Result<SubscribeUpdate, Status>>,
+    ) -> bool {
+        let update = match &update.update_oneof {
+            Some(UpdateOneof::Ping(_)) | Some(UpdateOneof::Pong(_)) => update,
+            _ => match matcher.filter(update) {
+                Some(update) => update,
+                None => return true,
+            },
+        };
+        let latency = self.state.lock().unwrap().latency;
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        tx.send(Ok(update)).await.is_ok()
+    }
+}
+
+/// Runs one preprocessed subscription until the client goes away or the mock ends
+/// it.
+async fn serve_preprocessed(
+    mut requests: Streaming<SubscribePreprocessedRequest>,
+    mut updates: broadcast::Receiver<Arc<SubscribePreprocessedUpdate>>,
+    mut events: broadcast::Receiver<Broadcast>,
+    tx: mpsc::Sender<Result<SubscribePreprocessedUpdate, Status>>,
+) {
+    // The client may close its side after the first request.
+    let mut requests_open = true;
+    loop {
+        tokio::select! {
+            request = requests.message(), if requests_open => match request {
+                Ok(Some(_)) => {}
+                Ok(None) => requests_open = false,
+                Err(_) => return,
+            },
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    if tx.send(Ok((*update).clone())).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    let status = Status::data_loss(format!("subscription lagged by {missed} updates"));
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(Broadcast::Update(_)) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Ok(Broadcast::Disconnect) | Err(broadcast::error::RecvError::Closed) => return,
+                Ok(Broadcast::Error(status)) => {
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                }
+            },
+        }
+    }
+}
+
+type UpdateStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Geyser for Service {
+    type SubscribeStream = UpdateStream<SubscribeUpdate>;
+    type SubscribePreprocessedStream = UpdateStream<SubscribePreprocessedUpdate>;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        if let Some(status) = self.state.lock().unwrap().rejections.pop_front() {
+            return Err(status);
+        }
+        let mut requests = request.into_inner();
+        let first = requests
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("no subscribe request"))?;
+        let matcher =
+            RequestMatcher::new(&first).map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let (replay, events) = {
+            let mut state = self.state.lock().unwrap();
+            state.connections += 1;
+            let replay = match first.from_slot {
+                Some(from_slot) => state
+                    .history
+                    .iter()
+                    .filter(|update| update_slot(update).is_some_and(|slot| slot >= from_slot))
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            (replay, self.events.subscribe())
+        };
+        self.record(&first);
+
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(self.clone().serve(requests, matcher, replay, events, tx));
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn subscribe_preprocessed(
+        &self,
+        request: Request<Streaming<SubscribePreprocessedRequest>>,
+    ) -> Result<Response<Self::SubscribePreprocessedStream>, Status> {
+        let mut requests = request.into_inner();
+        let first = requests
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("no subscribe request"))?;
+        let (updates, events) = (self.preprocessed.subscribe(), self.events.subscribe());
+        self.state.lock().unwrap().preprocessed_requests.push(first);
+        self.requested.notify_waiters();
+
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(serve_preprocessed(requests, updates, events, tx));
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn subscribe_replay_info(
+        &self,
+        _request: Request<SubscribeReplayInfoRequest>,
+    ) -> Result<Response<SubscribeReplayInfoResponse>, Status> {
+        let state = self.state.lock().unwrap();
+        Ok(Response::new(SubscribeReplayInfoResponse {
+            first_available: state.history.iter().find_map(update_slot),
+        }))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse {
+            count: request.into_inner().count,
+        }))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _request: Request<GetLatestBlockhashRequest>,
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        let state = self.state.lock().unwrap();
+        let block = state.latest_block()?;
+        Ok(Response::new(GetLatestBlockhashResponse {
+            slot: block.slot,
+            blockhash: block.blockhash.clone(),
+            last_valid_block_height: block.block_height + MAX_PROCESSING_AGE,
+        }))
+    }
+
+    async fn get_block_height(
+        &self,
+        _request: Request<GetBlockHeightRequest>,
+    ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        let state = self.state.lock().unwrap();
+        Ok(Response::new(GetBlockHeightResponse {
+            block_height: state.latest_block()?.block_height,
+        }))
+    }
+
+    async fn get_slot(
+        &self,
+        _request: Request<GetSlotRequest>,
+    ) -> Result<Response<GetSlotResponse>, Status> {
+        Ok(Response::new(GetSlotResponse {
+            slot: self.state.lock().unwrap().slot,
+        }))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        request: Request<IsBlockhashValidRequest>,
+    ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        let blockhash = request.into_inner().blockhash;
+        let state = self.state.lock().unwrap();
+        let height = state.blocks.last().map_or(0, |block| block.block_height);
+        let valid = state.blocks.iter().any(|block| {
+            block.blockhash == blockhash && height <= block.block_height + MAX_PROCESSING_AGE
+        });
+        Ok(Response::new(IsBlockhashValidResponse {
+            slot: state.slot,
+            valid,
+        }))
+    }
+
+    async fn get_version(
+        &self,
+        _request: Request<GetVersionRequest>,
+    ) -> Result<Response<GetVersionResponse>, Status> {
+        Ok(Response::new(GetVersionResponse {
+            version: format!("mock-geyser {}", env!("CARGO_PKG_VERSION")),
+        }))
+    }
+}
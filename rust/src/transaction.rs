@@ -0,0 +1,368 @@
+//! Read helpers for streamed transactions.
+//!
+//! [`TransactionView`] lines up the pieces of `SubscribeUpdateTransactionInfo` that
+//! are spread over the message and the status meta: the full account-key list
+//! (static keys, then keys loaded from lookup tables), SOL and token balance changes,
+//! fee payer, compute units and the error.
+
+use crate::instructions::{ComputeBudgetInstruction, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::logs::ProgramLogs;
+use laserstream_core_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateTransactionInfo,
+};
+use laserstream_core_proto::solana::storage::confirmed_block::{
+    Message, TokenBalance, TransactionStatusMeta,
+};
+use std::collections::BTreeMap;
+
+/// Bincode variant index of `TransactionError::InstructionError`.
+const INSTRUCTION_ERROR: u32 = 8;
+/// Bincode variant index of `InstructionError::Custom`.
+const CUSTOM_ERROR: u32 = 25;
+/// Compute units granted per instruction when no limit is requested.
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Lamport balance change of one account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange<'a> {
+    /// Position in [`TransactionView::account_keys`].
+    pub index: usize,
+    pub pubkey: &'a [u8],
+    pub pre: u64,
+    pub post: u64,
+    pub delta: i64,
+}
+
+/// Token balance change of one owner for one mint, summed over its token accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalanceChange {
+    /// Wallet owning the token accounts (base58). Falls back to the token account
+    /// address when the node did not report an owner.
+    pub owner: String,
+    pub mint: String,
+    /// Token program that owns the accounts (SPL Token or Token-2022), if reported.
+    pub program_id: String,
+    pub decimals: u32,
+    /// Raw amounts, in base units.
+    pub pre: u128,
+    pub post: u128,
+    pub delta: i128,
+}
+
+/// Error of a failed transaction, as the bincode-encoded `TransactionError` the node
+/// reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionFailure<'a> {
+    pub raw: &'a [u8],
+}
+
+impl TransactionFailure<'_> {
+    /// Variant index of the `TransactionError` (8 is `InstructionError`).
+    pub fn code(&self) -> Option<u32> {
+        read_u32(self.raw, 0)
+    }
+
+    /// Index of the failing instruction, for instruction errors.
+    pub fn instruction_index(&self) -> Option<u8> {
+        if self.code()? != INSTRUCTION_ERROR {
+            return None;
+        }
+        self.raw.get(4).copied()
+    }
+
+    /// Variant index of the `InstructionError`, for instruction errors.
+    pub fn instruction_error_code(&self) -> Option<u32> {
+        self.instruction_index()?;
+        read_u32(self.raw, 5)
+    }
+
+    /// Program-defined error code of an `InstructionError::Custom`.
+    pub fn custom_error(&self) -> Option<u32> {
+        if self.instruction_error_code()? != CUSTOM_ERROR {
+            return None;
+        }
+        read_u32(self.raw, 9)
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Borrowed view of one transaction from a `transactions` or `blocks` update.
+#[derive(Debug, Clone)]
+pub struct TransactionView<'a> {
+    slot: u64,
+    info: &'a SubscribeUpdateTransactionInfo,
+    message: &'a Message,
+    meta: &'a TransactionStatusMeta,
+    account_keys: Vec<&'a [u8]>,
+}
+
+impl<'a> TransactionView<'a> {
+    /// View of `info` in `slot`; `None` if the message or the meta is missing.
+    pub fn new(slot: u64, info: &'a SubscribeUpdateTransactionInfo) -> Option<Self> {
+        let message = info.transaction.as_ref()?.message.as_ref()?;
+        let meta = info.meta.as_ref()?;
+        let account_keys = message
+            .account_keys
+            .iter()
+            .chain(&meta.loaded_writable_addresses)
+            .chain(&meta.loaded_readonly_addresses)
+            .map(Vec::as_slice)
+            .collect();
+        Some(Self {
+            slot,
+            info,
+            message,
+            meta,
+            account_keys,
+        })
+    }
+
+    /// Every transaction carried by `update`: one for a transaction update, all of a
+    /// block's transactions for a block update.
+    pub fn from_update(update: &'a SubscribeUpdate) -> Vec<Self> {
+        match &update.update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => tx
+                .transaction
+                .as_ref()
+                .and_then(|info| Self::new(tx.slot, info))
+                .into_iter()
+                .collect(),
+            Some(UpdateOneof::Block(block)) => block
+                .transactions
+                .iter()
+                .filter_map(|info| Self::new(block.slot, info))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    /// Position of the transaction within its block.
+    pub fn index(&self) -> u64 {
+        self.info.index
+    }
+
+    pub fn is_vote(&self) -> bool {
+        self.info.is_vote
+    }
+
+    pub fn signature(&self) -> &'a [u8] {
+        &self.info.signature
+    }
+
+    /// Base58 signature, as shown by explorers.
+    pub fn signature_string(&self) -> String {
+        bs58::encode(&self.info.signature).into_string()
+    }
+
+    pub fn info(&self) -> &'a SubscribeUpdateTransactionInfo {
+        self.info
+    }
+
+    pub fn message(&self) -> &'a Message {
+        self.message
+    }
+
+    pub fn meta(&self) -> &'a TransactionStatusMeta {
+        self.meta
+    }
+
+    /// All account keys in instruction-index order: static keys, then writable and
+    /// readonly keys loaded from address lookup tables.
+    pub fn account_keys(&self) -> &[&'a [u8]] {
+        &self.account_keys
+    }
+
+    /// Key at an instruction account index.
+    pub fn account_key(&self, index: usize) -> Option<&'a [u8]> {
+        self.account_keys.get(index).copied()
+    }
+
+    /// First signer; pays the fee.
+    pub fn fee_payer(&self) -> Option<&'a [u8]> {
+        self.account_key(0)
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        let signers = self.message.header.as_ref().map_or(0, |h| h.num_required_signatures);
+        index < signers as usize
+    }
+
+    pub fn is_writable(&self, index: usize) -> bool {
+        let static_keys = self.message.account_keys.len();
+        if index >= static_keys {
+            return index < static_keys + self.meta.loaded_writable_addresses.len();
+        }
+        let Some(header) = &self.message.header else {
+            return false;
+        };
+        let signers = header.num_required_signatures as usize;
+        if index < signers {
+            index < signers.saturating_sub(header.num_readonly_signed_accounts as usize)
+        } else {
+            index < static_keys.saturating_sub(header.num_readonly_unsigned_accounts as usize)
+        }
+    }
+
+    /// Fee charged, in lamports.
+    pub fn fee(&self) -> u64 {
+        self.meta.fee
+    }
+
+    pub fn compute_units_consumed(&self) -> Option<u64> {
+        self.meta.compute_units_consumed
+    }
+
+    /// Compute-unit limit requested by `SetComputeUnitLimit` or the V1 config, else
+    /// the runtime default for the message's instructions.
+    pub fn compute_unit_limit(&self) -> u32 {
+        if let Some(limit) = self.message.config.and_then(|c| c.compute_unit_limit) {
+            return limit;
+        }
+        let mut budget_instructions = 0;
+        for ix in self.compute_budget_instructions() {
+            budget_instructions += 1;
+            if let Some(ComputeBudgetInstruction::SetComputeUnitLimit { units }) = ix {
+                return units.min(MAX_COMPUTE_UNIT_LIMIT);
+            }
+        }
+        let other = self.message.instructions.len() - budget_instructions;
+        (other as u32)
+            .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    /// Priority fee in micro-lamports per compute unit; 0 if none was set.
+    ///
+    /// A V1 config's `priority_fee` is the total priority fee in lamports and is
+    /// spread over [`compute_unit_limit`](Self::compute_unit_limit).
+    pub fn compute_unit_price(&self) -> u64 {
+        if let Some(fee) = self.message.config.and_then(|c| c.priority_fee) {
+            let limit = u128::from(self.compute_unit_limit().max(1));
+            return (u128::from(fee) * 1_000_000 / limit).min(u64::MAX as u128) as u64;
+        }
+        self.compute_budget_instructions()
+            .find_map(|ix| match ix {
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice { micro_lamports }) => {
+                    Some(micro_lamports)
+                }
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Top-level Compute Budget instructions, `None` for ones that don't decode.
+    fn compute_budget_instructions(
+        &self,
+    ) -> impl Iterator<Item = Option<ComputeBudgetInstruction>> + '_ {
+        self.message.instructions.iter().filter_map(|ix| {
+            let program = self.account_key(ix.program_id_index as usize)?;
+            if bs58::encode(program).into_string() != COMPUTE_BUDGET_PROGRAM_ID {
+                return None;
+            }
+            Some(ComputeBudgetInstruction::parse(&ix.data))
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.meta.err.is_none()
+    }
+
+    pub fn error(&self) -> Option<TransactionFailure<'a>> {
+        self.meta
+            .err
+            .as_ref()
+            .map(|err| TransactionFailure { raw: &err.err })
+    }
+
+    /// Log lines; `None` if the node did not record logs.
+    pub fn logs(&self) -> Option<&'a [String]> {
+        if self.meta.log_messages_none {
+            return None;
+        }
+        Some(&self.meta.log_messages)
+    }
+
+    /// [`logs`](Self::logs) parsed into the invocation tree.
+    pub fn program_logs(&self) -> Option<ProgramLogs> {
+        self.logs().map(ProgramLogs::parse)
+    }
+
+    /// Accounts whose lamport balance changed, in key order.
+    pub fn balance_changes(&self) -> Vec<BalanceChange<'a>> {
+        self.meta
+            .pre_balances
+            .iter()
+            .zip(&self.meta.post_balances)
+            .enumerate()
+            .filter(|(_, (pre, post))| pre != post)
+            .filter_map(|(index, (&pre, &post))| {
+                Some(BalanceChange {
+                    index,
+                    pubkey: self.account_key(index)?,
+                    pre,
+                    post,
+                    delta: post as i64 - pre as i64,
+                })
+            })
+            .collect()
+    }
+
+    /// Net token balance changes per (owner, mint), sorted by owner then mint. Token
+    /// accounts created or closed by the transaction count as zero on the missing side.
+    pub fn token_balance_changes(&self) -> Vec<TokenBalanceChange> {
+        let mut changes: BTreeMap<(String, String), TokenBalanceChange> = BTreeMap::new();
+        let sides = [(&self.meta.pre_token_balances, true), (&self.meta.post_token_balances, false)];
+        for (balances, is_pre) in sides {
+            for balance in balances {
+                let amount = token_amount(balance);
+                let owner = if balance.owner.is_empty() {
+                    self.account_key(balance.account_index as usize)
+                        .map(|key| bs58::encode(key).into_string())
+                        .unwrap_or_default()
+                } else {
+                    balance.owner.clone()
+                };
+                let change = changes
+                    .entry((owner.clone(), balance.mint.clone()))
+                    .or_insert_with(|| TokenBalanceChange {
+                        owner,
+                        mint: balance.mint.clone(),
+                        program_id: balance.program_id.clone(),
+                        decimals: balance.ui_token_amount.as_ref().map_or(0, |a| a.decimals),
+                        pre: 0,
+                        post: 0,
+                        delta: 0,
+                    });
+                if is_pre {
+                    change.pre += amount;
+                } else {
+                    change.post += amount;
+                }
+            }
+        }
+        changes
+            .into_values()
+            .filter_map(|mut change| {
+                change.delta = change.post as i128 - change.pre as i128;
+                (change.delta != 0).then_some(change)
+            })
+            .collect()
+    }
+}
+
+fn token_amount(balance: &TokenBalance) -> u128 {
+    balance
+        .ui_token_amount
+        .as_ref()
+        .and_then(|a| a.amount.parse().ok())
+        .unwrap_or(0)
+}
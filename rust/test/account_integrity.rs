@@ -11,7 +11,6 @@ use helius_laserstream::{
     subscribe, LaserstreamConfig,
 };
 use laserstream_core_client::{ClientTlsConfig, GeyserGrpcClient};
-use bs58;
 use tracing::{error, warn};
 use std::io::{self, Write};
 use sha2::{Sha256, Digest};
@@ -20,7 +19,7 @@ use sha2::{Sha256, Digest};
 fn fingerprint_account(data: &[u8], lamports: u64) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
-    hasher.update(&lamports.to_le_bytes());
+    hasher.update(lamports.to_le_bytes());
     let digest = hasher.finalize();
     format!("{:x}", digest)
 }
@@ -30,7 +30,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         SubscribeRequestFilterSlots {
             filter_by_commitment: Some(true),
             interslot_updates: Some(false),
-            ..Default::default()
         },
     );
 
@@ -0,0 +1,118 @@
+use helius_laserstream::chaos::{ChaosOptions, ChaosProxy, Fault, Schedule};
+use std::time::Duration;
+
+/// Chaos proxy for the resilience tests, in front of a plaintext (h2c) endpoint
+/// such as the mock server or a plaintext LaserStream edge.
+///
+/// Run:
+///   CHAOS_UPSTREAM=<host:port> cargo run --features testing --bin chaos_proxy
+///
+/// Environment:
+///   CHAOS_UPSTREAM    upstream `host:port` (required)
+///   CHAOS_LISTEN      listen address, default `127.0.0.1:4003`
+///   CHAOS_SEED        seed for every random choice; random (and printed) if unset
+///   CHAOS_LATENCY     added delay per chunk, e.g. `50ms`; `50ms~20ms` adds jitter
+///   CHAOS_BANDWIDTH   bytes per second per direction
+///   CHAOS_FAULTS      comma-separated `<fault>@<schedule>`: fault is `drop`, `cut`,
+///                     `goaway[:<grace>]` or `stall:<duration>`; schedule is a
+///                     duration (once) or `<min>-<max>` (repeatedly), e.g.
+///                     `goaway:1s@20s-40s,cut@90s`
+///   CHAOS_OUTAGES     `<up min>-<up max>/<down min>-<down max>`, default
+///                     `20s-60s/5s-30s` (the TypeScript proxy's windows); `off` to
+///                     disable
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".into()),
+        )
+        .init();
+
+    let upstream = std::env::var("CHAOS_UPSTREAM").map_err(|_| "CHAOS_UPSTREAM is not set")?;
+    let listen = std::env::var("CHAOS_LISTEN").unwrap_or_else(|_| "127.0.0.1:4003".to_string());
+    let seed = match std::env::var("CHAOS_SEED") {
+        Ok(seed) => seed.parse()?,
+        Err(_) => rand::random(),
+    };
+
+    let mut options = ChaosOptions::default().with_seed(seed);
+    if let Ok(latency) = std::env::var("CHAOS_LATENCY") {
+        let (latency, jitter) = latency.split_once('~').unwrap_or((&latency, "0ms"));
+        options = options.with_latency(parse_duration(latency)?, parse_duration(jitter)?);
+    }
+    if let Ok(bandwidth) = std::env::var("CHAOS_BANDWIDTH") {
+        options = options.with_bandwidth(bandwidth.parse()?);
+    }
+    if let Ok(faults) = std::env::var("CHAOS_FAULTS") {
+        for rule in faults.split(',').filter(|rule| !rule.trim().is_empty()) {
+            let (fault, schedule) = parse_rule(rule.trim())?;
+            options = options.with_fault(schedule, fault);
+        }
+    }
+    let outages = std::env::var("CHAOS_OUTAGES").unwrap_or_else(|_| "20s-60s/5s-30s".to_string());
+    if outages != "off" {
+        let (up, down) = outages.split_once('/').ok_or("CHAOS_OUTAGES needs `up/down`")?;
+        let (up, down) = (parse_range(up)?, parse_range(down)?);
+        options = options.with_outages(up.0..=up.1, down.0..=down.1);
+    }
+
+    let proxy = ChaosProxy::start(&listen, upstream.clone(), options).await?;
+    println!(
+        "Chaos proxy listening on {} -> {} (seed {})",
+        proxy.endpoint(),
+        upstream,
+        seed
+    );
+    // Runs until killed.
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+fn parse_rule(rule: &str) -> Result<(Fault, Schedule), Box<dyn std::error::Error>> {
+    let (fault, schedule) = rule
+        .split_once('@')
+        .ok_or_else(|| format!("fault `{rule}` needs `@<schedule>`"))?;
+    let fault = match fault.split_once(':') {
+        None if fault == "drop" => Fault::Drop,
+        None if fault == "cut" => Fault::Cut,
+        None if fault == "goaway" => Fault::GoAway {
+            grace: Duration::from_secs(1),
+        },
+        Some(("goaway", grace)) => Fault::GoAway {
+            grace: parse_duration(grace)?,
+        },
+        Some(("stall", duration)) => Fault::Stall(parse_duration(duration)?),
+        _ => return Err(format!("unknown fault `{fault}`").into()),
+    };
+    let schedule = match schedule.split_once('-') {
+        Some(_) => {
+            let (min, max) = parse_range(schedule)?;
+            Schedule::Every(min..=max)
+        }
+        None => Schedule::After(parse_duration(schedule)?),
+    };
+    Ok((fault, schedule))
+}
+
+fn parse_range(range: &str) -> Result<(Duration, Duration), Box<dyn std::error::Error>> {
+    let (min, max) = range
+        .split_once('-')
+        .ok_or_else(|| format!("`{range}` is not a `<min>-<max>` range"))?;
+    Ok((parse_duration(min)?, parse_duration(max)?))
+}
+
+/// `250ms`, `20s` or `2m`.
+fn parse_duration(value: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("`{value}` is not a duration"))?;
+    Ok(match unit {
+        "ms" => Duration::from_millis(number),
+        "s" => Duration::from_secs(number),
+        "m" => Duration::from_secs(number * 60),
+        _ => return Err(format!("`{value}` needs a unit: ms, s or m").into()),
+    })
+}
@@ -28,7 +28,9 @@ const MIN_PHASE3_MSGS: u64 = 5;
 
 /// Test for subscription replacement behavior and persistence across reconnections.
 ///
-/// Requires the JS chaos proxy running:
+/// Requires a chaos proxy running, either the Rust one:
+///   CHAOS_UPSTREAM=<host:port> cargo run --features testing --bin chaos_proxy
+/// or the JS one:
 ///   cd javascript && npx ts-node test/laserstreamChaosProxy.ts
 ///
 /// Run:
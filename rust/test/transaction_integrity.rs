@@ -10,7 +10,6 @@ use helius_laserstream::{
 use laserstream_core_client::{ClientTlsConfig, GeyserGrpcClient};
 use tokio_stream::StreamExt;
 use tracing::{error, info, warn};
-use bs58;
 use std::io::{self, Write};
 
 #[tokio::main]
@@ -56,7 +55,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let max_slot_ys = Arc::new(Mutex::new(0u64));
 
     // For display and match when both slots known
-    let status_map: Arc<Mutex<HashMap<String, (Option<u64>, Option<u64>)>>> = Arc::new(Mutex::new(HashMap::new()));
+    type StatusMap = HashMap<String, (Option<u64>, Option<u64>)>;
+    let status_map: Arc<Mutex<StatusMap>> = Arc::new(Mutex::new(HashMap::new()));
 
     // Counters for periodic report
     let new_ls = Arc::new(Mutex::new(0u64));
@@ -99,8 +99,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     let mut st = status_map.lock().await;
                                     let entry = st.entry(sig_str.clone()).or_insert((None, None));
                                     entry.0 = Some(slot);
-                                    if entry.0.is_some() && entry.1.is_some() {
-                                        info!("MATCH {}  LS_slot={}  YS_slot={}", sig_str, slot, entry.1.unwrap());
+                                    if let Some(ys_slot) = entry.1 {
+                                        info!("MATCH {}  LS_slot={}  YS_slot={}", sig_str, slot, ys_slot);
                                         st.remove(&sig_str);
                                         println!("[LS] sig={} slot={}", sig_str, slot);
                                         io::stdout().flush().ok();
@@ -162,8 +162,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     let mut st = status_map.lock().await;
                                     let entry = st.entry(sig_str.clone()).or_insert((None, None));
                                     entry.1 = Some(slot);
-                                    if entry.0.is_some() && entry.1.is_some() {
-                                        info!("MATCH {}  LS_slot={}  YS_slot={}", sig_str, entry.0.unwrap(), slot);
+                                    if let Some(ls_slot) = entry.0 {
+                                        info!("MATCH {}  LS_slot={}  YS_slot={}", sig_str, ls_slot, slot);
                                         st.remove(&sig_str);
                                         println!("[YS] sig={} slot={}", sig_str, slot);
                                         io::stdout().flush().ok();
@@ -0,0 +1,87 @@
+//! AccountCache: replay ordering, owner index, commitment views and rollbacks.
+
+mod common;
+
+use common::{account_update, slot_update};
+use helius_laserstream::accounts::AccountCache;
+use helius_laserstream::grpc::{
+    CommitmentLevel, SlotStatus, SubscribeUpdate, SubscribeUpdateAccountInfo,
+};
+
+const KEY: [u8; 32] = [1; 32];
+const OWNER: [u8; 32] = [9; 32];
+
+fn account(slot: u64, write_version: u64, lamports: u64, owner: [u8; 32]) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: KEY.to_vec(),
+            lamports,
+            owner: owner.to_vec(),
+            write_version,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn replays_never_move_state_backwards() {
+    let mut cache = AccountCache::new();
+    cache.apply(&account(10, 5, 100, OWNER));
+    cache.apply(&account(10, 7, 200, OWNER));
+    // Reconnect replays older writes.
+    cache.apply(&account(9, 50, 300, OWNER));
+    cache.apply(&account(10, 6, 400, OWNER));
+
+    assert_eq!(cache.get(&KEY).unwrap().account.lamports, 200);
+    assert_eq!(cache.by_owner(&OWNER).count(), 1);
+
+    // Closing the account hides it.
+    cache.apply(&account(11, 1, 0, OWNER));
+    assert!(cache.get(&KEY).is_none());
+    assert_eq!(cache.by_owner(&OWNER).count(), 0);
+}
+
+#[test]
+fn commitment_views_follow_slot_statuses() {
+    let mut cache = AccountCache::new();
+    cache.apply(&slot_update(10, 9, SlotStatus::SlotProcessed));
+    cache.apply(&account(10, 1, 100, OWNER));
+    cache.apply(&slot_update(11, 10, SlotStatus::SlotProcessed));
+    cache.apply(&account(11, 1, 200, [8; 32]));
+
+    assert_eq!(cache.get(&KEY).unwrap().account.lamports, 200);
+    assert!(cache.view(CommitmentLevel::Confirmed).get(&KEY).is_none());
+
+    cache.apply(&slot_update(10, 9, SlotStatus::SlotConfirmed));
+    let confirmed = cache.view(CommitmentLevel::Confirmed);
+    assert_eq!(confirmed.get(&KEY).unwrap().account.lamports, 100);
+    assert_eq!(confirmed.by_owner(&OWNER).count(), 1);
+    assert_eq!(cache.by_owner(&OWNER).count(), 0);
+
+    cache.apply(&slot_update(11, 10, SlotStatus::SlotFinalized));
+    let finalized = cache.view(CommitmentLevel::Finalized);
+    assert_eq!(finalized.get(&KEY).unwrap().account.lamports, 200);
+}
+
+#[tokio::test]
+async fn dead_slot_rolls_back_and_notifies_watchers() {
+    let mut cache = AccountCache::new();
+    let mut watcher = cache.watch(&KEY);
+    assert!(watcher.borrow().is_none());
+
+    cache.apply(&slot_update(20, 19, SlotStatus::SlotProcessed));
+    cache.apply(&account(20, 1, 100, OWNER));
+    watcher.changed().await.unwrap();
+    assert_eq!(watcher.borrow_and_update().as_ref().unwrap().account.lamports, 100);
+
+    cache.apply(&slot_update(21, 20, SlotStatus::SlotProcessed));
+    cache.apply(&account(21, 1, 500, OWNER));
+    watcher.changed().await.unwrap();
+    assert_eq!(watcher.borrow_and_update().as_ref().unwrap().account.lamports, 500);
+
+    cache.apply(&slot_update(21, 20, SlotStatus::SlotDead));
+    watcher.changed().await.unwrap();
+    assert_eq!(watcher.borrow_and_update().as_ref().unwrap().account.lamports, 100);
+    assert_eq!(cache.get(&KEY).unwrap().slot, 20);
+}
@@ -0,0 +1,180 @@
+//! accounts::decode: token accounts with Token-2022 extensions, stake and vote
+//! accounts, and fields recovered from sliced data.
+
+use helius_laserstream::accounts::decode::{
+    decode, decode_account, decode_sliced, AccountKind, DecodedAccount, FieldValue, StakeAccount,
+    TokenAccountState, TokenExtension,
+};
+use helius_laserstream::grpc::{SubscribeRequestAccountsDataSlice, SubscribeUpdateAccountInfo};
+use helius_laserstream::instructions::{
+    STAKE_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, VOTE_PROGRAM_ID,
+};
+
+fn program(id: &str) -> Vec<u8> {
+    bs58::decode(id).into_vec().unwrap()
+}
+
+fn key(byte: u8) -> String {
+    bs58::encode([byte; 32]).into_string()
+}
+
+/// 165-byte token account: mint [1; 32], owner [2; 32], no delegate, initialized.
+fn token_account(amount: u64) -> Vec<u8> {
+    let mut data = vec![1; 32];
+    data.extend_from_slice(&[2; 32]);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&[0; 36]); // delegate: None
+    data.push(1); // initialized
+    data.extend_from_slice(&[0; 12]); // is_native: None
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes()); // close_authority: Some
+    data.extend_from_slice(&[3; 32]);
+    assert_eq!(data.len(), 165);
+    data
+}
+
+#[test]
+fn decodes_token_2022_account_with_extensions() {
+    let mut data = token_account(5_000);
+    data.push(2); // account type: Account
+    data.extend_from_slice(&7u16.to_le_bytes()); // ImmutableOwner
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes()); // TransferFeeAmount
+    data.extend_from_slice(&8u16.to_le_bytes());
+    data.extend_from_slice(&25u64.to_le_bytes());
+    data.extend_from_slice(&99u16.to_le_bytes()); // unknown
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(&[0xAA, 0xBB]);
+
+    let info = SubscribeUpdateAccountInfo {
+        owner: program(TOKEN_2022_PROGRAM_ID),
+        data,
+        ..Default::default()
+    };
+    let Some(DecodedAccount::TokenAccount(account)) = decode(&info) else {
+        panic!("expected a token account");
+    };
+    assert_eq!(account.mint, key(1));
+    assert_eq!(account.owner, key(2));
+    assert_eq!(account.amount, 5_000);
+    assert_eq!(account.delegate, None);
+    assert_eq!(account.state, TokenAccountState::Initialized);
+    assert_eq!(account.close_authority, Some(key(3)));
+    assert_eq!(
+        account.extensions,
+        vec![
+            TokenExtension::ImmutableOwner,
+            TokenExtension::TransferFeeAmount {
+                withheld_amount: 25
+            },
+            TokenExtension::Other {
+                extension_type: 99,
+                data: vec![0xAA, 0xBB],
+            },
+        ]
+    );
+
+    // The same bytes under SPL Token are not a valid account size.
+    assert_eq!(decode_account(&program(TOKEN_PROGRAM_ID), &info.data), None);
+    assert!(decode_account(&program(TOKEN_PROGRAM_ID), &token_account(1)).is_some());
+}
+
+#[test]
+fn decodes_stake_and_vote_accounts() {
+    let mut stake = 2u32.to_le_bytes().to_vec();
+    stake.extend_from_slice(&2_282_880u64.to_le_bytes());
+    stake.extend_from_slice(&[4; 32]); // staker
+    stake.extend_from_slice(&[5; 32]); // withdrawer
+    stake.extend_from_slice(&[0; 48]); // lockup
+    stake.extend_from_slice(&[6; 32]); // voter
+    stake.extend_from_slice(&1_000_000u64.to_le_bytes());
+    stake.extend_from_slice(&500u64.to_le_bytes());
+    stake.extend_from_slice(&u64::MAX.to_le_bytes());
+    stake.extend_from_slice(&0.25f64.to_le_bytes());
+    stake.extend_from_slice(&77u64.to_le_bytes());
+    stake.extend_from_slice(&[0; 4]);
+
+    let Some(DecodedAccount::Stake(StakeAccount::Delegated { meta, delegation })) =
+        decode_account(&program(STAKE_PROGRAM_ID), &stake)
+    else {
+        panic!("expected a delegated stake account");
+    };
+    assert_eq!(meta.staker, key(4));
+    assert_eq!(meta.withdrawer, key(5));
+    assert_eq!(delegation.voter, key(6));
+    assert_eq!(delegation.stake, 1_000_000);
+    assert_eq!(delegation.deactivation_epoch, u64::MAX);
+    assert_eq!(delegation.warmup_cooldown_rate, 0.25);
+    assert_eq!(delegation.credits_observed, 77);
+
+    let mut vote = 2u32.to_le_bytes().to_vec();
+    vote.extend_from_slice(&[7; 32]);
+    vote.extend_from_slice(&[8; 32]);
+    vote.push(10);
+    vote.extend_from_slice(&1u64.to_le_bytes()); // one landed vote
+    vote.push(1);
+    vote.extend_from_slice(&300u64.to_le_bytes());
+    vote.extend_from_slice(&1u32.to_le_bytes());
+    vote.push(1);
+    vote.extend_from_slice(&250u64.to_le_bytes()); // root slot
+    vote.extend_from_slice(&1u64.to_le_bytes());
+    vote.extend_from_slice(&12u64.to_le_bytes());
+    vote.extend_from_slice(&[9; 32]);
+    vote.extend_from_slice(&[0; 1545]); // prior voters
+    vote.extend_from_slice(&1u64.to_le_bytes());
+    for value in [12u64, 4_000, 3_000] {
+        vote.extend_from_slice(&value.to_le_bytes());
+    }
+    vote.extend_from_slice(&300u64.to_le_bytes());
+    vote.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+
+    let Some(DecodedAccount::Vote(vote)) = decode_account(&program(VOTE_PROGRAM_ID), &vote) else {
+        panic!("expected a vote account");
+    };
+    assert_eq!(vote.node_pubkey, key(7));
+    assert_eq!(vote.commission, 10);
+    assert_eq!(vote.votes[0].slot, 300);
+    assert_eq!(vote.votes[0].latency, Some(1));
+    assert_eq!(vote.root_slot, Some(250));
+    assert_eq!(vote.authorized_voters, vec![(12, key(9))]);
+    assert_eq!(vote.epoch_credits[0].credits, 4_000);
+    assert_eq!(vote.last_timestamp, 1_700_000_000);
+}
+
+#[test]
+fn sliced_token_account_reports_unavailable_fields() {
+    // mint..amount, then close_authority tag only: the key itself is cut off.
+    let slices = [
+        SubscribeRequestAccountsDataSlice {
+            offset: 0,
+            length: 72,
+        },
+        SubscribeRequestAccountsDataSlice {
+            offset: 129,
+            length: 4,
+        },
+    ];
+    let full = token_account(42);
+    let mut data = full[..72].to_vec();
+    data.extend_from_slice(&full[129..133]);
+
+    assert_eq!(decode_account(&program(TOKEN_PROGRAM_ID), &data), None);
+
+    let partial = decode_sliced(AccountKind::TokenAccount, &slices, &data);
+    assert_eq!(partial.get("mint"), Some(&FieldValue::Pubkey(key(1))));
+    assert_eq!(partial.get("amount"), Some(&FieldValue::U64(42)));
+    assert!(!partial.is_complete());
+    assert_eq!(
+        partial.unavailable,
+        vec![
+            "delegate",
+            "state",
+            "is_native",
+            "delegated_amount",
+            "close_authority"
+        ]
+    );
+
+    let partial = decode_sliced(AccountKind::Token2022Account, &slices, &data);
+    assert_eq!(partial.unavailable.last(), Some(&"extensions"));
+}
@@ -0,0 +1,265 @@
+//! AnchorIdl: discriminators from current and legacy IDLs, borsh values, and events
+//! from logs and self-CPIs.
+
+use base64::Engine;
+use helius_laserstream::anchor::{AnchorIdl, EVENT_IX_TAG};
+use helius_laserstream::grpc::{SubscribeUpdateAccountInfo, SubscribeUpdateTransactionInfo};
+use helius_laserstream::instructions::ParsedInstruction;
+use helius_laserstream::solana::storage::confirmed_block::{
+    CompiledInstruction, InnerInstruction, InnerInstructions, Message, Transaction,
+    TransactionStatusMeta,
+};
+use helius_laserstream::{InstructionRegistry, TransactionView};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+const PROGRAM: &str = "Vau1t11111111111111111111111111111111111111";
+
+fn disc(preimage: &str) -> Vec<u8> {
+    Sha256::digest(preimage.as_bytes())[..8].to_vec()
+}
+
+fn idl() -> AnchorIdl {
+    AnchorIdl::from_value(&json!({
+        "address": PROGRAM,
+        "metadata": { "name": "vault", "version": "0.1.0", "spec": "0.1.0" },
+        "instructions": [{
+            "name": "deposit",
+            "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+            "accounts": [
+                { "name": "user", "writable": true, "signer": true },
+                { "name": "vault_accounts", "accounts": [{ "name": "vault" }, { "name": "token" }] }
+            ],
+            "args": [
+                { "name": "amount", "type": "u64" },
+                { "name": "memo", "type": { "option": "string" } }
+            ]
+        }, {
+            "name": "mark",
+            "discriminator": [8, 7, 6, 5, 4, 3, 2, 1],
+            "accounts": [],
+            "args": [{ "name": "markers", "type": { "vec": { "defined": { "name": "Marker" } } } }]
+        }],
+        "accounts": [{ "name": "Vault", "discriminator": disc("account:Vault") }],
+        "events": [{ "name": "Deposited", "discriminator": disc("event:Deposited") }],
+        "types": [
+            {
+                "name": "Vault",
+                "type": { "kind": "struct", "fields": [
+                    { "name": "authority", "type": "pubkey" },
+                    { "name": "total", "type": "u128" },
+                    { "name": "status", "type": { "defined": { "name": "Status" } } },
+                    { "name": "history", "type": { "vec": "i16" } }
+                ] }
+            },
+            {
+                "name": "Status",
+                "type": { "kind": "enum", "variants": [
+                    { "name": "Open" },
+                    { "name": "Locked", "fields": [{ "name": "until", "type": "i64" }] }
+                ] }
+            },
+            { "name": "Marker", "type": { "kind": "struct", "fields": [] } },
+            {
+                "name": "Deposited",
+                "type": { "kind": "struct", "fields": [{ "name": "amount", "type": "u64" }] }
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+fn deposited(amount: u64) -> Vec<u8> {
+    let mut data = disc("event:Deposited");
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+#[test]
+fn decodes_accounts_and_instructions() {
+    let idl = idl();
+
+    let mut data = disc("account:Vault");
+    data.extend_from_slice(&[9; 32]);
+    data.extend_from_slice(&(u64::MAX as u128 + 1).to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&(-5i16).to_le_bytes());
+    data.extend_from_slice(&7i16.to_le_bytes());
+
+    let info = SubscribeUpdateAccountInfo {
+        owner: bs58::decode(PROGRAM).into_vec().unwrap(),
+        data,
+        ..Default::default()
+    };
+    let account = idl.decode_account_info(&info).unwrap();
+    assert_eq!(account.name, "Vault");
+    assert_eq!(
+        account.data,
+        json!({
+            "authority": bs58::encode([9; 32]).into_string(),
+            "total": "18446744073709551616",
+            "status": { "Locked": { "until": 1_700_000_000 } },
+            "history": [-5, 7],
+        })
+    );
+    let foreign = SubscribeUpdateAccountInfo {
+        owner: vec![0; 32],
+        ..info
+    };
+    assert!(idl.decode_account_info(&foreign).is_none());
+
+    let mut data = disc("account:Vault");
+    data.extend_from_slice(&[9; 32]);
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(&0u32.to_le_bytes());
+    let open = SubscribeUpdateAccountInfo {
+        owner: bs58::decode(PROGRAM).into_vec().unwrap(),
+        data,
+        ..Default::default()
+    };
+    assert_eq!(
+        idl.decode_account_info(&open).unwrap().data["status"],
+        "Open"
+    );
+
+    let mut ix = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    ix.extend_from_slice(&250u64.to_le_bytes());
+    ix.push(0);
+    let decoded = idl.decode_instruction(&ix).unwrap();
+    assert_eq!(decoded.name, "deposit");
+    assert_eq!(decoded.args, json!({ "amount": 250, "memo": null }));
+    assert_eq!(decoded.accounts, vec!["user", "vault", "token"]);
+
+    // Elements that take no bytes aren't bounded by the data length.
+    let mut ix = vec![8, 7, 6, 5, 4, 3, 2, 1];
+    ix.extend_from_slice(&3u32.to_le_bytes());
+    let decoded = idl.decode_instruction(&ix).unwrap();
+    assert_eq!(decoded.args, json!({ "markers": [[], [], []] }));
+}
+
+#[test]
+fn legacy_idl_hashes_names() {
+    let idl = AnchorIdl::from_json(
+        r#"{
+            "version": "0.1.0",
+            "name": "legacy",
+            "instructions": [{
+                "name": "setLimit",
+                "accounts": [{ "name": "admin", "isMut": false, "isSigner": true }],
+                "args": [{ "name": "limit", "type": "u32" }]
+            }],
+            "accounts": [{
+                "name": "Config",
+                "type": { "kind": "struct", "fields": [{ "name": "admin", "type": "publicKey" }] }
+            }],
+            "events": [{ "name": "LimitSet", "fields": [{ "name": "limit", "type": "u32", "index": false }] }]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(idl.program_id(), None);
+
+    let mut ix = disc("global:set_limit");
+    ix.extend_from_slice(&10u32.to_le_bytes());
+    assert_eq!(
+        idl.decode_instruction(&ix).unwrap().args,
+        json!({ "limit": 10 })
+    );
+
+    let mut account = disc("account:Config");
+    account.extend_from_slice(&[1; 32]);
+    assert_eq!(
+        idl.decode_account(&account).unwrap().data,
+        json!({ "admin": bs58::encode([1; 32]).into_string() })
+    );
+
+    let mut event = disc("event:LimitSet");
+    event.extend_from_slice(&3u32.to_le_bytes());
+    assert_eq!(
+        idl.decode_event(&event).unwrap().data,
+        json!({ "limit": 3 })
+    );
+
+    assert!(AnchorIdl::from_json("{ \"instructions\": [{}] }").is_err());
+}
+
+#[test]
+fn events_from_logs_and_self_cpi() {
+    let idl = idl();
+    let b64 = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+    let other = "Other11111111111111111111111111111111111111";
+    let logs = vec![
+        format!("Program {PROGRAM} invoke [1]"),
+        format!("Program data: {}", b64(&deposited(5))),
+        format!("Program {other} invoke [2]"),
+        // Logged by another program: ignored even though it decodes.
+        format!("Program data: {}", b64(&deposited(6))),
+        format!("Program {other} success"),
+        format!("Program data: {}", b64(&deposited(7))),
+        format!("Program {PROGRAM} success"),
+    ];
+
+    let mut cpi = EVENT_IX_TAG.to_vec();
+    cpi.extend_from_slice(&deposited(8));
+    let mut deposit = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    deposit.extend_from_slice(&1u64.to_le_bytes());
+    deposit.push(0);
+    let info = SubscribeUpdateTransactionInfo {
+        signature: vec![1; 64],
+        transaction: Some(Transaction {
+            signatures: vec![vec![1; 64]],
+            message: Some(Message {
+                account_keys: vec![vec![3; 32], bs58::decode(PROGRAM).into_vec().unwrap()],
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![0],
+                    data: deposit,
+                }],
+                ..Default::default()
+            }),
+        }),
+        meta: Some(TransactionStatusMeta {
+            log_messages: logs,
+            inner_instructions: vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    program_id_index: 1,
+                    accounts: vec![],
+                    data: cpi,
+                    stack_height: Some(2),
+                }],
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let view = TransactionView::new(1, &info).unwrap();
+
+    let amounts: Vec<_> = idl
+        .decode_events(&view)
+        .into_iter()
+        .map(|event| event.data["amount"].clone())
+        .collect();
+    assert_eq!(amounts, vec![json!(5), json!(7), json!(8)]);
+
+    let decoded = InstructionRegistry::with_builtins()
+        .with(idl)
+        .decode_transaction(&view);
+    assert_eq!(decoded[0].program.as_deref(), Some("vault"));
+    assert_eq!(
+        decoded[0].parsed,
+        Some(ParsedInstruction::Other(json!({
+            "name": "deposit",
+            "args": { "amount": 1, "memo": null },
+            "accounts": { "user": bs58::encode([3; 32]).into_string() },
+        })))
+    );
+    assert_eq!(
+        decoded[1].parsed,
+        Some(ParsedInstruction::Other(
+            json!({ "event": "Deposited", "data": { "amount": 8 } })
+        ))
+    );
+}
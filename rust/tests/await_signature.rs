@@ -0,0 +1,177 @@
+//! SignatureWaitlist: per-signature filters and commitment-aware resolution behind
+//! `LaserstreamClient::await_signature`, and the client's shared subscription.
+
+mod common;
+
+use common::{slot, slot_update};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus, SubscribeUpdate,
+    SubscribeUpdateTransactionStatus,
+};
+use helius_laserstream::solana::storage::confirmed_block::TransactionError;
+use helius_laserstream::testing::MockGeyser;
+use helius_laserstream::{LaserstreamClient, SignatureWaitlist};
+use std::time::Duration;
+
+const SIG: [u8; 64] = [7; 64];
+
+fn status(signature: &[u8], slot: u64, index: u64, err: Option<Vec<u8>>) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::TransactionStatus(
+            SubscribeUpdateTransactionStatus {
+                slot,
+                signature: signature.to_vec(),
+                index,
+                err: err.map(|err| TransactionError { err }),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn resolves_each_waiter_at_its_commitment() {
+    let mut waitlist = SignatureWaitlist::new();
+    let (_, mut processed, added) = waitlist.register(&SIG, CommitmentLevel::Processed);
+    assert!(added);
+    let (_, mut confirmed, added) = waitlist.register(&SIG, CommitmentLevel::Confirmed);
+    assert!(!added);
+
+    let request = waitlist.request();
+    let filter = &request.transactions_status
+        [&format!("await-signature:{}", bs58::encode(SIG).into_string())];
+    assert_eq!(filter.signature, Some(bs58::encode(SIG).into_string()));
+
+    waitlist.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    waitlist.apply(&status(&SIG, 10, 3, Some(vec![8, 0, 0, 0, 1])));
+    let landed = processed.try_recv().unwrap();
+    assert_eq!((landed.slot, landed.index), (10, 3));
+    assert_eq!(landed.commitment, CommitmentLevel::Processed);
+    assert!(!landed.is_success());
+    assert!(confirmed.try_recv().is_err());
+
+    waitlist.slot(&slot(10, 9, SlotStatus::SlotConfirmed));
+    let landed = confirmed.try_recv().unwrap();
+    assert_eq!(landed.commitment, CommitmentLevel::Confirmed);
+    assert!(waitlist.is_empty());
+    assert!(waitlist.request().transactions_status.is_empty());
+}
+
+#[test]
+fn keeps_waiting_when_the_landing_slot_is_orphaned() {
+    let mut waitlist = SignatureWaitlist::new();
+    let (_, mut receiver, _) = waitlist.register(&SIG, CommitmentLevel::Confirmed);
+
+    waitlist.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    waitlist.slot(&slot(11, 9, SlotStatus::SlotProcessed));
+    waitlist.apply(&status(&SIG, 10, 0, None));
+    waitlist.slot(&slot(11, 9, SlotStatus::SlotConfirmed));
+    assert!(receiver.try_recv().is_err());
+
+    waitlist.apply(&status(&SIG, 11, 5, None));
+    waitlist.slot(&slot(12, 11, SlotStatus::SlotConfirmed));
+    waitlist.slot(&slot(11, 9, SlotStatus::SlotFinalized));
+    let landed = receiver.try_recv().unwrap();
+    assert_eq!((landed.slot, landed.index), (11, 5));
+    assert!(landed.is_success());
+}
+
+#[test]
+fn cancelling_the_last_waiter_drops_the_filter() {
+    let mut waitlist = SignatureWaitlist::new();
+    let (first, _a, _) = waitlist.register(&SIG, CommitmentLevel::Finalized);
+    let (second, _b, _) = waitlist.register(&SIG, CommitmentLevel::Finalized);
+
+    assert!(!waitlist.cancel(&SIG, first));
+    assert_eq!(waitlist.request().transactions_status.len(), 1);
+    assert!(waitlist.cancel(&SIG, second));
+    assert!(waitlist.request().transactions_status.is_empty());
+    assert!(!waitlist.cancel(&SIG, second));
+}
+
+/// Waits for the next request whose signature filters satisfy `check`.
+async fn wait_for_filters(mock: &MockGeyser, seen: &mut usize, check: impl Fn(usize) -> bool) {
+    loop {
+        *seen += 1;
+        tokio::time::timeout(Duration::from_secs(10), mock.wait_for_requests(*seen))
+            .await
+            .expect("no request within 10s");
+        if check(mock.requests()[*seen - 1].transactions_status.len()) {
+            return;
+        }
+    }
+}
+
+#[tokio::test]
+async fn resolves_through_the_shared_subscription() {
+    let mock = MockGeyser::start().await.unwrap();
+    let client = LaserstreamClient::new(mock.config());
+    let landed = client.await_signature(&SIG, CommitmentLevel::Confirmed, Duration::from_secs(10));
+    let mut seen = 0;
+    wait_for_filters(&mock, &mut seen, |filters| filters == 1).await;
+
+    for update in [
+        slot_update(10, 9, SlotStatus::SlotProcessed),
+        status(&SIG, 10, 2, None),
+        // Not awaited: filtered out by the server.
+        status(&[8; 64], 10, 3, None),
+        slot_update(10, 9, SlotStatus::SlotConfirmed),
+    ] {
+        mock.push(update);
+    }
+    let landed = landed.await.unwrap();
+    assert_eq!((landed.slot, landed.index), (10, 2));
+    assert_eq!(landed.commitment, CommitmentLevel::Confirmed);
+    // The resolved signature's filter is removed.
+    wait_for_filters(&mock, &mut seen, |filters| filters == 0).await;
+}
+
+#[tokio::test]
+async fn dropping_the_wait_removes_its_filter() {
+    let mock = MockGeyser::start().await.unwrap();
+    let client = LaserstreamClient::new(mock.config());
+    let kept = client.await_signature(&SIG, CommitmentLevel::Finalized, Duration::from_secs(10));
+    let dropped = client.await_signature(
+        &[8; 64],
+        CommitmentLevel::Finalized,
+        Duration::from_secs(10),
+    );
+    let mut seen = 0;
+    wait_for_filters(&mock, &mut seen, |filters| filters == 2).await;
+
+    // Never polled: dropping it still cancels the wait.
+    drop(dropped);
+    wait_for_filters(&mock, &mut seen, |filters| filters == 1).await;
+    let request = mock.requests().pop().unwrap();
+    assert!(request.transactions_status.contains_key(&format!(
+        "await-signature:{}",
+        bs58::encode(SIG).into_string()
+    )));
+
+    drop(kept);
+    wait_for_filters(&mock, &mut seen, |filters| filters == 0).await;
+}
+
+#[tokio::test]
+async fn a_burst_of_waits_is_written_together() {
+    let mock = MockGeyser::start().await.unwrap();
+    let client = LaserstreamClient::new(mock.config());
+    let waits: Vec<_> = (0..100u8)
+        .map(|byte| {
+            client.await_signature(
+                &[byte; 64],
+                CommitmentLevel::Confirmed,
+                Duration::from_secs(10),
+            )
+        })
+        .collect();
+    let mut seen = 0;
+    wait_for_filters(&mock, &mut seen, |filters| filters == 100).await;
+    // The initial subscription, then one write for the whole burst.
+    assert!(seen <= 3, "{seen} requests for one burst of waits");
+
+    drop(waits);
+    wait_for_filters(&mock, &mut seen, |filters| filters == 0).await;
+    assert!(seen <= 5, "{seen} requests after dropping the burst");
+}
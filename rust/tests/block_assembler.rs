@@ -0,0 +1,131 @@
+//! BlockAssembler: completion against block-meta counts, duplicates and timeouts;
+//! assemble_blocks' report of slots pending at end of stream.
+
+use futures::StreamExt;
+use helius_laserstream::blocks::{
+    assemble_blocks, BlockAssembler, BlockAssemblyOptions, IncompleteBlock,
+};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateBlockMeta,
+    SubscribeUpdateEntry, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::LaserstreamError;
+use std::time::{Duration, Instant};
+
+fn wrap(update: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec!["block-assembly".to_string()],
+        update_oneof: Some(update),
+        created_at: None,
+    }
+}
+
+fn tx(slot: u64, index: u64) -> SubscribeUpdate {
+    wrap(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+        transaction: Some(SubscribeUpdateTransactionInfo {
+            signature: vec![index as u8; 64],
+            index,
+            ..Default::default()
+        }),
+        slot,
+    }))
+}
+
+fn entry(slot: u64, index: u64) -> SubscribeUpdate {
+    wrap(UpdateOneof::Entry(SubscribeUpdateEntry {
+        slot,
+        index,
+        ..Default::default()
+    }))
+}
+
+fn meta(slot: u64, transactions: u64, entries: u64) -> SubscribeUpdate {
+    wrap(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+        slot,
+        blockhash: "hash".to_string(),
+        parent_slot: slot - 1,
+        executed_transaction_count: transactions,
+        entries_count: entries,
+        ..Default::default()
+    }))
+}
+
+#[test]
+fn emits_block_once_counts_match() {
+    let mut assembler = BlockAssembler::default();
+    assert!(assembler.push(&meta(100, 3, 2)).is_none());
+    assert!(assembler.push(&tx(100, 2)).is_none());
+    assert!(assembler.push(&tx(100, 0)).is_none());
+    // A replayed duplicate does not count twice.
+    assert!(assembler.push(&tx(100, 0)).is_none());
+    assert!(assembler.push(&entry(100, 1)).is_none());
+    assert!(assembler.push(&entry(100, 0)).is_none());
+
+    let block = assembler.push(&tx(100, 1)).expect("block complete");
+    assert_eq!(block.slot, 100);
+    assert_eq!(block.parent_slot, 99);
+    assert_eq!(block.blockhash, "hash");
+    let indexes: Vec<u64> = block.transactions.iter().map(|t| t.index).collect();
+    assert_eq!(indexes, vec![0, 1, 2]);
+    assert_eq!(block.entries.len(), 2);
+    assert_eq!(assembler.pending_slots(), 0);
+
+    // Stragglers for an emitted slot are ignored.
+    assert!(assembler.push(&tx(100, 3)).is_none());
+    assert_eq!(assembler.pending_slots(), 0);
+}
+
+#[test]
+fn empty_block_completes_on_meta() {
+    let mut assembler = BlockAssembler::default();
+    let block = assembler.push(&meta(7, 0, 0)).expect("nothing to wait for");
+    assert!(block.transactions.is_empty());
+}
+
+#[test]
+fn reports_incomplete_after_timeout() {
+    let mut assembler =
+        BlockAssembler::new(BlockAssemblyOptions::default().with_timeout(Duration::from_secs(5)));
+    let start = Instant::now();
+    assembler.push_at(&meta(200, 2, 1), start);
+    assembler.push_at(&tx(200, 0), start);
+    assembler.push_at(&tx(201, 0), start + Duration::from_secs(3));
+
+    assert!(assembler.expire(start + Duration::from_secs(4)).is_empty());
+    let expired = assembler.expire(start + Duration::from_secs(6));
+    assert_eq!(
+        expired,
+        vec![IncompleteBlock {
+            slot: 200,
+            transactions: 1,
+            expected_transactions: Some(2),
+            entries: 0,
+            expected_entries: Some(1),
+        }]
+    );
+
+    let expired = assembler.expire(start + Duration::from_secs(9));
+    assert_eq!(expired[0].slot, 201);
+    assert_eq!(expired[0].expected_transactions, None);
+    assert_eq!(assembler.pending_slots(), 0);
+}
+
+#[tokio::test]
+async fn reports_pending_slots_when_the_stream_ends() {
+    let updates = vec![meta(300, 0, 0), meta(301, 1, 0), tx(302, 0)];
+    let stream = futures::stream::iter(updates.into_iter().map(Ok));
+    let items: Vec<_> = assemble_blocks(stream, BlockAssemblyOptions::default())
+        .collect()
+        .await;
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_ref().unwrap().slot, 300);
+    let incomplete: Vec<u64> = items[1..]
+        .iter()
+        .map(|item| match item {
+            Err(LaserstreamError::IncompleteBlock(block)) => block.slot,
+            other => panic!("unexpected item: {other:?}"),
+        })
+        .collect();
+    assert_eq!(incomplete, vec![301, 302]);
+}
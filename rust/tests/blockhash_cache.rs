@@ -0,0 +1,102 @@
+//! BlockhashCache: per-commitment latest blockhash, validity window, seeding and
+//! orphaned slots.
+
+mod common;
+
+use common::slot;
+use helius_laserstream::blockhash::MAX_PROCESSING_AGE;
+use helius_laserstream::grpc::{
+    CommitmentLevel, GetLatestBlockhashResponse, SlotStatus, SubscribeUpdateBlockMeta,
+};
+use helius_laserstream::solana::storage::confirmed_block::BlockHeight;
+use helius_laserstream::BlockhashCache;
+
+fn meta(slot: u64, height: u64) -> SubscribeUpdateBlockMeta {
+    SubscribeUpdateBlockMeta {
+        slot,
+        blockhash: format!("hash-{slot}"),
+        block_height: Some(BlockHeight {
+            block_height: height,
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn serves_the_freshest_blockhash_per_commitment() {
+    let cache = BlockhashCache::new();
+    cache.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    cache.block_meta(&meta(10, 1_000));
+    cache.slot(&slot(11, 10, SlotStatus::SlotProcessed));
+    cache.block_meta(&meta(11, 1_001));
+
+    let processed = cache.latest(CommitmentLevel::Processed).unwrap();
+    assert_eq!(processed.blockhash, "hash-11");
+    assert_eq!(processed.last_valid_block_height, 1_001 + MAX_PROCESSING_AGE);
+    assert!(cache.latest(CommitmentLevel::Confirmed).is_none());
+
+    cache.slot(&slot(10, 9, SlotStatus::SlotConfirmed));
+    assert_eq!(
+        cache.latest(CommitmentLevel::Confirmed).unwrap().blockhash,
+        "hash-10"
+    );
+    cache.slot(&slot(10, 9, SlotStatus::SlotFinalized));
+    assert_eq!(
+        cache.latest(CommitmentLevel::Finalized).unwrap().blockhash,
+        "hash-10"
+    );
+    assert!(cache.is_valid("hash-10"));
+    assert!(!cache.is_valid("unknown"));
+}
+
+#[test]
+fn seeded_blockhashes_expire_with_block_height() {
+    let cache = BlockhashCache::new();
+    cache.seed(
+        CommitmentLevel::Finalized,
+        &GetLatestBlockhashResponse {
+            slot: 5,
+            blockhash: "seed".to_string(),
+            last_valid_block_height: 500 + MAX_PROCESSING_AGE,
+        },
+    );
+    assert_eq!(cache.block_height(), 500);
+    assert_eq!(
+        cache.latest(CommitmentLevel::Finalized).unwrap().blockhash,
+        "seed"
+    );
+
+    cache.block_meta(&meta(200, 500 + MAX_PROCESSING_AGE));
+    assert!(cache.is_valid("seed"));
+    cache.block_meta(&meta(201, 501 + MAX_PROCESSING_AGE));
+    assert!(!cache.is_valid("seed"));
+    assert!(cache.latest(CommitmentLevel::Finalized).is_none());
+    assert_eq!(
+        cache.latest(CommitmentLevel::Processed).unwrap().blockhash,
+        "hash-201"
+    );
+}
+
+#[test]
+fn orphaned_blocks_lose_their_blockhash() {
+    let cache = BlockhashCache::new();
+    cache.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    cache.block_meta(&meta(10, 1_000));
+    cache.slot(&slot(11, 9, SlotStatus::SlotProcessed));
+    cache.block_meta(&meta(11, 1_000));
+    cache.slot(&slot(12, 10, SlotStatus::SlotProcessed));
+    cache.block_meta(&meta(12, 1_001));
+    assert_eq!(
+        cache.latest(CommitmentLevel::Processed).unwrap().blockhash,
+        "hash-12"
+    );
+
+    // 11 forks off the chain that finalizes; its blockhash can never land.
+    cache.slot(&slot(12, 10, SlotStatus::SlotFinalized));
+    assert!(!cache.is_valid("hash-11"));
+    assert!(cache.is_valid("hash-12"));
+    assert_eq!(
+        cache.latest(CommitmentLevel::Finalized).unwrap().blockhash,
+        "hash-12"
+    );
+}
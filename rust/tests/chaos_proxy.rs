@@ -0,0 +1,156 @@
+//! ChaosProxy: seeded fault plans, latency, and drops, cuts and GOAWAYs in front of
+//! the mock server.
+
+mod common;
+
+use common::slot_update;
+use futures::StreamExt;
+use helius_laserstream::chaos::{ChaosOptions, ChaosProxy, Fault, Schedule};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus, SubscribeRequest,
+    SubscribeRequestFilterSlots, SubscribeUpdate,
+};
+use helius_laserstream::testing::MockGeyser;
+use helius_laserstream::{subscribe, LaserstreamConfig, LaserstreamError};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+fn request() -> SubscribeRequest {
+    let mut request = SubscribeRequest {
+        commitment: Some(CommitmentLevel::Confirmed as i32),
+        ..Default::default()
+    };
+    request
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    request
+}
+
+/// Subscribes through `proxy` and polls the stream in the background.
+fn subscribe_through(
+    proxy: &ChaosProxy,
+) -> mpsc::UnboundedReceiver<Result<SubscribeUpdate, LaserstreamError>> {
+    let config = LaserstreamConfig::new(proxy.endpoint(), "mock-api-key".to_string());
+    let (stream, _handle) = subscribe(config, request());
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            if tx.send(item).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+async fn next_slot(
+    updates: &mut mpsc::UnboundedReceiver<Result<SubscribeUpdate, LaserstreamError>>,
+) -> u64 {
+    let update = tokio::time::timeout(Duration::from_secs(10), updates.recv())
+        .await
+        .expect("no update within 10s")
+        .expect("stream ended")
+        .expect("stream error");
+    match update.update_oneof {
+        Some(UpdateOneof::Slot(slot)) => slot.slot,
+        other => panic!("expected a slot update, got {other:?}"),
+    }
+}
+
+#[test]
+fn plans_are_reproducible_per_seed() {
+    let options = |seed| {
+        ChaosOptions::default()
+            .with_seed(seed)
+            .with_fault(
+                Schedule::Every(Duration::from_secs(1)..=Duration::from_secs(10)),
+                Fault::Stall(Duration::from_millis(200)),
+            )
+            .with_fault(Schedule::After(Duration::from_secs(15)), Fault::Drop)
+    };
+    let plan = |seed, connection| -> Vec<(Duration, Fault)> {
+        options(seed).plan(connection).take(8).collect()
+    };
+
+    assert_eq!(plan(7, 0), plan(7, 0));
+    assert_ne!(plan(7, 0), plan(8, 0));
+    assert_ne!(plan(7, 0), plan(7, 1));
+
+    let plan = plan(7, 0);
+    assert!(plan.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    let drops: Vec<_> = plan
+        .iter()
+        .filter(|(_, fault)| *fault == Fault::Drop)
+        .collect();
+    assert_eq!(drops, vec![&(Duration::from_secs(15), Fault::Drop)]);
+}
+
+#[test]
+fn empty_repeat_ranges_keep_a_minimum_gap() {
+    let options = ChaosOptions::default().with_fault(
+        Schedule::Every(Duration::ZERO..=Duration::ZERO),
+        Fault::Stall(Duration::ZERO),
+    );
+    let at: Vec<_> = options.plan(0).take(3).map(|(at, _)| at).collect();
+    assert_eq!(
+        at,
+        [10, 20, 30].map(Duration::from_millis).to_vec(),
+        "a zero-length range would repeat without pause"
+    );
+}
+
+#[tokio::test]
+async fn adds_latency_and_recovers_from_drops() {
+    let mock = MockGeyser::start().await.unwrap();
+    let options = ChaosOptions::default().with_latency(Duration::from_millis(100), Duration::ZERO);
+    let proxy = ChaosProxy::start("127.0.0.1:0", mock.local_addr().to_string(), options)
+        .await
+        .unwrap();
+    let mut updates = subscribe_through(&proxy);
+    mock.wait_for_requests(1).await;
+
+    let pushed = Instant::now();
+    mock.push(slot_update(10, 9, SlotStatus::SlotConfirmed));
+    assert_eq!(next_slot(&mut updates).await, 10);
+    assert!(pushed.elapsed() >= Duration::from_millis(100));
+
+    proxy.inject(Fault::Drop);
+    mock.wait_for_requests(2).await;
+    assert_eq!(proxy.connections(), 2);
+    // Resumed from slot 10, which is replayed.
+    assert_eq!(next_slot(&mut updates).await, 10);
+}
+
+#[tokio::test]
+async fn goaway_and_cut_frames_force_reconnects() {
+    let mock = MockGeyser::start().await.unwrap();
+    let proxy = ChaosProxy::start(
+        "127.0.0.1:0",
+        mock.local_addr().to_string(),
+        ChaosOptions::default(),
+    )
+    .await
+    .unwrap();
+    let mut updates = subscribe_through(&proxy);
+    mock.wait_for_requests(1).await;
+    mock.push(slot_update(20, 19, SlotStatus::SlotConfirmed));
+    assert_eq!(next_slot(&mut updates).await, 20);
+
+    proxy.inject(Fault::GoAway {
+        grace: Duration::from_millis(200),
+    });
+    mock.wait_for_requests(2).await;
+    assert_eq!(next_slot(&mut updates).await, 20);
+
+    // The next update is truncated mid-frame; the client reconnects instead of
+    // yielding it, then gets it from the replay.
+    proxy.inject(Fault::Cut);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    mock.push(slot_update(21, 20, SlotStatus::SlotConfirmed));
+    mock.wait_for_requests(3).await;
+    assert_eq!(mock.requests()[2].from_slot, Some(20));
+    let replayed = [next_slot(&mut updates).await, next_slot(&mut updates).await];
+    assert_eq!(replayed, [20, 21]);
+    assert_eq!(proxy.connections(), 3);
+}
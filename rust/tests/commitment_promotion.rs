@@ -0,0 +1,183 @@
+//! PromotionTracker: per-slot updates turned into Confirmed/Finalized promotions and
+//! drops, with the identifiers of the updates they cover.
+
+mod common;
+
+use common::{account_update, slot};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus, SubscribeUpdate,
+    SubscribeUpdateAccountInfo, SubscribeUpdateTransactionStatus,
+};
+use helius_laserstream::promotion::{PromotionEvent, PromotionTracker, UpdateId};
+
+fn tx(slot: u64, signature: u8) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec!["txs".to_string()],
+        update_oneof: Some(UpdateOneof::TransactionStatus(
+            SubscribeUpdateTransactionStatus {
+                slot,
+                signature: vec![signature; 64],
+                ..Default::default()
+            },
+        )),
+        created_at: None,
+    }
+}
+
+fn account(slot: u64, pubkey: u8, write_version: u64) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: vec![pubkey; 32],
+            write_version,
+            ..Default::default()
+        },
+    )
+}
+
+fn signature(signature: u8) -> UpdateId {
+    UpdateId::Transaction {
+        signature: vec![signature; 64],
+    }
+}
+
+fn promotions(events: &[PromotionEvent]) -> Vec<(u64, CommitmentLevel, Vec<UpdateId>)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            PromotionEvent::Promoted {
+                slot,
+                commitment,
+                updates,
+            } => Some((*slot, *commitment, updates.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn dropped(events: &[PromotionEvent]) -> Vec<(u64, usize)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            PromotionEvent::Dropped { slot, updates } => Some((*slot, updates.len())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn promotes_slots_with_updates() {
+    let mut tracker = PromotionTracker::new();
+    tracker.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    tracker.record(&tx(10, 1));
+    tracker.record(&account(10, 2, 7));
+    tracker.slot(&slot(11, 10, SlotStatus::SlotProcessed));
+    assert_eq!(tracker.pending(10), 2);
+
+    // Confirming 11 confirms its ancestor 10; 11 had no updates so it stays silent.
+    let delivered = vec![
+        signature(1),
+        UpdateId::Account {
+            pubkey: vec![2; 32],
+            write_version: 7,
+        },
+    ];
+    let confirmed = tracker.slot(&slot(11, 10, SlotStatus::SlotConfirmed));
+    assert_eq!(
+        promotions(&confirmed),
+        vec![(10, CommitmentLevel::Confirmed, delivered.clone())]
+    );
+
+    let finalized = tracker.slot(&slot(10, 9, SlotStatus::SlotFinalized));
+    assert_eq!(
+        promotions(&finalized),
+        vec![(10, CommitmentLevel::Finalized, delivered)]
+    );
+    assert_eq!(tracker.pending(10), 0);
+}
+
+#[test]
+fn finalization_implies_confirmation() {
+    let mut tracker = PromotionTracker::new();
+    tracker.slot(&slot(20, 19, SlotStatus::SlotProcessed));
+    tracker.record(&tx(20, 1));
+
+    let events = tracker.slot(&slot(20, 19, SlotStatus::SlotFinalized));
+    assert_eq!(
+        promotions(&events),
+        vec![
+            (20, CommitmentLevel::Confirmed, vec![signature(1)]),
+            (20, CommitmentLevel::Finalized, vec![signature(1)]),
+        ]
+    );
+}
+
+#[test]
+fn dead_slot_is_dropped() {
+    let mut tracker = PromotionTracker::new();
+    tracker.slot(&slot(30, 29, SlotStatus::SlotProcessed));
+    tracker.record(&tx(30, 1));
+
+    let events = tracker.slot(&slot(30, 29, SlotStatus::SlotDead));
+    assert!(matches!(
+        events.as_slice(),
+        [PromotionEvent::Dropped { slot: 30, updates }] if updates == &[signature(1)]
+    ));
+    // Late data for the dead slot is not counted.
+    tracker.record(&tx(30, 2));
+    assert_eq!(tracker.pending(30), 0);
+}
+
+#[test]
+fn finalizing_another_fork_drops_the_abandoned_slots() {
+    let mut tracker = PromotionTracker::new();
+    tracker.slot(&slot(40, 39, SlotStatus::SlotProcessed));
+    tracker.slot(&slot(41, 40, SlotStatus::SlotProcessed));
+    tracker.record(&tx(41, 1));
+    tracker.slot(&slot(42, 41, SlotStatus::SlotProcessed));
+    tracker.record(&tx(42, 2));
+    tracker.record(&tx(42, 3));
+    // 43 builds on 40, abandoning 41 and 42.
+    tracker.slot(&slot(43, 40, SlotStatus::SlotProcessed));
+    tracker.record(&tx(43, 4));
+
+    // Confirmed on the abandoned fork, then finalized away.
+    let events = tracker.slot(&slot(41, 40, SlotStatus::SlotConfirmed));
+    assert_eq!(
+        promotions(&events),
+        vec![(41, CommitmentLevel::Confirmed, vec![signature(1)])]
+    );
+    let events = tracker.slot(&slot(43, 40, SlotStatus::SlotFinalized));
+    assert_eq!(dropped(&events), vec![(41, 1), (42, 2)]);
+    assert_eq!(
+        promotions(&events),
+        vec![
+            (43, CommitmentLevel::Confirmed, vec![signature(4)]),
+            (43, CommitmentLevel::Finalized, vec![signature(4)]),
+        ]
+    );
+}
+
+#[test]
+fn data_for_settled_slots_is_not_counted() {
+    let mut tracker = PromotionTracker::new();
+    tracker.slot(&slot(50, 49, SlotStatus::SlotFinalized));
+    tracker.record(&tx(50, 1));
+    assert_eq!(tracker.pending(50), 0);
+    // Slot statuses are passed through, not counted.
+    tracker.record(&SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Slot(slot(51, 50, SlotStatus::SlotProcessed))),
+        ..Default::default()
+    });
+    assert_eq!(tracker.pending(51), 0);
+
+    // Past the cap the oldest slot is forgotten without an event.
+    let mut tracker = PromotionTracker::new().with_max_pending_slots(2);
+    for number in [60, 61, 62] {
+        tracker.record(&tx(number, number as u8));
+    }
+    assert_eq!(tracker.pending(60), 0);
+    let events = tracker.slot(&slot(60, 59, SlotStatus::SlotDead));
+    assert!(events.is_empty());
+    assert_eq!(tracker.pending(62), 1);
+}
@@ -0,0 +1,75 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate uses its own subset.
+#![allow(dead_code)]
+
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SlotStatus, SubscribeUpdate, SubscribeUpdateAccount,
+    SubscribeUpdateAccountInfo, SubscribeUpdateSlot,
+};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Scratch directory under the system temp dir, removed on drop. Any directory
+/// left by an earlier run is removed first.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("laserstream-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Slot status update of `slot`, built on `parent`.
+pub fn slot(slot: u64, parent: u64, status: SlotStatus) -> SubscribeUpdateSlot {
+    SubscribeUpdateSlot {
+        slot,
+        parent: Some(parent),
+        status: status as i32,
+        dead_error: None,
+    }
+}
+
+/// [`slot`] as delivered for a `slots` filter.
+pub fn slot_update(number: u64, parent: u64, status: SlotStatus) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec!["slots".to_string()],
+        update_oneof: Some(UpdateOneof::Slot(slot(number, parent, status))),
+        created_at: None,
+    }
+}
+
+/// Update of `info` at `slot`, as delivered for an `accounts` filter.
+pub fn account_update(slot: u64, info: SubscribeUpdateAccountInfo) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: vec!["accounts".to_string()],
+        update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(info),
+            slot,
+            is_startup: false,
+        })),
+        created_at: None,
+    }
+}
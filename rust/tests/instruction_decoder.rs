@@ -0,0 +1,198 @@
+//! InstructionRegistry: built-in decoders, CPI stack heights and custom decoders.
+
+use helius_laserstream::grpc::SubscribeUpdateTransactionInfo;
+use helius_laserstream::instructions::{
+    ComputeBudgetInstruction, InstructionDecoder, InstructionNode, InstructionRegistry,
+    ParsedInstruction, SystemInstruction, TokenInstruction, COMPUTE_BUDGET_PROGRAM_ID,
+    SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use helius_laserstream::solana::storage::confirmed_block::{
+    CompiledInstruction, InnerInstruction, InnerInstructions, Message, MessageHeader,
+    Transaction, TransactionStatusMeta,
+};
+use helius_laserstream::TransactionView;
+
+const CUSTOM_PROGRAM: &str = "Custom1111111111111111111111111111111111111";
+
+fn decode(key: &str) -> Vec<u8> {
+    bs58::decode(key).into_vec().unwrap()
+}
+
+fn inner(program: u32, accounts: Vec<u8>, data: Vec<u8>, height: u32) -> InnerInstruction {
+    InnerInstruction {
+        program_id_index: program,
+        accounts,
+        data,
+        stack_height: Some(height),
+    }
+}
+
+/// Keys: 0 payer, 1 source, 2 destination, 3 custom program, 4 token, 5 system,
+/// 6 compute budget.
+fn transaction() -> SubscribeUpdateTransactionInfo {
+    let mut transfer = vec![3];
+    transfer.extend_from_slice(&1_500u64.to_le_bytes());
+    let mut sol = 2u32.to_le_bytes().to_vec();
+    sol.extend_from_slice(&42u64.to_le_bytes());
+    let mut price = vec![3];
+    price.extend_from_slice(&10_000u64.to_le_bytes());
+
+    SubscribeUpdateTransactionInfo {
+        signature: vec![1; 64],
+        transaction: Some(Transaction {
+            signatures: vec![vec![1; 64]],
+            message: Some(Message {
+                header: Some(MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 4,
+                }),
+                account_keys: vec![
+                    vec![10; 32],
+                    vec![11; 32],
+                    vec![12; 32],
+                    decode(CUSTOM_PROGRAM),
+                    decode(TOKEN_PROGRAM_ID),
+                    decode(SYSTEM_PROGRAM_ID),
+                    decode(COMPUTE_BUDGET_PROGRAM_ID),
+                ],
+                instructions: vec![
+                    CompiledInstruction {
+                        program_id_index: 6,
+                        accounts: vec![],
+                        data: price,
+                    },
+                    CompiledInstruction {
+                        program_id_index: 3,
+                        accounts: vec![0, 1, 2],
+                        data: vec![0xAB],
+                    },
+                ],
+                ..Default::default()
+            }),
+        }),
+        meta: Some(TransactionStatusMeta {
+            inner_instructions: vec![InnerInstructions {
+                index: 1,
+                instructions: vec![
+                    // custom -> token transfer
+                    inner(4, vec![1, 2, 0], transfer, 2),
+                    // custom -> custom (self-CPI) -> system transfer
+                    inner(3, vec![0], vec![0xCD], 2),
+                    inner(5, vec![0, 2], sol, 3),
+                ],
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+struct CustomDecoder;
+
+impl InstructionDecoder for CustomDecoder {
+    fn program_ids(&self) -> Vec<String> {
+        vec![CUSTOM_PROGRAM.to_string()]
+    }
+
+    fn name(&self, _program_id: &[u8]) -> String {
+        "custom".to_string()
+    }
+
+    fn decode(&self, ix: &InstructionNode<'_>) -> Option<ParsedInstruction> {
+        Some(ParsedInstruction::Other(serde_json::json!({ "tag": ix.data.first()? })))
+    }
+}
+
+#[test]
+fn walks_outer_and_inner_with_parents() {
+    let info = transaction();
+    let view = TransactionView::new(1, &info).unwrap();
+    let nodes = InstructionNode::collect(&view);
+
+    let shape: Vec<(usize, Option<usize>, u32, Option<usize>)> = nodes
+        .iter()
+        .map(|n| (n.outer_index, n.inner_index, n.stack_height, n.parent))
+        .collect();
+    assert_eq!(
+        shape,
+        vec![
+            (0, None, 1, None),
+            (1, None, 1, None),
+            (1, Some(0), 2, Some(1)),
+            (1, Some(1), 2, Some(1)),
+            (1, Some(2), 3, Some(3)),
+        ]
+    );
+}
+
+#[test]
+fn builtins_decode_core_programs() {
+    let info = transaction();
+    let view = TransactionView::new(1, &info).unwrap();
+    let decoded = InstructionRegistry::with_builtins().decode_transaction(&view);
+
+    assert_eq!(
+        decoded[0].parsed,
+        Some(ParsedInstruction::ComputeBudget(
+            ComputeBudgetInstruction::SetComputeUnitPrice { micro_lamports: 10_000 }
+        ))
+    );
+    // No decoder registered for the custom program.
+    assert!(decoded[1].program.is_none() && decoded[1].parsed.is_none());
+
+    assert_eq!(decoded[2].program.as_deref(), Some("spl-token"));
+    let Some(ParsedInstruction::Token(transfer)) = &decoded[2].parsed else {
+        panic!("expected a token instruction");
+    };
+    assert_eq!(transfer.transfer_amount(), Some(1_500));
+    assert!(matches!(
+        transfer,
+        TokenInstruction::Transfer { source, .. } if *source == bs58::encode([11; 32]).into_string()
+    ));
+
+    assert!(matches!(
+        decoded[4].parsed,
+        Some(ParsedInstruction::System(SystemInstruction::Transfer { lamports: 42, .. }))
+    ));
+}
+
+#[test]
+fn extension_tags_decode_for_token_2022_only() {
+    let registry = InstructionRegistry::with_builtins();
+    let decode_with = |program: &str| {
+        let program_id = decode(program);
+        registry.decode(&InstructionNode {
+            outer_index: 0,
+            inner_index: None,
+            stack_height: 1,
+            parent: None,
+            program_id: &program_id,
+            accounts: vec![&[7; 32]],
+            data: &[26, 1, 2],
+        })
+    };
+
+    assert_eq!(
+        decode_with(TOKEN_2022_PROGRAM_ID),
+        Some(ParsedInstruction::Token(TokenInstruction::Extension {
+            tag: 26,
+            data: vec![1, 2],
+        }))
+    );
+    assert_eq!(decode_with(TOKEN_PROGRAM_ID), None);
+}
+
+#[test]
+fn custom_decoders_plug_in() {
+    let info = transaction();
+    let view = TransactionView::new(1, &info).unwrap();
+    let registry = InstructionRegistry::with_builtins().with(CustomDecoder);
+    let decoded = registry.decode_transaction(&view);
+
+    assert_eq!(decoded[1].program.as_deref(), Some("custom"));
+    assert_eq!(
+        decoded[3].parsed,
+        Some(ParsedInstruction::Other(serde_json::json!({ "tag": 0xCD })))
+    );
+}
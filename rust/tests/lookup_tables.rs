@@ -0,0 +1,279 @@
+//! LookupTableCache: table parsing, resolution order, stream updates and on-demand
+//! RPC loads for preprocessed transactions, shared, bounded and negatively cached.
+
+use base64::Engine;
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_preprocessed_update, subscribe_update::UpdateOneof, SubscribePreprocessedTransaction,
+    SubscribePreprocessedTransactionInfo, SubscribePreprocessedUpdate, SubscribeUpdate,
+    SubscribeUpdateAccount, SubscribeUpdateAccountInfo,
+};
+use helius_laserstream::instructions::ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+use helius_laserstream::lookup_tables::LoadedAddresses;
+use helius_laserstream::rpc::RpcClient;
+use helius_laserstream::solana::storage::confirmed_block::{
+    Message, MessageAddressTableLookup, Transaction,
+};
+use helius_laserstream::{resolve_lookup_tables, LookupTableCache};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const TABLE_A: [u8; 32] = [0xA; 32];
+const TABLE_B: [u8; 32] = [0xB; 32];
+
+fn table_data(addresses: &[u8]) -> Vec<u8> {
+    let mut data = 1u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    data.extend_from_slice(&7u64.to_le_bytes());
+    data.push(0);
+    data.push(1);
+    data.extend_from_slice(&[9; 32]);
+    data.extend_from_slice(&[0; 2]);
+    for byte in addresses {
+        data.extend_from_slice(&[*byte; 32]);
+    }
+    data
+}
+
+fn table_update(slot: u64, key: [u8; 32], addresses: &[u8]) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: key.to_vec(),
+                lamports: 1,
+                owner: bs58::decode(ADDRESS_LOOKUP_TABLE_PROGRAM_ID)
+                    .into_vec()
+                    .unwrap(),
+                data: table_data(addresses),
+                ..Default::default()
+            }),
+            slot,
+            is_startup: false,
+        })),
+        ..Default::default()
+    }
+}
+
+fn lookup(table: [u8; 32], writable: Vec<u8>, readonly: Vec<u8>) -> MessageAddressTableLookup {
+    MessageAddressTableLookup {
+        account_key: table.to_vec(),
+        writable_indexes: writable,
+        readonly_indexes: readonly,
+    }
+}
+
+fn message() -> Message {
+    Message {
+        account_keys: vec![vec![1; 32]],
+        versioned: true,
+        address_table_lookups: vec![
+            lookup(TABLE_A, vec![2], vec![0]),
+            lookup(TABLE_B, vec![1], vec![]),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn resolves_writable_before_readonly() {
+    let cache = LookupTableCache::new();
+    assert!(cache.apply(&table_update(10, TABLE_A, &[20, 21, 22])));
+    // An older state never replaces a newer one.
+    assert!(!cache.apply(&table_update(9, TABLE_A, &[20])));
+    assert!(cache.apply(&table_update(10, TABLE_B, &[30, 31])));
+
+    let table = cache.get(&TABLE_A).unwrap();
+    assert!(table.is_active());
+    assert_eq!(table.authority, Some(vec![9; 32]));
+
+    let message = message();
+    let loaded = cache.resolve(&message);
+    assert!(loaded.is_complete());
+    assert_eq!(loaded.writable, vec![vec![22; 32], vec![31; 32]]);
+    assert_eq!(loaded.readonly, vec![vec![20; 32]]);
+    assert_eq!(
+        loaded.account_keys(&message),
+        vec![&[1; 32][..], &[22; 32][..], &[31; 32][..], &[20; 32][..]]
+    );
+}
+
+#[test]
+fn reports_missing_and_short_tables() {
+    let cache = LookupTableCache::new();
+    // Table B is known but hasn't been extended to index 1 yet.
+    cache.apply(&table_update(10, TABLE_B, &[30]));
+
+    let loaded = cache.resolve(&message());
+    assert_eq!(
+        loaded.missing_tables,
+        vec![TABLE_A.to_vec(), TABLE_B.to_vec()]
+    );
+    assert!(loaded.writable.is_empty() && loaded.readonly.is_empty());
+
+    // Closing a table removes it.
+    let mut closed = table_update(11, TABLE_B, &[]);
+    if let Some(UpdateOneof::Account(account)) = &mut closed.update_oneof {
+        let info = account.account.as_mut().unwrap();
+        info.lamports = 0;
+        info.data.clear();
+    }
+    assert!(cache.apply(&closed));
+    assert!(cache.is_empty());
+}
+
+/// Answers every `getMultipleAccounts` call with table A holding [40, 41, 42], and
+/// counts the calls.
+fn serve() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let value: Vec<Value> = request["params"][0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| {
+                    if *key != json!(bs58::encode(TABLE_A).into_string()) {
+                        return Value::Null;
+                    }
+                    json!({
+                        "lamports": 1,
+                        "owner": ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                        "data": [base64::engine::general_purpose::STANDARD.encode(table_data(&[40, 41, 42])), "base64"],
+                        "executable": false,
+                        "rentEpoch": 0,
+                    })
+                })
+                .collect();
+            let payload = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": { "context": { "slot": 20 }, "value": value },
+            })
+            .to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                payload.len(),
+                payload
+            )
+            .unwrap();
+        }
+    });
+    (url, calls)
+}
+
+fn preprocessed(message: Message) -> SubscribePreprocessedUpdate {
+    SubscribePreprocessedUpdate {
+        update_oneof: Some(subscribe_preprocessed_update::UpdateOneof::Transaction(
+            SubscribePreprocessedTransaction {
+                transaction: Some(SubscribePreprocessedTransactionInfo {
+                    signature: vec![1; 64],
+                    is_vote: false,
+                    transaction: Some(Transaction {
+                        signatures: vec![vec![1; 64]],
+                        message: Some(message),
+                    }),
+                }),
+                slot: 21,
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn preprocessed_stream_loads_missing_tables() {
+    let (url, _) = serve();
+    let cache = LookupTableCache::new().with_rpc(RpcClient::new(url));
+    cache.apply(&table_update(10, TABLE_B, &[30, 31]));
+
+    let transaction = preprocessed(message());
+    let ping = SubscribePreprocessedUpdate {
+        update_oneof: Some(subscribe_preprocessed_update::UpdateOneof::Ping(
+            Default::default(),
+        )),
+        ..Default::default()
+    };
+
+    let updates = futures::stream::iter(vec![Ok(transaction), Ok(ping)]);
+    let resolved: Vec<_> = resolve_lookup_tables(updates, cache.clone())
+        .collect()
+        .await;
+
+    let first = resolved[0].as_ref().unwrap();
+    assert_eq!(
+        first.loaded,
+        Some(LoadedAddresses {
+            writable: vec![vec![42; 32], vec![31; 32]],
+            readonly: vec![vec![40; 32]],
+            missing_tables: vec![],
+        })
+    );
+    assert_eq!(resolved[1].as_ref().unwrap().loaded, None);
+    assert_eq!(cache.get(&TABLE_A).unwrap().slot, 20);
+}
+
+#[tokio::test]
+async fn tables_the_rpc_lacks_are_requested_once() {
+    let (url, calls) = serve();
+    let cache = LookupTableCache::new().with_rpc(RpcClient::new(url));
+    let unknown = Message {
+        address_table_lookups: vec![lookup([0xC; 32], vec![0], vec![])],
+        ..message()
+    };
+
+    let updates = futures::stream::iter((0..3).map(|_| Ok(preprocessed(unknown.clone()))));
+    let resolved: Vec<_> = resolve_lookup_tables(updates, cache.clone())
+        .collect()
+        .await;
+    for item in resolved {
+        let loaded = item.unwrap().loaded.unwrap();
+        assert_eq!(loaded.missing_tables, vec![[0xC; 32].to_vec()]);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn a_stalled_rpc_delays_the_stream_by_the_load_timeout_only() {
+    // Accepts connections but never answers.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let cache = LookupTableCache::new()
+        .with_rpc(RpcClient::new(url))
+        .with_load_timeout(Duration::from_millis(100));
+
+    let began = Instant::now();
+    let updates = futures::stream::iter((0..2).map(|_| Ok(preprocessed(message()))));
+    let resolved: Vec<_> = resolve_lookup_tables(updates, cache).collect().await;
+    assert!(began.elapsed() < Duration::from_secs(1));
+    for item in resolved {
+        assert!(!item.unwrap().loaded.unwrap().is_complete());
+    }
+    drop(listener);
+}
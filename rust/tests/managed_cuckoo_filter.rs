@@ -0,0 +1,190 @@
+//! ManagedAccountFilter: growth on TableFullError, request assembly, local
+//! re-checking of server-side matches, and the bound writer's debounced writes
+//! against the mock server.
+
+#![cfg(feature = "cuckoo")]
+
+use helius_laserstream::cuckoo::{ManagedAccountFilter, ManagedFilterOptions, Pubkey};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterSlots, SubscribeUpdate,
+    SubscribeUpdateAccount, SubscribeUpdateAccountInfo,
+};
+use helius_laserstream::subscribe;
+use helius_laserstream::testing::MockGeyser;
+use std::time::Duration;
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+fn key(i: u32) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes[..4].copy_from_slice(&i.to_le_bytes());
+    bytes[31] = 0xAA;
+    Pubkey::new_from_array(bytes)
+}
+
+fn account_update(pk: Pubkey, filters: &[&str]) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: filters.iter().map(|f| f.to_string()).collect(),
+        update_oneof: Some(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: pk.to_bytes().to_vec(),
+                ..Default::default()
+            }),
+            slot: 1,
+            is_startup: false,
+        })),
+        created_at: None,
+    }
+}
+
+#[test]
+fn grows_when_table_is_full() {
+    let filter = ManagedAccountFilter::new(
+        SubscribeRequest::default(),
+        ManagedFilterOptions::default().with_initial_capacity(16),
+    )
+    .unwrap();
+
+    let added = filter.insert_many((0..1_000).map(key)).unwrap();
+    assert_eq!(added, 1_000);
+    assert_eq!(filter.len(), 1_000);
+    assert!(filter.capacity() > 16);
+    assert!((0..1_000).all(|i| filter.contains(key(i))));
+    assert!(!filter.contains(key(5_000)));
+}
+
+#[test]
+fn request_keeps_base_filters() {
+    let mut base = SubscribeRequest::default();
+    base.slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    let filter = ManagedAccountFilter::new(
+        base,
+        ManagedFilterOptions::default().with_filter_name("wallets"),
+    )
+    .unwrap();
+    filter.insert(key(1)).unwrap();
+
+    let request = filter.request();
+    assert!(request.slots.contains_key("slots"));
+    assert!(request.accounts["wallets"].cuckoo_accounts_filter.is_some());
+}
+
+#[test]
+fn retain_drops_untracked_matches() {
+    let filter = ManagedAccountFilter::new(
+        SubscribeRequest::default(),
+        ManagedFilterOptions::default().with_filter_name("wallets"),
+    )
+    .unwrap();
+    filter.insert(key(1)).unwrap();
+    filter.insert(key(2)).unwrap();
+    assert!(filter.remove(key(2)));
+
+    // Tracked: kept as-is.
+    let kept = filter.retain(account_update(key(1), &["wallets"])).unwrap();
+    assert_eq!(kept.filters, vec!["wallets"]);
+
+    // False positive (or removed but not yet written): dropped.
+    assert!(filter
+        .retain(account_update(key(2), &["wallets"]))
+        .is_none());
+
+    // Also matched by another filter: kept without the managed filter name.
+    let other = filter
+        .retain(account_update(key(3), &["wallets", "owners"]))
+        .unwrap();
+    assert_eq!(other.filters, vec!["owners"]);
+
+    // Not matched by the managed filter at all: untouched.
+    assert!(filter.retain(account_update(key(3), &["owners"])).is_some());
+}
+
+/// Subscribes to the mock with the filter's request and binds the handle. The
+/// stream is polled in the background for the rest of the test.
+async fn bind(mock: &MockGeyser, filter: &ManagedAccountFilter) -> tokio::task::JoinHandle<()> {
+    let (stream, handle) = subscribe(mock.config(), filter.request());
+    filter.bind(handle);
+    tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        while futures::StreamExt::next(&mut stream).await.is_some() {}
+    })
+}
+
+#[tokio::test]
+async fn coalesces_changes_into_one_debounced_write() {
+    let mock = MockGeyser::start().await.unwrap();
+    let filter = ManagedAccountFilter::new(
+        SubscribeRequest::default(),
+        ManagedFilterOptions::default()
+            .with_initial_capacity(1_024)
+            .with_debounce(DEBOUNCE),
+    )
+    .unwrap();
+    let _stream = bind(&mock, &filter).await;
+    mock.wait_for_requests(1).await;
+
+    for i in 0..3 {
+        filter.insert(key(i)).unwrap();
+        tokio::time::sleep(DEBOUNCE / 5).await;
+    }
+    mock.wait_for_requests(2).await;
+    // Nothing changed since, so nothing more is written.
+    tokio::time::sleep(DEBOUNCE * 3).await;
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].accounts, filter.request().accounts);
+
+    filter.remove(key(0));
+    mock.wait_for_requests(3).await;
+    assert_eq!(mock.requests()[2].accounts, filter.request().accounts);
+}
+
+#[tokio::test]
+async fn keeps_writing_after_a_failed_write() {
+    let mock = MockGeyser::start().await.unwrap();
+    let filter = ManagedAccountFilter::new(
+        SubscribeRequest::default(),
+        ManagedFilterOptions::default()
+            .with_initial_capacity(1_024)
+            .with_debounce(DEBOUNCE),
+    )
+    .unwrap();
+    // A handle whose stream is gone: every write fails.
+    let (stream, handle) = subscribe(mock.config(), filter.request());
+    drop(stream);
+    filter.bind(handle);
+    filter.insert(key(1)).unwrap();
+    tokio::time::sleep(DEBOUNCE * 3).await;
+    assert!(filter.flush().await.is_err());
+
+    // Rebinding sends the changes the failed writes carried, then later ones.
+    let _stream = bind(&mock, &filter).await;
+    mock.wait_for_requests(2).await;
+    assert_eq!(mock.requests()[1].accounts, filter.request().accounts);
+    filter.insert(key(2)).unwrap();
+    mock.wait_for_requests(3).await;
+    assert_eq!(mock.requests()[2].accounts, filter.request().accounts);
+    assert!(filter.flush().await.is_ok());
+}
+
+#[tokio::test]
+async fn writes_what_a_failed_insert_many_added() {
+    let mock = MockGeyser::start().await.unwrap();
+    // Growing past the initial capacity overflows, so the batch fails partway.
+    let filter = ManagedAccountFilter::new(
+        SubscribeRequest::default(),
+        ManagedFilterOptions::default()
+            .with_initial_capacity(16)
+            .with_growth_factor(usize::MAX)
+            .with_debounce(DEBOUNCE),
+    )
+    .unwrap();
+    let _stream = bind(&mock, &filter).await;
+    mock.wait_for_requests(1).await;
+
+    assert!(filter.insert_many((0..1_000).map(key)).is_err());
+    assert!(!filter.is_empty());
+    mock.wait_for_requests(2).await;
+    assert_eq!(mock.requests()[1].accounts, filter.request().accounts);
+}
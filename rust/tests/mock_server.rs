@@ -0,0 +1,194 @@
+//! MockGeyser: filtering, write persistence, reconnect replay, injected errors and
+//! unary calls against the real client.
+
+mod common;
+
+use common::{account_update, slot_update};
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterSlots, SubscribeUpdate,
+    SubscribeUpdateAccountInfo, SubscribeUpdateBlockMeta,
+};
+use helius_laserstream::slots::update_slot;
+use helius_laserstream::solana::storage::confirmed_block::BlockHeight;
+use helius_laserstream::testing::{MockEvent, MockGeyser};
+use helius_laserstream::{subscribe, BlockhashCache, LaserstreamError};
+use laserstream_core_proto::tonic::Status;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+fn account(slot: u64) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: vec![1; 32],
+            owner: vec![2; 32],
+            ..Default::default()
+        },
+    )
+}
+
+fn slots_request() -> SubscribeRequest {
+    let mut request = SubscribeRequest {
+        commitment: Some(CommitmentLevel::Confirmed as i32),
+        ..Default::default()
+    };
+    request
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    request
+}
+
+fn with_accounts(mut request: SubscribeRequest) -> SubscribeRequest {
+    request.accounts.insert(
+        "accounts".to_string(),
+        SubscribeRequestFilterAccounts {
+            owner: vec![bs58::encode([2; 32]).into_string()],
+            ..Default::default()
+        },
+    );
+    request
+}
+
+/// Polls the client stream in the background; streams are lazy and would not
+/// connect otherwise.
+fn drive(
+    stream: impl futures::Stream<Item = Result<SubscribeUpdate, LaserstreamError>> + Send + 'static,
+) -> mpsc::UnboundedReceiver<Result<SubscribeUpdate, LaserstreamError>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            if tx.send(item).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+async fn next(
+    updates: &mut mpsc::UnboundedReceiver<Result<SubscribeUpdate, LaserstreamError>>,
+) -> Result<SubscribeUpdate, LaserstreamError> {
+    tokio::time::timeout(Duration::from_secs(10), updates.recv())
+        .await
+        .expect("no update within 10s")
+        .expect("stream ended")
+}
+
+#[tokio::test]
+async fn filters_updates_and_applies_writes() {
+    let mock = MockGeyser::start().await.unwrap();
+    let (stream, handle) = subscribe(mock.config(), slots_request());
+    let mut updates = drive(stream);
+    mock.wait_for_requests(1).await;
+
+    // Not subscribed to accounts yet.
+    mock.push(account(10));
+    mock.push(slot_update(10, 9, SlotStatus::SlotConfirmed));
+    assert_eq!(
+        next(&mut updates).await.unwrap().filters,
+        vec!["slots".to_string()]
+    );
+
+    handle.write(with_accounts(slots_request())).await.unwrap();
+    mock.wait_for_requests(2).await;
+    let written = &mock.requests()[1];
+    assert!(written.accounts.contains_key("accounts"));
+    // The client keeps its internal slot filter on writes.
+    assert_eq!(written.slots.len(), 2);
+    mock.push(account(11));
+    assert_eq!(
+        next(&mut updates).await.unwrap().filters,
+        vec!["accounts".to_string()]
+    );
+    assert_eq!(mock.connections(), 1);
+}
+
+#[tokio::test]
+async fn reconnects_from_the_last_slot_with_written_filters() {
+    let mock = MockGeyser::start().await.unwrap();
+    let (stream, handle) = subscribe(mock.config(), slots_request());
+    let mut updates = drive(stream);
+    mock.wait_for_requests(1).await;
+    handle.write(with_accounts(slots_request())).await.unwrap();
+    mock.wait_for_requests(2).await;
+
+    mock.play([
+        MockEvent::Update(slot_update(11, 10, SlotStatus::SlotConfirmed)),
+        MockEvent::Update(account(11)),
+        MockEvent::Update(slot_update(12, 11, SlotStatus::SlotConfirmed)),
+    ])
+    .await;
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        received.push(update_slot(&next(&mut updates).await.unwrap()).unwrap());
+    }
+    assert_eq!(received, vec![11, 11, 12]);
+
+    mock.fail(Status::internal("injected"));
+    mock.wait_for_requests(3).await;
+    let resumed = &mock.requests()[2];
+    // CONFIRMED resumes at the exact slot, with the write still applied.
+    assert_eq!(resumed.from_slot, Some(12));
+    assert!(resumed.accounts.contains_key("accounts"));
+    assert_eq!(mock.connections(), 2);
+    // Slot 12 is replayed from the history.
+    let replayed = next(&mut updates).await.unwrap();
+    assert!(matches!(replayed.update_oneof, Some(UpdateOneof::Slot(s)) if s.slot == 12));
+}
+
+#[tokio::test]
+async fn rejects_connections_and_serves_unary_calls() {
+    let mock = MockGeyser::start().await.unwrap();
+    mock.reject_connections([Status::unavailable("injected")]);
+    let config = mock.config().with_max_reconnect_attempts(1);
+    let (stream, _handle) = subscribe(config, slots_request());
+    let mut updates = drive(stream);
+    assert!(matches!(
+        next(&mut updates).await,
+        Err(LaserstreamError::MaxReconnectAttempts(_))
+    ));
+
+    mock.push(SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+            slot: 20,
+            blockhash: "hash-20".to_string(),
+            block_height: Some(BlockHeight { block_height: 18 }),
+            ..Default::default()
+        })),
+        ..Default::default()
+    });
+    let cache = BlockhashCache::start(mock.config()).await.unwrap();
+    let latest = cache.latest(CommitmentLevel::Finalized).unwrap();
+    assert_eq!((latest.blockhash.as_str(), latest.slot), ("hash-20", 20));
+    assert_eq!(latest.last_valid_block_height, 168);
+}
+
+#[tokio::test]
+async fn dropped_blockhash_cache_closes_its_subscription() {
+    let mock = MockGeyser::start().await.unwrap();
+    mock.push(SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+            slot: 20,
+            blockhash: "hash-20".to_string(),
+            block_height: Some(BlockHeight { block_height: 18 }),
+            ..Default::default()
+        })),
+        ..Default::default()
+    });
+    let cache = BlockhashCache::start(mock.config()).await.unwrap();
+    mock.wait_for_requests(1).await;
+    assert_eq!(mock.open_subscriptions(), 1);
+
+    // Nothing is pushed after the drop, so only the shutdown signal ends the task.
+    drop(cache);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while mock.open_subscriptions() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("subscription still open");
+}
@@ -0,0 +1,140 @@
+//! PriorityFeeEstimator: compute-unit prices from Compute Budget instructions and V1
+//! configs, rolling windows and per-account percentiles.
+
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateTransaction,
+    SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::solana::storage::confirmed_block::{
+    CompiledInstruction, Message, MessageHeader, Transaction, TransactionConfig,
+    TransactionStatusMeta,
+};
+use helius_laserstream::{PriorityFeeEstimator, TransactionView};
+
+const PAYER: [u8; 32] = [1; 32];
+const POOL: [u8; 32] = [2; 32];
+const OTHER: [u8; 32] = [3; 32];
+
+fn compute_budget() -> Vec<u8> {
+    bs58::decode("ComputeBudget111111111111111111111111111111")
+        .into_vec()
+        .unwrap()
+}
+
+fn set_price(micro_lamports: u64) -> CompiledInstruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    CompiledInstruction {
+        program_id_index: 2,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Transaction writing `writable`, with the Compute Budget program at index 2.
+fn tx(
+    writable: [u8; 32],
+    instructions: Vec<CompiledInstruction>,
+    config: Option<TransactionConfig>,
+) -> SubscribeUpdateTransactionInfo {
+    SubscribeUpdateTransactionInfo {
+        signature: vec![9; 64],
+        transaction: Some(Transaction {
+            signatures: vec![vec![9; 64]],
+            message: Some(Message {
+                header: Some(MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                }),
+                account_keys: vec![PAYER.to_vec(), writable.to_vec(), compute_budget()],
+                instructions,
+                versioned: config.is_some(),
+                config,
+                ..Default::default()
+            }),
+        }),
+        meta: Some(TransactionStatusMeta::default()),
+        ..Default::default()
+    }
+}
+
+fn update(slot: u64, info: SubscribeUpdateTransactionInfo) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(info),
+            slot,
+        })),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reads_prices_from_instructions_and_v1_config() {
+    let info = tx(POOL, vec![set_price(5_000)], None);
+    let view = TransactionView::new(1, &info).unwrap();
+    assert_eq!(view.compute_unit_price(), 5_000);
+    assert_eq!(view.compute_unit_limit(), 0);
+
+    let info = tx(POOL, vec![], None);
+    assert_eq!(TransactionView::new(1, &info).unwrap().compute_unit_price(), 0);
+
+    // 2_000 lamports over 400_000 units is 5_000 micro-lamports per unit.
+    let config = TransactionConfig {
+        priority_fee: Some(2_000),
+        compute_unit_limit: Some(400_000),
+        ..Default::default()
+    };
+    let info = tx(POOL, vec![], Some(config));
+    let view = TransactionView::new(1, &info).unwrap();
+    assert_eq!(view.compute_unit_limit(), 400_000);
+    assert_eq!(view.compute_unit_price(), 5_000);
+
+    // The config of the V1 regression vector in v1_config_vectors.rs.
+    let config = TransactionConfig {
+        priority_fee: Some(123_456_789_012),
+        compute_unit_limit: Some(1_400_000),
+        heap_size: Some(262_144),
+        ..Default::default()
+    };
+    let info = tx(POOL, vec![], Some(config));
+    let view = TransactionView::new(1, &info).unwrap();
+    assert_eq!(view.compute_unit_limit(), 1_400_000);
+    assert_eq!(view.compute_unit_price(), 88_183_420_722);
+}
+
+#[test]
+fn estimates_percentiles_per_account() {
+    let mut estimator = PriorityFeeEstimator::new();
+    for (slot, price) in [(10, 100), (10, 200), (11, 300), (11, 400)] {
+        estimator.apply(&update(slot, tx(POOL, vec![set_price(price)], None)));
+    }
+    estimator.apply(&update(11, tx(OTHER, vec![set_price(10_000)], None)));
+
+    assert_eq!(estimator.estimate_for_account(&POOL, 75, 150), Some(300));
+    assert_eq!(estimator.estimate_for_account(&POOL, 100, 1), Some(400));
+    assert_eq!(estimator.estimate_for_account(&OTHER, 50, 150), Some(10_000));
+    assert_eq!(estimator.estimate(100, 150), Some(10_000));
+    assert_eq!(estimator.slot_estimate(10, 50), Some(100));
+
+    let levels = estimator.levels(Some(&PAYER), 150).unwrap();
+    assert_eq!((levels.min, levels.max, levels.samples), (100, 10_000, 5));
+    assert_eq!(levels.medium, 300);
+    assert!(estimator.estimate_for_account(&[4; 32], 50, 150).is_none());
+}
+
+#[test]
+fn forgets_slots_outside_the_window() {
+    let mut estimator = PriorityFeeEstimator::new().with_max_slots(3);
+    estimator.apply(&update(10, tx(POOL, vec![set_price(1_000)], None)));
+    estimator.apply(&update(12, tx(POOL, vec![set_price(10)], None)));
+    assert_eq!(estimator.estimate(100, 3), Some(1_000));
+
+    estimator.apply(&update(13, tx(POOL, vec![set_price(20)], None)));
+    assert_eq!(estimator.estimate(100, 150), Some(20));
+    assert!(estimator.slot_estimate(10, 50).is_none());
+
+    // Too old for the window.
+    estimator.apply(&update(9, tx(POOL, vec![set_price(5_000)], None)));
+    assert_eq!(estimator.estimate(100, 150), Some(20));
+}
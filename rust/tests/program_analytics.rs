@@ -0,0 +1,117 @@
+//! ProgramAnalytics: exclusive compute units per program, fees, failure origins and
+//! leaderboards over slot windows.
+
+use helius_laserstream::analytics::{Metric, ProgramAnalytics};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateTransaction,
+    SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::solana::storage::confirmed_block::{
+    Message, Transaction, TransactionError, TransactionStatusMeta,
+};
+
+const DEX: &str = "Dex1111111111111111111111111111111111111111";
+const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+fn update(slot: u64, fee: u64, consumed: u64, failed: bool, logs: &[String]) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![1; 64],
+                transaction: Some(Transaction {
+                    signatures: vec![vec![1; 64]],
+                    message: Some(Message::default()),
+                }),
+                meta: Some(TransactionStatusMeta {
+                    fee,
+                    compute_units_consumed: Some(consumed),
+                    err: failed.then(|| TransactionError { err: vec![8] }),
+                    log_messages: logs.to_vec(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            slot,
+        })),
+        ..Default::default()
+    }
+}
+
+/// DEX spends `dex_total` units including a Token CPI of `token_units`.
+fn swap(dex_total: u64, token_units: u64, token_error: Option<&str>) -> Vec<String> {
+    let mut logs = vec![
+        format!("Program {DEX} invoke [1]"),
+        format!("Program {TOKEN} invoke [2]"),
+        format!("Program {TOKEN} consumed {token_units} of 190000 compute units"),
+    ];
+    match token_error {
+        Some(error) => {
+            logs.push(format!("Program {TOKEN} failed: {error}"));
+            logs.push(format!("Program {DEX} consumed {dex_total} of 200000 compute units"));
+            logs.push(format!("Program {DEX} failed: {error}"));
+        }
+        None => {
+            logs.push(format!("Program {TOKEN} success"));
+            logs.push(format!("Program {DEX} consumed {dex_total} of 200000 compute units"));
+            logs.push(format!("Program {DEX} success"));
+        }
+    }
+    logs
+}
+
+#[test]
+fn credits_each_program_with_its_own_compute_units() {
+    let mut analytics = ProgramAnalytics::new();
+    analytics.apply(&update(10, 5_000, 30_000, false, &swap(30_000, 4_000, None)));
+    analytics.apply(&update(10, 7_000, 25_000, false, &swap(25_000, 5_000, None)));
+
+    let dex = analytics.program(DEX, 150);
+    assert_eq!(dex.compute_units, 46_000);
+    assert_eq!((dex.transactions, dex.invocations, dex.fees), (2, 2, 12_000));
+    assert_eq!(dex.compute_units_per_invocation(), Some(23_000.0));
+
+    let token = analytics.program(TOKEN, 150);
+    assert_eq!(token.compute_units, 9_000);
+
+    let network = analytics.network(150);
+    assert_eq!((network.transactions, network.compute_units), (2, 55_000));
+}
+
+#[test]
+fn attributes_failures_to_their_origin() {
+    let mut analytics = ProgramAnalytics::new();
+    analytics.apply(&update(
+        10,
+        5_000,
+        10_000,
+        true,
+        &swap(10_000, 3_000, Some("insufficient funds")),
+    ));
+    analytics.apply(&update(10, 5_000, 20_000, false, &swap(20_000, 3_000, None)));
+
+    let dex = analytics.program(DEX, 150);
+    let token = analytics.program(TOKEN, 150);
+    assert_eq!((dex.failed_transactions, dex.failures_originated), (1, 0));
+    assert_eq!((token.failed_transactions, token.failures_originated), (1, 1));
+    assert_eq!(dex.failure_rate(), 0.5);
+}
+
+#[test]
+fn ranks_programs_over_slot_windows() {
+    let mut analytics = ProgramAnalytics::new().with_max_slots(10);
+    analytics.apply(&update(1, 5_000, 100_000, false, &swap(100_000, 99_000, None)));
+    analytics.apply(&update(20, 5_000, 30_000, false, &swap(30_000, 4_000, None)));
+
+    // Slot 1 left the window when slot 20 arrived.
+    let board = analytics.leaderboard(Metric::ComputeUnits, 150, 5);
+    let ranked: Vec<(&str, u64)> = board
+        .iter()
+        .map(|(id, stats)| (id.as_str(), stats.compute_units))
+        .collect();
+    assert_eq!(ranked, vec![(DEX, 26_000), (TOKEN, 4_000)]);
+
+    analytics.apply(&update(21, 9_000, 50_000, false, &swap(50_000, 45_000, None)));
+    let board = analytics.leaderboard(Metric::ComputeUnits, 1, 1);
+    assert_eq!(board[0].0, TOKEN);
+    assert_eq!(analytics.leaderboard(Metric::Fees, 2, 5)[0].1.fees, 14_000);
+}
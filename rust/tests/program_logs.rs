@@ -0,0 +1,109 @@
+//! ProgramLogs: invocation tree, compute units, failures and truncated logs.
+
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateTransaction,
+    SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::logs::{InvocationStatus, LogEntry, ProgramLogs, TransactionLogsExt};
+use helius_laserstream::solana::storage::confirmed_block::TransactionStatusMeta;
+
+const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const APP: &str = "App1111111111111111111111111111111111111111";
+const BUDGET: &str = "ComputeBudget111111111111111111111111111111";
+
+fn lines(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.replace("{APP}", APP).replace("{TOKEN}", TOKEN))
+        .collect()
+}
+
+#[test]
+fn rebuilds_invocation_tree() {
+    let logs = ProgramLogs::parse(&[
+        format!("Program {BUDGET} invoke [1]"),
+        format!("Program {BUDGET} success"),
+        format!("Program {APP} invoke [1]"),
+        "Program log: Instruction: Swap".to_string(),
+        format!("Program {TOKEN} invoke [2]"),
+        "Program log: Instruction: Transfer".to_string(),
+        format!("Program {TOKEN} consumed 4645 of 180000 compute units"),
+        format!("Program {TOKEN} success"),
+        "Program data: AQID BAU=".to_string(),
+        format!("Program return: {APP} KgAAAAAAAAA="),
+        format!("Program {APP} consumed 20000 of 199850 compute units"),
+        format!("Program {APP} success"),
+    ]);
+
+    assert!(!logs.is_truncated());
+    let roots: Vec<&str> = logs.roots().map(|i| i.program_id.as_str()).collect();
+    assert_eq!(roots, vec![BUDGET, APP]);
+
+    let app = &logs.invocations()[1];
+    assert_eq!(app.status, InvocationStatus::Success);
+    assert_eq!(app.compute_units_consumed, Some(20_000));
+    assert_eq!(app.compute_units_limit, Some(199_850));
+    assert_eq!(app.logs().collect::<Vec<_>>(), vec!["Instruction: Swap"]);
+    assert_eq!(app.data().next(), Some(&[vec![1, 2, 3], vec![4, 5]][..]));
+    assert_eq!(app.return_data, Some(42u64.to_le_bytes().to_vec()));
+    assert_eq!(app.entries[1], LogEntry::Invoke(2));
+
+    let token = &logs.invocations()[2];
+    assert_eq!((token.depth, token.parent), (2, Some(1)));
+    assert_eq!(token.compute_units_consumed, Some(4_645));
+}
+
+#[test]
+fn finds_where_a_failure_started() {
+    let logs = ProgramLogs::parse(&lines(&[
+        "Program {APP} invoke [1]",
+        "Program {TOKEN} invoke [2]",
+        "Program log: Error: insufficient funds",
+        "Program {TOKEN} consumed 3000 of 190000 compute units",
+        "Program {TOKEN} failed: custom program error: 0x1",
+        "Program {APP} consumed 12000 of 200000 compute units",
+        "Program {APP} failed: custom program error: 0x1",
+    ]));
+
+    assert_eq!(logs.failed().count(), 2);
+    let origin = logs.failure_origin().unwrap();
+    assert_eq!(origin.program_id, TOKEN);
+    assert_eq!(origin.error(), Some("custom program error: 0x1"));
+    assert_eq!(origin.logs().next(), Some("Error: insufficient funds"));
+}
+
+#[test]
+fn truncated_logs_and_update_extension() {
+    let meta = TransactionStatusMeta {
+        log_messages: lines(&[
+            "Program {APP} invoke [1]",
+            "Program {TOKEN} invoke [2]",
+            "Program {TOKEN} success",
+            "Program log: still going",
+            "Log truncated",
+        ]),
+        ..Default::default()
+    };
+    let update = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                meta: Some(meta),
+                ..Default::default()
+            }),
+            slot: 1,
+        })),
+        ..Default::default()
+    };
+
+    let logs = update.program_logs().unwrap();
+    assert!(logs.is_truncated());
+    assert_eq!(logs.invocations()[0].status, InvocationStatus::Unknown);
+    assert_eq!(logs.invocations()[1].status, InvocationStatus::Success);
+    assert_eq!(logs.failure_origin(), None);
+
+    let ping = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Ping(Default::default())),
+        ..Default::default()
+    };
+    assert_eq!(ping.program_logs(), None);
+}
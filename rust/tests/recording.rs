@@ -0,0 +1,200 @@
+//! Recorder and Recording: segmented length-delimited records, zstd, slot index,
+//! recorded requests, and record_stream's written requests and write failures.
+
+mod common;
+
+use common::{slot_update, TempDir};
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SlotStatus, SubscribeRequest, SubscribeRequestFilterSlots,
+    SubscribeUpdate, SubscribeUpdatePing,
+};
+use helius_laserstream::recording::{
+    record_stream, replay_from_files, Recorder, RecorderOptions, Recording, ReplayOptions,
+};
+use helius_laserstream::{LaserstreamError, StreamHandle};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn request() -> SubscribeRequest {
+    let mut request = SubscribeRequest::default();
+    request
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    request
+}
+
+#[test]
+fn round_trips_across_segments() {
+    let dir = TempDir::new("segments");
+    let options = RecorderOptions::default().with_segment_bytes(40);
+    let mut recorder = Recorder::create(&dir, &request(), options).unwrap();
+    for (i, n) in (100..105).enumerate() {
+        let at = UNIX_EPOCH + Duration::from_millis(1_000 + i as u64);
+        recorder
+            .record_at(&slot_update(n, n - 1, SlotStatus::SlotProcessed), at)
+            .unwrap();
+    }
+    recorder
+        .record_at(
+            &SubscribeUpdate {
+                update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+                ..Default::default()
+            },
+            UNIX_EPOCH,
+        )
+        .unwrap();
+    let manifest = recorder.finish().unwrap();
+    assert!(manifest.segments.len() > 1);
+    assert_eq!(manifest.records(), 6);
+    assert_eq!(manifest.segments[0].min_slot, Some(100));
+
+    let recording = Recording::open(&dir).unwrap();
+    assert_eq!(recording.manifest(), &manifest);
+    assert_eq!(recording.manifest().requests[0].decode().unwrap(), request());
+    let records: Vec<_> = recording.records().collect::<Result<_, _>>().unwrap();
+    assert_eq!(records.len(), 6);
+    assert_eq!(
+        records[2].update,
+        slot_update(102, 101, SlotStatus::SlotProcessed)
+    );
+    assert_eq!(
+        records[2].received_at,
+        UNIX_EPOCH + Duration::from_millis(1_002)
+    );
+}
+
+#[test]
+fn rejects_a_record_longer_than_its_segment() {
+    let dir = TempDir::new("corrupt");
+    let mut recorder = Recorder::create(&dir, &request(), RecorderOptions::default()).unwrap();
+    recorder
+        .record(&slot_update(100, 99, SlotStatus::SlotProcessed))
+        .unwrap();
+    recorder
+        .record(&slot_update(101, 100, SlotStatus::SlotProcessed))
+        .unwrap();
+    let manifest = recorder.finish().unwrap();
+
+    // Overwrite the first record's length with a varint near u64::MAX.
+    let path = dir.join(&manifest.segments[0].file);
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.splice(8..9, [0xff; 9].into_iter().chain([0x01]));
+    std::fs::write(&path, bytes).unwrap();
+
+    let records: Vec<_> = Recording::open(&dir).unwrap().records().collect();
+    assert_eq!(records.len(), 1);
+    assert!(matches!(
+        &records[0],
+        Err(LaserstreamError::InvalidRecording(message)) if message.contains("left in its segment")
+    ));
+}
+
+#[test]
+fn compresses_and_indexes_by_slot() {
+    let dir = TempDir::new("zstd");
+    let options = RecorderOptions::default()
+        .with_zstd(3)
+        .with_segment_bytes(100);
+    let mut recorder = Recorder::create(&dir, &request(), options).unwrap();
+    for n in 1..=20 {
+        recorder
+            .record(&slot_update(n, n - 1, SlotStatus::SlotProcessed))
+            .unwrap();
+        if n == 10 {
+            recorder.set_request(&SubscribeRequest::default());
+        }
+    }
+    let manifest = recorder.finish().unwrap();
+    assert_eq!(manifest.compression.as_deref(), Some("zstd"));
+    assert!(manifest.segments[0].file.ends_with(".zst"));
+    assert_eq!(manifest.requests[1].first_record, 10);
+
+    let recording = Recording::open(&dir).unwrap();
+    let all: Vec<_> = recording.records().collect::<Result<_, _>>().unwrap();
+    assert_eq!(all.len(), 20);
+    let late: Vec<u64> = recording
+        .records_for_slots(18, 30)
+        .map(|record| match record.unwrap().update.update_oneof {
+            Some(UpdateOneof::Slot(s)) => s.slot,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert!(late.contains(&18) && late.contains(&20) && !late.contains(&1));
+
+    // A directory can hold only one recording.
+    assert!(matches!(
+        Recorder::create(&dir, &request(), RecorderOptions::default()),
+        Err(LaserstreamError::InvalidRecording(_))
+    ));
+}
+
+/// A finite `subscribe`-like stream of `slots` and its handle, replayed from a
+/// recording made in `dir`.
+fn source(
+    dir: PathBuf,
+    slots: impl IntoIterator<Item = u64>,
+) -> (
+    impl futures::Stream<Item = Result<SubscribeUpdate, LaserstreamError>>,
+    StreamHandle,
+) {
+    let mut recorder = Recorder::create(&dir, &request(), RecorderOptions::default()).unwrap();
+    for n in slots {
+        recorder
+            .record(&slot_update(n, n - 1, SlotStatus::SlotProcessed))
+            .unwrap();
+    }
+    recorder.finish().unwrap();
+    replay_from_files(dir, ReplayOptions::default())
+}
+
+#[tokio::test]
+async fn records_a_stream_while_passing_it_through() {
+    let dir = TempDir::new("stream");
+    let (updates, handle) = source(dir.join("source"), 1..=3);
+    let recorder =
+        Recorder::create(dir.join("copy"), &request(), RecorderOptions::default()).unwrap();
+    let (updates, handle) = record_stream(updates, handle, recorder);
+
+    // Forwarded to the source and recorded in the manifest.
+    let mut written = request();
+    written
+        .slots
+        .insert("more".to_string(), SubscribeRequestFilterSlots::default());
+    handle.write(written.clone()).await.unwrap();
+    let passed: Vec<_> = updates.collect().await;
+    assert_eq!(passed.len(), 3);
+    assert!(passed.iter().all(Result::is_ok));
+
+    let recording = Recording::open(dir.join("copy")).unwrap();
+    assert_eq!(recording.manifest().records(), 3);
+    let requests = &recording.manifest().requests;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].decode().unwrap(), written);
+}
+
+#[tokio::test]
+async fn keeps_passing_updates_when_a_write_fails() {
+    let dir = TempDir::new("stream-failing");
+    let (updates, handle) = source(dir.join("source"), 1..=3);
+    let recorder =
+        Recorder::create(dir.join("copy"), &request(), RecorderOptions::default()).unwrap();
+    // Segments can no longer be created.
+    std::fs::remove_dir_all(dir.join("copy")).unwrap();
+    let (updates, _handle) = record_stream(updates, handle, recorder);
+
+    let passed: Vec<_> = updates.collect().await;
+    let updates: Vec<_> = passed
+        .iter()
+        .filter_map(|item| item.as_ref().ok())
+        .collect();
+    assert_eq!(
+        updates,
+        vec![
+            &slot_update(1, 0, SlotStatus::SlotProcessed),
+            &slot_update(2, 1, SlotStatus::SlotProcessed),
+            &slot_update(3, 2, SlotStatus::SlotProcessed)
+        ]
+    );
+    assert_eq!(passed.iter().filter(|item| item.is_err()).count(), 1);
+}
@@ -0,0 +1,403 @@
+//! replay_from_files and RequestMatcher: slot ranges, pacing and client-side
+//! filtering of recorded updates.
+
+mod common;
+
+use common::{account_update, slot_update, TempDir};
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_request_filter_accounts_filter::Filter,
+    subscribe_request_filter_accounts_filter_lamports::Cmp,
+    subscribe_request_filter_accounts_filter_memcmp::Data, subscribe_update::UpdateOneof,
+    CommitmentLevel, SlotStatus, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+    SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterAccountsFilterMemcmp,
+    SubscribeRequestFilterBlocks, SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions,
+    SubscribeUpdate, SubscribeUpdateAccountInfo, SubscribeUpdateBlock, SubscribeUpdatePing,
+    SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::recording::{
+    replay_from_files, Pacing, Recorder, RecorderOptions, ReplayOptions,
+};
+use helius_laserstream::solana::storage::confirmed_block::{Message, Transaction};
+use helius_laserstream::{LaserstreamError, RequestMatcher};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+fn account(slot: u64, pubkey: u8, owner: u8, lamports: u64, data: &[u8]) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: vec![pubkey; 32],
+            owner: vec![owner; 32],
+            lamports,
+            data: data.to_vec(),
+            ..Default::default()
+        },
+    )
+}
+
+fn transaction(keys: &[u8], is_vote: bool) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![7; 64],
+                is_vote,
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: keys.iter().map(|k| vec![*k; 32]).collect(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            slot: 5,
+        })),
+        ..Default::default()
+    }
+}
+
+fn b58(byte: u8) -> String {
+    bs58::encode([byte; 32]).into_string()
+}
+
+fn accounts_request() -> SubscribeRequest {
+    let mut request = SubscribeRequest::default();
+    request.accounts.insert(
+        "owned".to_string(),
+        SubscribeRequestFilterAccounts {
+            owner: vec![b58(9)],
+            filters: vec![
+                SubscribeRequestFilterAccountsFilter {
+                    filter: Some(Filter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                        offset: 1,
+                        data: Some(Data::Base58(bs58::encode([2, 3]).into_string())),
+                    })),
+                },
+                SubscribeRequestFilterAccountsFilter {
+                    filter: Some(Filter::Lamports(
+                        SubscribeRequestFilterAccountsFilterLamports {
+                            cmp: Some(Cmp::Gt(100)),
+                        },
+                    )),
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    request
+}
+
+#[test]
+fn matches_like_the_server() {
+    let mut request = accounts_request();
+    request.accounts_data_slice = vec![SubscribeRequestAccountsDataSlice {
+        offset: 1,
+        length: 2,
+    }];
+    request
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    request.transactions.insert(
+        "swaps".to_string(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            account_required: vec![b58(1), b58(2)],
+            ..Default::default()
+        },
+    );
+    let matcher = RequestMatcher::new(&request).unwrap();
+
+    let hit = matcher
+        .filter(account(5, 1, 9, 500, &[1, 2, 3, 4]))
+        .unwrap();
+    assert_eq!(hit.filters, vec!["owned".to_string()]);
+    let Some(UpdateOneof::Account(sliced)) = hit.update_oneof else {
+        panic!("expected an account update");
+    };
+    assert_eq!(sliced.account.unwrap().data, vec![2, 3]);
+    // Wrong owner, wrong bytes, too few lamports.
+    assert!(matcher.filter(account(5, 1, 8, 500, &[1, 2, 3])).is_none());
+    assert!(matcher.filter(account(5, 1, 9, 500, &[1, 2, 4])).is_none());
+    assert!(matcher.filter(account(5, 1, 9, 100, &[1, 2, 3])).is_none());
+
+    assert_eq!(
+        matcher.matches(&slot_update(5, 4, SlotStatus::SlotConfirmed)),
+        vec!["slots".to_string()]
+    );
+    assert!(matcher
+        .matches(&slot_update(5, 4, SlotStatus::SlotCreatedBank))
+        .is_empty());
+
+    assert_eq!(
+        matcher.matches(&transaction(&[1, 2, 3], false)),
+        vec!["swaps".to_string()]
+    );
+    assert!(matcher.matches(&transaction(&[1, 3], false)).is_empty());
+    assert!(matcher.matches(&transaction(&[1, 2], true)).is_empty());
+
+    let mut invalid = SubscribeRequest::default();
+    invalid.accounts.insert(
+        "bad".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: vec!["not base58 0OIl".to_string()],
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        RequestMatcher::new(&invalid),
+        Err(LaserstreamError::InvalidFilter(_))
+    ));
+}
+
+#[test]
+fn narrows_blocks_to_each_filters_contents() {
+    let mut request = SubscribeRequest::default();
+    request.blocks.insert(
+        "accounts".to_string(),
+        SubscribeRequestFilterBlocks {
+            account_include: vec![b58(1)],
+            include_transactions: Some(false),
+            include_accounts: Some(true),
+            ..Default::default()
+        },
+    );
+    request.blocks.insert(
+        "transactions".to_string(),
+        SubscribeRequestFilterBlocks {
+            account_include: vec![b58(4)],
+            ..Default::default()
+        },
+    );
+    request.accounts.insert(
+        "far".to_string(),
+        SubscribeRequestFilterAccounts {
+            filters: vec![SubscribeRequestFilterAccountsFilter {
+                filter: Some(Filter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                    offset: u64::MAX,
+                    data: Some(Data::Bytes(vec![1])),
+                })),
+            }],
+            ..Default::default()
+        },
+    );
+    let matcher = RequestMatcher::new(&request).unwrap();
+
+    let transactions = [&[1, 2][..], &[3], &[4, 5]]
+        .into_iter()
+        .map(|keys| match transaction(keys, false).update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => tx.transaction.unwrap(),
+            _ => unreachable!(),
+        })
+        .collect();
+    let accounts = [1, 4]
+        .into_iter()
+        .map(|key| match account(5, key, 9, 1, &[]).update_oneof {
+            Some(UpdateOneof::Account(account)) => account.account.unwrap(),
+            _ => unreachable!(),
+        })
+        .collect();
+    let block = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Block(SubscribeUpdateBlock {
+            slot: 5,
+            transactions,
+            accounts,
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    let filtered = matcher.filter(block).unwrap();
+    assert_eq!(
+        filtered.filters,
+        vec!["accounts".to_string(), "transactions".to_string()]
+    );
+    let Some(UpdateOneof::Block(block)) = filtered.update_oneof else {
+        panic!("expected a block");
+    };
+    // Only the transactions filter includes transactions, and only those touching 4.
+    let keys: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| {
+            tx.transaction
+                .as_ref()
+                .unwrap()
+                .message
+                .as_ref()
+                .unwrap()
+                .account_keys[0][0]
+        })
+        .collect();
+    assert_eq!(keys, vec![4]);
+    // Only the accounts filter includes accounts, and only account 1.
+    let accounts: Vec<_> = block.accounts.iter().map(|a| a.pubkey[0]).collect();
+    assert_eq!(accounts, vec![1]);
+
+    // A block touching no included key is still delivered, emptied.
+    let empty = matcher
+        .filter(SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Block(SubscribeUpdateBlock::default())),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(empty.filters.len(), 2);
+
+    // A memcmp offset near u64::MAX doesn't match (or overflow).
+    assert!(matcher.filter(account(5, 1, 9, 1, &[1, 2, 3])).is_none());
+}
+
+#[tokio::test]
+async fn replays_a_slot_range_through_a_request() {
+    let dir = TempDir::new("replay-range");
+    let mut recorder = Recorder::create(
+        &dir,
+        &SubscribeRequest::default(),
+        RecorderOptions::default(),
+    )
+    .unwrap();
+    for n in 10..20 {
+        recorder
+            .record(&slot_update(n, n - 1, SlotStatus::SlotProcessed))
+            .unwrap();
+        recorder.record(&account(n, 1, 9, n, &[0, 2, 3])).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let options = ReplayOptions::default()
+        .with_start_slot(12)
+        .with_end_slot(15)
+        .with_request(accounts_request());
+    let (stream, handle) = replay_from_files(&dir, options);
+    futures::pin_mut!(stream);
+
+    // No account passes lamports > 100, so only the written slot filter yields.
+    let mut slots = SubscribeRequest::default();
+    slots
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    handle.write(slots).await.unwrap();
+    let mut seen = Vec::new();
+    while let Some(update) = stream.next().await {
+        let update = update.unwrap();
+        assert_eq!(update.filters, vec!["slots".to_string()]);
+        if let Some(UpdateOneof::Slot(s)) = update.update_oneof {
+            seen.push(s.slot);
+        }
+    }
+    assert_eq!(seen, vec![12, 13, 14, 15]);
+
+    let (missing, _) = replay_from_files(dir.join("missing"), ReplayOptions::default());
+    futures::pin_mut!(missing);
+    assert!(missing.next().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn paces_by_receive_time() {
+    let dir = TempDir::new("replay-pacing");
+    let mut recorder = Recorder::create(
+        &dir,
+        &SubscribeRequest::default(),
+        RecorderOptions::default(),
+    )
+    .unwrap();
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    for (n, offset) in [(1, 0), (2, 500), (3, 1_000)] {
+        let at = start + Duration::from_millis(offset);
+        recorder
+            .record_at(&slot_update(n, n - 1, SlotStatus::SlotProcessed), at)
+            .unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let began = Instant::now();
+    let options = ReplayOptions::default().with_pacing(Pacing::Speed(10.0));
+    let (stream, _handle) = replay_from_files(&dir, options);
+    assert_eq!(stream.collect::<Vec<_>>().await.len(), 3);
+    let elapsed = began.elapsed();
+    assert!(elapsed >= Duration::from_millis(95), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(900), "{elapsed:?}");
+
+    let began = Instant::now();
+    let (stream, _handle) = replay_from_files(&dir, ReplayOptions::default());
+    assert_eq!(stream.collect::<Vec<_>>().await.len(), 3);
+    assert!(began.elapsed() < Duration::from_millis(95));
+}
+
+#[tokio::test]
+async fn slot_filters_by_commitment() {
+    let dir = TempDir::new("replay-commitment");
+    let mut recorder = Recorder::create(
+        &dir,
+        &SubscribeRequest::default(),
+        RecorderOptions::default(),
+    )
+    .unwrap();
+    for status in [
+        SlotStatus::SlotProcessed,
+        SlotStatus::SlotConfirmed,
+        SlotStatus::SlotFinalized,
+    ] {
+        recorder.record(&slot_update(7, 6, status)).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let mut request = SubscribeRequest {
+        commitment: Some(CommitmentLevel::Confirmed as i32),
+        ..Default::default()
+    };
+    request.slots.insert(
+        "committed".to_string(),
+        SubscribeRequestFilterSlots {
+            filter_by_commitment: Some(true),
+            ..Default::default()
+        },
+    );
+    request
+        .slots
+        .insert("all".to_string(), SubscribeRequestFilterSlots::default());
+    let (stream, _handle) = replay_from_files(&dir, ReplayOptions::default().with_request(request));
+    let filters: Vec<_> = stream.map(|update| update.unwrap().filters).collect().await;
+    assert_eq!(
+        filters,
+        vec![
+            vec!["all".to_string()],
+            vec!["all".to_string(), "committed".to_string()],
+            vec!["all".to_string()],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn replays_pings_and_survives_tiny_speeds() {
+    let dir = TempDir::new("replay-pings");
+    let mut recorder = Recorder::create(
+        &dir,
+        &SubscribeRequest::default(),
+        RecorderOptions::default(),
+    )
+    .unwrap();
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let ping = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+        ..Default::default()
+    };
+    recorder.record_at(&ping, start).unwrap();
+    recorder
+        .record_at(&ping, start + Duration::from_secs(2))
+        .unwrap();
+    recorder.finish().unwrap();
+
+    let (stream, _handle) = replay_from_files(&dir, ReplayOptions::default());
+    assert_eq!(stream.collect::<Vec<_>>().await.len(), 2);
+
+    // A gap this slow is never reached rather than overflowing.
+    let options = ReplayOptions::default().with_pacing(Pacing::Speed(1e-20));
+    let (stream, _handle) = replay_from_files(&dir, options);
+    futures::pin_mut!(stream);
+    assert!(stream.next().await.unwrap().is_ok());
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), stream.next())
+            .await
+            .is_err()
+    );
+}
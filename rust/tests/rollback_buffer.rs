@@ -0,0 +1,171 @@
+//! RollbackBuffer: optimistic delivery, rollback of orphaned slots and deep forks,
+//! rooting, settled slots and the buffer cap.
+
+mod common;
+
+use common::{account_update, slot_update};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SlotStatus, SubscribeUpdate, SubscribeUpdateAccountInfo,
+    SubscribeUpdateSlot,
+};
+use helius_laserstream::rollback::{ForkEvent, RollbackBuffer};
+
+fn account(slot: u64, lamports: u64) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: vec![1; 32],
+            lamports,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn rolls_back_orphaned_slot_and_roots_the_rest() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.push(slot_update(10, 9, SlotStatus::SlotProcessed));
+    buffer.push(account(11, 1));
+    buffer.push(slot_update(11, 10, SlotStatus::SlotProcessed));
+    buffer.push(account(12, 2));
+    buffer.push(account(12, 3));
+    buffer.push(slot_update(12, 10, SlotStatus::SlotProcessed));
+
+    let events = buffer.push(account(13, 4));
+    assert!(matches!(&events[..], [ForkEvent::Update { slot: Some(13), .. }]));
+    assert_eq!(buffer.buffered(12).len(), 2);
+
+    let events = buffer.push(slot_update(13, 12, SlotStatus::SlotFinalized));
+    let rolled_back: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            ForkEvent::Rollback { slot, updates } => Some((*slot, updates.len())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rolled_back, vec![(11, 1)]);
+
+    let rooted: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            ForkEvent::Rooted { slot } => Some(*slot),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rooted, vec![13, 10, 12]);
+    assert!(buffer.buffered(12).is_empty());
+}
+
+#[test]
+fn late_update_for_dead_slot_is_rolled_back_immediately() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.push(account(20, 1));
+    let events = buffer.push(SubscribeUpdate {
+        filters: vec!["slots".to_string()],
+        update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot: 20,
+            parent: Some(19),
+            status: SlotStatus::SlotDead as i32,
+            dead_error: Some("bad".to_string()),
+        })),
+        created_at: None,
+    });
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, ForkEvent::Rollback { slot: 20, updates } if updates.len() == 1)));
+
+    let events = buffer.push(account(20, 2));
+    assert!(matches!(
+        &events[..],
+        [ForkEvent::Update { .. }, ForkEvent::Rollback { slot: 20, .. }]
+    ));
+}
+
+fn rolled_back(events: &[ForkEvent]) -> Vec<(u64, Vec<u64>)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            ForkEvent::Rollback { slot, updates } => Some((
+                *slot,
+                updates
+                    .iter()
+                    .map(|update| match &update.update_oneof {
+                        Some(UpdateOneof::Account(account)) => {
+                            account.account.as_ref().unwrap().lamports
+                        }
+                        _ => 0,
+                    })
+                    .collect(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn deep_fork_rolls_back_every_abandoned_slot() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.push(slot_update(10, 9, SlotStatus::SlotProcessed));
+    for (number, parent) in [(11, 10), (12, 11), (13, 12)] {
+        buffer.push(slot_update(number, parent, SlotStatus::SlotProcessed));
+        buffer.push(account(number, number * 10));
+        buffer.push(account(number, number * 10 + 1));
+    }
+
+    // The switch is advisory: the abandoned branch stays buffered.
+    let events = buffer.push(slot_update(14, 10, SlotStatus::SlotProcessed));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        ForkEvent::Reorg { previous_tip: 13, new_tip: 14, abandoned } if abandoned == &[13, 12, 11]
+    )));
+    assert_eq!(buffer.buffered(12).len(), 2);
+
+    let events = buffer.push(slot_update(14, 10, SlotStatus::SlotFinalized));
+    assert_eq!(
+        rolled_back(&events),
+        vec![
+            (11, vec![110, 111]),
+            (12, vec![120, 121]),
+            (13, vec![130, 131]),
+        ]
+    );
+    assert!((11..=13).all(|slot| buffer.buffered(slot).is_empty()));
+}
+
+#[test]
+fn settled_slots_are_not_buffered() {
+    let mut buffer = RollbackBuffer::new();
+    buffer.push(slot_update(10, 9, SlotStatus::SlotProcessed));
+    buffer.push(slot_update(10, 9, SlotStatus::SlotFinalized));
+
+    // Late data for the finalized slot and its ancestors is final already.
+    for slot in [10, 9] {
+        let events = buffer.push(account(slot, 1));
+        assert!(matches!(&events[..], [ForkEvent::Update { .. }]));
+        assert!(buffer.buffered(slot).is_empty());
+    }
+    // Data ahead of its slot status is buffered until the slot settles.
+    buffer.push(account(11, 2));
+    assert_eq!(buffer.buffered(11).len(), 1);
+    let events = buffer.push(slot_update(11, 10, SlotStatus::SlotFinalized));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, ForkEvent::Rooted { slot: 11 })));
+    assert!(buffer.buffered(11).is_empty());
+}
+
+#[test]
+fn drops_the_oldest_slot_past_the_cap() {
+    let mut buffer = RollbackBuffer::new().with_max_buffered_slots(2);
+    for slot in [1, 2, 3] {
+        buffer.push(account(slot, slot));
+    }
+    assert!(buffer.buffered(1).is_empty());
+    assert_eq!(buffer.buffered(3).len(), 1);
+
+    // A dropped slot rolls back without the updates it no longer holds.
+    let events = buffer.push(slot_update(1, 0, SlotStatus::SlotDead));
+    assert_eq!(rolled_back(&events), vec![(1, vec![])]);
+    let events = buffer.push(slot_update(2, 0, SlotStatus::SlotDead));
+    assert_eq!(rolled_back(&events), vec![(2, vec![2])]);
+}
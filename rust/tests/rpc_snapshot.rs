@@ -0,0 +1,274 @@
+//! RpcClient against a local JSON-RPC stand-in: snapshot calls, batching and errors;
+//! bootstrap reconciling that snapshot with a MockGeyser stream.
+
+mod common;
+
+use common::account_update;
+use futures::StreamExt;
+use helius_laserstream::accounts::{bootstrap, BootstrapEvent, BootstrapOptions};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeUpdate, SubscribeUpdateAccountInfo,
+};
+use helius_laserstream::rpc::RpcClient;
+use helius_laserstream::testing::MockGeyser;
+use helius_laserstream::LaserstreamError;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+const PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const KEY_A: &str = "So11111111111111111111111111111111111111112";
+
+/// Serves one JSON-RPC response per connection and records every request body.
+fn serve(handler: impl Fn(&Value) -> Value + Send + 'static) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let mut response = handler(&request);
+            response["jsonrpc"] = json!("2.0");
+            response["id"] = request["id"].clone();
+            log.lock().unwrap().push(request);
+
+            let payload = response.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                payload.len(),
+                payload
+            )
+            .unwrap();
+        }
+    });
+    (url, seen)
+}
+
+fn ui_account(lamports: u64) -> Value {
+    json!({
+        "lamports": lamports,
+        "owner": PROGRAM,
+        "data": ["AQID", "base64"],
+        "executable": false,
+        "rentEpoch": u64::MAX,
+        "space": 3,
+    })
+}
+
+fn handler(request: &Value) -> Value {
+    match request["method"].as_str().unwrap() {
+        "getSlot" => json!({ "result": 500 }),
+        "getProgramAccounts" => json!({
+            "result": {
+                "context": { "slot": 502 },
+                "value": [{ "pubkey": KEY_A, "account": ui_account(10) }],
+            }
+        }),
+        "getMultipleAccounts" => {
+            let keys = request["params"][0].as_array().unwrap();
+            let value: Vec<Value> = keys
+                .iter()
+                .enumerate()
+                .map(|(i, _)| if i % 2 == 0 { ui_account(i as u64) } else { Value::Null })
+                .collect();
+            json!({ "result": { "context": { "slot": 501 }, "value": value } })
+        }
+        _ => json!({ "error": { "code": -32601, "message": "Method not found" } }),
+    }
+}
+
+#[tokio::test]
+async fn program_accounts_snapshot() {
+    let (url, seen) = serve(handler);
+    let rpc = RpcClient::new(url);
+
+    assert_eq!(rpc.get_slot(CommitmentLevel::Confirmed).await.unwrap(), 500);
+    let filters = [json!({ "dataSize": 165 })];
+    let snapshot = rpc
+        .get_program_accounts(PROGRAM, &filters, CommitmentLevel::Confirmed, Some(500))
+        .await
+        .unwrap();
+
+    assert_eq!(snapshot.slot, 502);
+    let account = &snapshot.value[0];
+    assert_eq!(account.pubkey, bs58::decode(KEY_A).into_vec().unwrap());
+    assert_eq!(account.owner, bs58::decode(PROGRAM).into_vec().unwrap());
+    assert_eq!(account.data, vec![1, 2, 3]);
+    assert_eq!(account.rent_epoch, u64::MAX);
+
+    let requests = seen.lock().unwrap();
+    let config = &requests[1]["params"][1];
+    assert_eq!(config["commitment"], "confirmed");
+    assert_eq!(config["minContextSlot"], 500);
+    assert_eq!(config["withContext"], true);
+    assert_eq!(config["filters"][0]["dataSize"], 165);
+}
+
+#[tokio::test]
+async fn multiple_accounts_are_batched() {
+    let (url, seen) = serve(handler);
+    let rpc = RpcClient::new(url);
+    let keys: Vec<String> = (0..150).map(|_| KEY_A.to_string()).collect();
+
+    let result = rpc
+        .get_multiple_accounts(&keys, CommitmentLevel::Processed, None)
+        .await
+        .unwrap();
+    assert_eq!(result.slot, 501);
+    assert_eq!(result.value.len(), 150);
+    assert!(result.value[0].is_some() && result.value[1].is_none());
+    // 150 keys: one full batch of 100, then 50.
+    assert!(result.value[100].is_some());
+
+    let requests = seen.lock().unwrap();
+    let batches: Vec<usize> = requests
+        .iter()
+        .map(|r| r["params"][0].as_array().unwrap().len())
+        .collect();
+    assert_eq!(batches, vec![100, 50]);
+}
+
+#[tokio::test]
+async fn rpc_errors_are_reported() {
+    let (url, _) = serve(|_| json!({ "error": { "code": -32016, "message": "Minimum context slot has not been reached" } }));
+    let rpc = RpcClient::new(url);
+
+    let err = rpc
+        .get_program_accounts(PROGRAM, &[], CommitmentLevel::Processed, Some(1))
+        .await
+        .unwrap_err();
+    match err {
+        LaserstreamError::Rpc(message) => assert!(message.contains("-32016")),
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+fn account(slot: u64, lamports: u64) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: bs58::decode(KEY_A).into_vec().unwrap(),
+            lamports,
+            write_version: 1,
+            ..Default::default()
+        },
+    )
+}
+
+fn lamports(event: &BootstrapEvent) -> u64 {
+    match event {
+        BootstrapEvent::Update(SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Account(update)),
+            ..
+        }) => update.account.as_ref().unwrap().lamports,
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn bootstrap_reconciles_the_snapshot_with_the_stream() {
+    // getProgramAccounts answers at slot 502 once released.
+    let (requested, snapshot_requested) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let released = Mutex::new(released);
+    let (url, _) = serve(move |request| {
+        if request["method"] == "getProgramAccounts" {
+            requested.send(()).unwrap();
+            released.lock().unwrap().recv().unwrap();
+        }
+        handler(request)
+    });
+
+    let mock = MockGeyser::start().await.unwrap();
+    // Before the stream's start slot: not replayed.
+    mock.push(account(499, 1));
+    // Replayed from the start slot, but already in the snapshot.
+    mock.push(account(501, 2));
+
+    let mut request = SubscribeRequest::default();
+    request.accounts.insert(
+        "accounts".to_string(),
+        SubscribeRequestFilterAccounts::default(),
+    );
+    let (events, _handle) = bootstrap(
+        mock.config(),
+        request,
+        BootstrapOptions::program(url, PROGRAM),
+    )
+    .await
+    .unwrap();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            if tx.send(event.unwrap()).is_err() {
+                return;
+            }
+        }
+    });
+
+    mock.wait_for_requests(1).await;
+    assert_eq!(mock.requests()[0].from_slot, Some(500));
+    tokio::task::spawn_blocking(move || snapshot_requested.recv().unwrap())
+        .await
+        .unwrap();
+
+    // Streamed while the snapshot is loading: held back until it is yielded.
+    mock.push(account(502, 3));
+    mock.push(account(503, 4));
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .is_err(),
+        "an update was yielded before the snapshot"
+    );
+    release.send(()).unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let BootstrapEvent::Snapshot { slot, accounts } = event else {
+        panic!("expected the snapshot first, got {event:?}");
+    };
+    assert_eq!(slot, 502);
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].slot, 502);
+    assert_eq!(accounts[0].account.lamports, 10);
+    assert_eq!(accounts[0].account.write_version, u64::MAX);
+
+    // Live: writes at or below the snapshot slot are still dropped.
+    mock.push(account(502, 5));
+    mock.push(account(504, 6));
+    let mut streamed = Vec::new();
+    for _ in 0..2 {
+        let event = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        streamed.push(lamports(&event));
+    }
+    assert_eq!(streamed, vec![4, 6]);
+}
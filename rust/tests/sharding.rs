@@ -0,0 +1,333 @@
+//! split_request: pubkey partitioning, limits and filter-name stability;
+//! subscribe_sharded: merged-stream deduplication and re-splitting on write.
+
+mod common;
+
+use common::account_update;
+use futures::{Stream, StreamExt};
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterAccounts,
+    SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeUpdate,
+    SubscribeUpdateAccountInfo, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::sharding::{split_request, subscribe_sharded, ShardingOptions};
+use helius_laserstream::solana::storage::confirmed_block::{Message, Transaction};
+use helius_laserstream::testing::MockGeyser;
+use helius_laserstream::LaserstreamError;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+fn keys(prefix: &str, n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("{prefix}{i:06}")).collect()
+}
+
+fn request() -> SubscribeRequest {
+    let mut request = SubscribeRequest {
+        commitment: Some(1),
+        ..Default::default()
+    };
+    request.accounts.insert(
+        "wallets".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: keys("acct", 2_500),
+            owner: vec!["owner".to_string()],
+            ..Default::default()
+        },
+    );
+    request.accounts.insert(
+        "programs".to_string(),
+        SubscribeRequestFilterAccounts {
+            owner: vec!["program".to_string()],
+            ..Default::default()
+        },
+    );
+    request.transactions.insert(
+        "swaps".to_string(),
+        SubscribeRequestFilterTransactions {
+            account_include: keys("tx", 1_200),
+            account_required: vec!["pool".to_string()],
+            vote: Some(false),
+            ..Default::default()
+        },
+    );
+    request
+        .slots
+        .insert("slots".to_string(), SubscribeRequestFilterSlots::default());
+    request
+}
+
+#[test]
+fn respects_per_filter_limit_without_losing_keys() {
+    let options = ShardingOptions::default().with_max_accounts_per_filter(1_000);
+    let shards = split_request(&request(), &options);
+    assert!(shards.len() >= 3);
+
+    let mut accounts = HashSet::new();
+    let mut includes = HashSet::new();
+    for shard in &shards {
+        assert_eq!(shard.commitment, Some(1));
+        if let Some(f) = shard.accounts.get("wallets") {
+            assert!(!f.account.is_empty() && f.account.len() <= 1_000);
+            assert_eq!(f.owner, vec!["owner"]);
+            for key in &f.account {
+                assert!(accounts.insert(key.clone()), "key on two shards");
+            }
+        }
+        if let Some(f) = shard.transactions.get("swaps") {
+            assert!(!f.account_include.is_empty() && f.account_include.len() <= 1_000);
+            assert_eq!(f.account_required, vec!["pool"]);
+            includes.extend(f.account_include.iter().cloned());
+        }
+    }
+    assert_eq!(accounts.len(), 2_500);
+    assert_eq!(includes.len(), 1_200);
+}
+
+#[test]
+fn unsplittable_filters_go_to_first_shard() {
+    let options = ShardingOptions::default().with_max_accounts_per_filter(1_000);
+    let shards = split_request(&request(), &options);
+
+    assert!(shards[0].slots.contains_key("slots"));
+    assert!(shards[0].accounts.contains_key("programs"));
+    for shard in &shards[1..] {
+        assert!(shard.slots.is_empty());
+        assert!(!shard.accounts.contains_key("programs"));
+    }
+}
+
+#[test]
+fn growing_moves_only_some_keys() {
+    let options = ShardingOptions::default().with_max_accounts_per_filter(100_000);
+    let before = split_request(&request(), &options.clone().with_min_shards(4));
+    let after = split_request(&request(), &options.with_min_shards(5));
+    assert_eq!((before.len(), after.len()), (4, 5));
+
+    // Every key either stays put or moves to the new shard.
+    for (index, shard) in before.iter().enumerate() {
+        let kept: HashSet<_> = after[index].accounts["wallets"].account.iter().collect();
+        for key in &shard.accounts["wallets"].account {
+            assert!(
+                kept.contains(key) || after[4].accounts["wallets"].account.contains(key),
+                "{key} moved between existing shards"
+            );
+        }
+    }
+}
+
+fn pubkey(byte: u8) -> String {
+    bs58::encode([byte; 32]).into_string()
+}
+
+fn transaction(signature: u8, keys: &[u8]) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![signature; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        account_keys: keys.iter().map(|k| vec![*k; 32]).collect(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            slot: 5,
+        })),
+        ..Default::default()
+    }
+}
+
+fn account(key: u8, owner: u8) -> SubscribeUpdate {
+    account_update(
+        5,
+        SubscribeUpdateAccountInfo {
+            pubkey: vec![key; 32],
+            owner: vec![owner; 32],
+            write_version: 1,
+            ..Default::default()
+        },
+    )
+}
+
+/// Forwards the merged stream's updates to a channel.
+fn drive(
+    stream: impl Stream<Item = Result<SubscribeUpdate, LaserstreamError>> + Send + 'static,
+) -> UnboundedReceiver<SubscribeUpdate> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        while let Some(update) = stream.next().await {
+            if tx.send(update.unwrap()).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+async fn receive(updates: &mut UnboundedReceiver<SubscribeUpdate>) -> SubscribeUpdate {
+    tokio::time::timeout(Duration::from_secs(10), updates.recv())
+        .await
+        .expect("no update within 10s")
+        .expect("stream ended")
+}
+
+#[tokio::test]
+async fn merged_stream_yields_each_update_once_per_filter() {
+    let mock = MockGeyser::start().await.unwrap();
+    let mut request = SubscribeRequest::default();
+    // At most one key per shard: a transaction touching both is delivered by two.
+    request.transactions.insert(
+        "swaps".to_string(),
+        SubscribeRequestFilterTransactions {
+            account_include: vec![pubkey(1), pubkey(2)],
+            ..Default::default()
+        },
+    );
+    request.accounts.insert(
+        "wallets".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: vec![pubkey(1), pubkey(2)],
+            ..Default::default()
+        },
+    );
+    // Unsplittable, on shard 0 only.
+    request.accounts.insert(
+        "owned".to_string(),
+        SubscribeRequestFilterAccounts {
+            owner: vec![pubkey(9)],
+            ..Default::default()
+        },
+    );
+    let options = ShardingOptions::default().with_max_accounts_per_filter(1);
+    let (stream, handle) = subscribe_sharded(mock.config(), request, options);
+    let shards = handle.shard_count().await;
+    assert!(shards >= 2);
+    let mut updates = drive(stream);
+    mock.wait_for_requests(shards).await;
+
+    mock.push(transaction(7, &[1, 2]));
+    mock.push(transaction(8, &[2]));
+    // Matched as "wallets" on its own shard and as "owned" on shard 0.
+    mock.push(account(1, 9));
+    mock.push(account(2, 9));
+
+    // On shard 0, an account is one update matching both filters.
+    let mut delivered = Vec::new();
+    while delivered.len() < 6 {
+        let update = receive(&mut updates).await;
+        let id = match update.update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => tx.transaction.unwrap().signature[0],
+            Some(UpdateOneof::Account(account)) => account.account.unwrap().pubkey[0],
+            other => panic!("unexpected update: {other:?}"),
+        };
+        for filter in update.filters {
+            delivered.push((id, filter));
+        }
+    }
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), updates.recv())
+            .await
+            .is_err(),
+        "a duplicate was yielded"
+    );
+    delivered.sort();
+    let expected: Vec<(u8, String)> = [
+        (1, "owned"),
+        (1, "wallets"),
+        (2, "owned"),
+        (2, "wallets"),
+        (7, "swaps"),
+        (8, "swaps"),
+    ]
+    .into_iter()
+    .map(|(id, filter)| (id, filter.to_string()))
+    .collect();
+    assert_eq!(delivered, expected);
+}
+
+#[tokio::test]
+async fn write_resplits_across_more_shards() {
+    let mock = MockGeyser::start().await.unwrap();
+    let wallets = |keys: std::ops::Range<u8>| {
+        let mut request = SubscribeRequest::default();
+        request.accounts.insert(
+            "wallets".to_string(),
+            SubscribeRequestFilterAccounts {
+                account: keys.map(pubkey).collect(),
+                ..Default::default()
+            },
+        );
+        request
+    };
+    let options = ShardingOptions::default().with_max_accounts_per_filter(2);
+    let (stream, handle) = subscribe_sharded(mock.config(), wallets(1..3), options);
+    let mut updates = drive(stream);
+    assert_eq!(handle.shard_count().await, 1);
+    mock.wait_for_requests(1).await;
+
+    handle.write(wallets(1..7)).await.unwrap();
+    let shards = handle.shard_count().await;
+    assert!(shards >= 3);
+    // The existing shard's write, then one subscription per new shard.
+    mock.wait_for_requests(1 + shards).await;
+    let requests = mock.requests();
+    let mut keys = HashSet::new();
+    for request in &requests[1..] {
+        // A shard none of the keys landed on gets no filter.
+        let Some(part) = request.accounts.get("wallets") else {
+            continue;
+        };
+        assert!(!part.account.is_empty() && part.account.len() <= 2);
+        for key in &part.account {
+            assert!(keys.insert(key.clone()), "{key} on two shards");
+        }
+    }
+    assert_eq!(keys, (1..7).map(pubkey).collect());
+
+    // Keys on the new shards stream through the merged stream.
+    for key in 1..7 {
+        mock.push(account(key, 0));
+    }
+    let mut streamed = HashSet::new();
+    for _ in 1..7 {
+        let update = receive(&mut updates).await;
+        let Some(UpdateOneof::Account(account)) = update.update_oneof else {
+            panic!("unexpected update");
+        };
+        streamed.insert(account.account.unwrap().pubkey[0]);
+    }
+    assert_eq!(streamed, (1..7).collect());
+
+    // Shrinking the lists keeps every shard open.
+    handle.write(wallets(1..2)).await.unwrap();
+    assert_eq!(handle.shard_count().await, shards);
+}
+
+#[tokio::test]
+async fn write_reports_every_failed_shard() {
+    let mock = MockGeyser::start().await.unwrap();
+    let mut request = SubscribeRequest::default();
+    request.accounts.insert(
+        "wallets".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: (1..5).map(pubkey).collect(),
+            ..Default::default()
+        },
+    );
+    let options = ShardingOptions::default().with_max_accounts_per_filter(2);
+    let (stream, handle) = subscribe_sharded(mock.config(), request.clone(), options);
+    let shards = handle.shard_count().await;
+    assert!(shards >= 2);
+
+    // Dropping the merged stream ends every shard's stream.
+    drop(stream);
+    let err = handle.write(request).await.unwrap_err();
+    let LaserstreamError::ShardWrite { shards: failed, .. } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(failed, (0..shards).collect::<Vec<_>>());
+}
@@ -0,0 +1,168 @@
+//! SkipDetector: skipped slots from finalized parent links, fork depths and leader
+//! attribution; subscribe_skips with schedules extended through its handle.
+
+mod common;
+
+use common::{slot, slot_update};
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SlotStatus, SubscribeUpdate, SubscribeUpdateBlockMeta,
+    SubscribeUpdateSlot,
+};
+use helius_laserstream::skips::{subscribe_skips, LeaderSchedule, SkipDetector, SkipEvent};
+use helius_laserstream::testing::MockGeyser;
+use std::time::Duration;
+
+/// A slot update without its parent link.
+fn unlinked(number: u64, status: SlotStatus) -> SubscribeUpdateSlot {
+    SubscribeUpdateSlot {
+        parent: None,
+        ..slot(number, 0, status)
+    }
+}
+
+fn skipped(events: &[SkipEvent]) -> Vec<u64> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            SkipEvent::SlotSkipped { slot, .. } => Some(*slot),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn reports_gaps_between_finalized_parents() {
+    let mut detector = SkipDetector::new();
+    detector.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    detector.slot(&slot(13, 10, SlotStatus::SlotProcessed));
+    detector.slot(&slot(14, 13, SlotStatus::SlotProcessed));
+    assert!(skipped(&detector.slot(&slot(10, 9, SlotStatus::SlotFinalized))).is_empty());
+
+    let events = detector.slot(&slot(14, 13, SlotStatus::SlotFinalized));
+    assert_eq!(skipped(&events), vec![11, 12]);
+    let stats = detector.stats();
+    assert_eq!((stats.produced, stats.skipped), (3, 2));
+    assert!((stats.skip_rate() - 0.4).abs() < 1e-9);
+}
+
+#[test]
+fn measures_forks_and_attributes_skips_to_leaders() {
+    let schedule = LeaderSchedule::from_epoch(
+        8,
+        [
+            ("alpha".to_string(), vec![2, 3]),
+            ("beta".to_string(), vec![4, 5]),
+        ],
+    );
+    let mut detector = SkipDetector::new().with_leader_schedule(schedule);
+    detector.slot(&slot(10, 9, SlotStatus::SlotProcessed));
+    detector.slot(&slot(10, 9, SlotStatus::SlotFinalized));
+    detector.slot(&slot(11, 10, SlotStatus::SlotProcessed));
+
+    // 12 builds on 10, abandoning 11.
+    let events = detector.slot(&slot(12, 10, SlotStatus::SlotProcessed));
+    assert!(events.contains(&SkipEvent::Fork {
+        common_ancestor: 10,
+        depth: 1
+    }));
+    detector.slot(&slot(13, 12, SlotStatus::SlotProcessed));
+
+    let events = detector.slot(&slot(13, 12, SlotStatus::SlotFinalized));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, SkipEvent::SlotSkipped { .. }))
+            .collect::<Vec<_>>(),
+        vec![&SkipEvent::SlotSkipped {
+            slot: 11,
+            leader: Some("alpha".to_string())
+        }]
+    );
+
+    let alpha = detector.leader_stats("alpha").unwrap();
+    assert_eq!((alpha.produced, alpha.skipped), (1, 1));
+    assert_eq!(alpha.skip_rate(), 0.5);
+    assert_eq!(detector.leader_stats("beta").unwrap().produced, 2);
+    assert_eq!(detector.stats().max_fork_depth, 1);
+    assert_eq!(detector.stats().mean_fork_depth(), Some(1.0));
+}
+
+#[test]
+fn uses_block_meta_parents_when_slot_updates_lack_them() {
+    let mut detector = SkipDetector::new();
+    detector.slot(&slot(20, 19, SlotStatus::SlotFinalized));
+    detector.slot(&unlinked(23, SlotStatus::SlotProcessed));
+    detector.apply(&SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+            slot: 23,
+            parent_slot: 20,
+            ..Default::default()
+        })),
+        ..Default::default()
+    });
+
+    let events = detector.slot(&unlinked(23, SlotStatus::SlotFinalized));
+    assert_eq!(skipped(&events), vec![21, 22]);
+}
+
+async fn next(events: &mut tokio::sync::mpsc::UnboundedReceiver<SkipEvent>) -> SkipEvent {
+    tokio::time::timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("no event within 10s")
+        .expect("stream ended")
+}
+
+#[tokio::test]
+async fn attributes_across_epochs_added_while_running() {
+    let mock = MockGeyser::start().await.unwrap();
+    let epoch = |leader: &str| vec![(leader.to_string(), vec![0, 1, 2, 3])];
+    let detector =
+        SkipDetector::new().with_leader_schedule(LeaderSchedule::from_epoch(10, epoch("first")));
+    let (events, skips) = subscribe_skips(mock.config(), detector);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            if tx.send(event.unwrap()).is_err() {
+                return;
+            }
+        }
+    });
+    mock.wait_for_requests(1).await;
+
+    let finalize = |n: u64, parent: u64| {
+        for status in [SlotStatus::SlotProcessed, SlotStatus::SlotFinalized] {
+            mock.push(slot_update(n, parent, status));
+        }
+    };
+    finalize(10, 9);
+    finalize(13, 10);
+    assert_eq!(
+        next(&mut rx).await,
+        SkipEvent::SlotSkipped {
+            slot: 11,
+            leader: Some("first".to_string())
+        }
+    );
+    next(&mut rx).await;
+
+    // The next epoch, added before it starts.
+    skips.extend_epoch(14, epoch("second"));
+    finalize(16, 13);
+    assert_eq!(
+        next(&mut rx).await,
+        SkipEvent::SlotSkipped {
+            slot: 14,
+            leader: Some("second".to_string())
+        }
+    );
+    next(&mut rx).await;
+
+    let first = skips.leader_stats("first").unwrap();
+    assert_eq!((first.produced, first.skipped), (2, 2));
+    let second = skips.leader_stats("second").unwrap();
+    assert_eq!((second.produced, second.skipped), (1, 2));
+    assert_eq!(skips.stats().skipped, 4);
+    assert_eq!(skips.leaders().len(), 2);
+}
@@ -0,0 +1,159 @@
+//! SlotTracker: lifecycle stages, fork switches and reorg depth, out-of-order statuses,
+//! finalized-chain queries and the retention window.
+
+mod common;
+
+use common::slot;
+use helius_laserstream::grpc::{SlotStatus, SubscribeUpdateSlot};
+use helius_laserstream::slots::{OrphanReason, SlotEvent, SlotStage, SlotTracker};
+
+#[test]
+fn records_stages_and_propagates_to_ancestors() {
+    let mut tracker = SlotTracker::new();
+    tracker.update(&slot(10, 9, SlotStatus::SlotFirstShredReceived));
+    tracker.update(&slot(10, 9, SlotStatus::SlotProcessed));
+    tracker.update(&slot(11, 10, SlotStatus::SlotProcessed));
+
+    let events = tracker.update(&slot(11, 10, SlotStatus::SlotConfirmed));
+    assert_eq!(
+        events,
+        vec![
+            SlotEvent::Stage { slot: 11, stage: SlotStage::Confirmed },
+            SlotEvent::Stage { slot: 10, stage: SlotStage::Confirmed },
+        ]
+    );
+
+    let info = tracker.get(10).unwrap();
+    assert!(info.first_shred_received.is_some());
+    assert_eq!(info.stage(), Some(SlotStage::Confirmed));
+    assert_eq!(tracker.confirmed_slot(), Some(11));
+    // Repeats are not reported again.
+    assert!(tracker.update(&slot(11, 10, SlotStatus::SlotConfirmed)).is_empty());
+}
+
+#[test]
+fn reports_fork_switch() {
+    let mut tracker = SlotTracker::new();
+    // 10 <- 11 <- 12, then 13 builds on 10 instead.
+    tracker.update(&slot(10, 9, SlotStatus::SlotProcessed));
+    tracker.update(&slot(11, 10, SlotStatus::SlotProcessed));
+    tracker.update(&slot(12, 11, SlotStatus::SlotProcessed));
+
+    let events = tracker.update(&slot(13, 10, SlotStatus::SlotProcessed));
+    assert!(events.contains(&SlotEvent::Reorg {
+        previous_tip: 12,
+        new_tip: 13,
+        common_ancestor: 10,
+        abandoned: vec![12, 11],
+    }));
+    assert_eq!(tracker.processed_tip(), Some(13));
+}
+
+#[test]
+fn finalization_orphans_other_forks() {
+    let mut tracker = SlotTracker::new();
+    tracker.update(&slot(10, 9, SlotStatus::SlotProcessed));
+    tracker.update(&slot(11, 10, SlotStatus::SlotProcessed));
+    tracker.update(&slot(12, 10, SlotStatus::SlotProcessed));
+    tracker.update(&SubscribeUpdateSlot {
+        slot: 14,
+        parent: Some(11),
+        status: SlotStatus::SlotDead as i32,
+        dead_error: Some("invalid block".to_string()),
+    })
+    .iter()
+    .find(|e| matches!(e, SlotEvent::Orphaned { slot: 14, reason: OrphanReason::Dead(Some(_)) }))
+    .expect("dead slot orphaned");
+
+    let events = tracker.update(&slot(13, 12, SlotStatus::SlotFinalized));
+    assert!(events.contains(&SlotEvent::Orphaned {
+        slot: 11,
+        reason: OrphanReason::NotOnFinalizedChain,
+    }));
+    assert!(events.contains(&SlotEvent::Stage { slot: 12, stage: SlotStage::Finalized }));
+
+    assert_eq!(tracker.is_on_finalized_chain(12), Some(true));
+    assert_eq!(tracker.is_on_finalized_chain(11), Some(false));
+    assert_eq!(tracker.is_on_finalized_chain(10), Some(true));
+    assert_eq!(tracker.is_on_finalized_chain(14), None);
+}
+
+#[test]
+fn measures_reorg_depth_and_switching_back() {
+    let mut tracker = SlotTracker::new();
+    // 10 <- 11 <- 12 <- 13, then 14 builds on 10.
+    for (number, parent) in [(10, 9), (11, 10), (12, 11), (13, 12)] {
+        tracker.update(&slot(number, parent, SlotStatus::SlotProcessed));
+    }
+    let events = tracker.update(&slot(14, 10, SlotStatus::SlotProcessed));
+    assert!(events.contains(&SlotEvent::Reorg {
+        previous_tip: 13,
+        new_tip: 14,
+        common_ancestor: 10,
+        abandoned: vec![13, 12, 11],
+    }));
+
+    // Back on the first fork, only 14 is abandoned.
+    let events = tracker.update(&slot(15, 13, SlotStatus::SlotProcessed));
+    assert!(events.contains(&SlotEvent::Reorg {
+        previous_tip: 14,
+        new_tip: 15,
+        common_ancestor: 10,
+        abandoned: vec![14],
+    }));
+    assert_eq!(tracker.ancestors(15).collect::<Vec<_>>(), vec![13, 12, 11, 10, 9]);
+}
+
+#[test]
+fn tolerates_out_of_order_statuses() {
+    let mut tracker = SlotTracker::new();
+    tracker.update(&slot(12, 11, SlotStatus::SlotProcessed));
+    // A late notification for an ancestor of the tip is not a fork switch.
+    let events = tracker.update(&slot(11, 10, SlotStatus::SlotProcessed));
+    assert_eq!(events, vec![SlotEvent::Stage { slot: 11, stage: SlotStage::Processed }]);
+    assert_eq!(tracker.processed_tip(), Some(12));
+
+    // Finalized before confirmed: ancestors are confirmed, then finalized.
+    let events = tracker.update(&slot(12, 11, SlotStatus::SlotFinalized));
+    assert_eq!(
+        events,
+        vec![
+            SlotEvent::Stage { slot: 12, stage: SlotStage::Finalized },
+            SlotEvent::Stage { slot: 11, stage: SlotStage::Confirmed },
+            SlotEvent::Stage { slot: 11, stage: SlotStage::Finalized },
+        ]
+    );
+    assert_eq!(tracker.confirmed_slot(), Some(12));
+
+    // Late statuses never move a slot or the tracker backwards.
+    tracker.update(&slot(12, 11, SlotStatus::SlotConfirmed));
+    assert!(tracker.update(&slot(11, 10, SlotStatus::SlotConfirmed)).is_empty());
+    tracker.update(&slot(10, 9, SlotStatus::SlotFinalized));
+    assert_eq!(tracker.get(12).unwrap().stage(), Some(SlotStage::Finalized));
+    assert_eq!(tracker.finalized_slot(), Some(12));
+    assert_eq!(tracker.processed_tip(), Some(12));
+}
+
+#[test]
+fn prunes_below_the_retention_window() {
+    let mut tracker = SlotTracker::with_retention(5);
+    for number in 10..=20 {
+        tracker.update(&slot(number, number - 1, SlotStatus::SlotProcessed));
+    }
+    tracker.update(&slot(20, 19, SlotStatus::SlotFinalized));
+
+    // 20 - 5 is the oldest slot kept.
+    assert!(tracker.get(14).is_none());
+    assert!(tracker.get(15).is_some());
+    assert!(tracker.update(&slot(14, 13, SlotStatus::SlotConfirmed)).is_empty());
+    assert!(tracker.get(14).is_none());
+
+    assert_eq!(tracker.is_on_finalized_chain(15), Some(true));
+    assert_eq!(tracker.is_on_finalized_chain(14), None);
+    assert_eq!(tracker.is_on_finalized_chain(21), None);
+
+    // Finalizing further moves the window with it.
+    tracker.update(&slot(21, 20, SlotStatus::SlotFinalized));
+    assert!(tracker.get(15).is_none());
+    assert_eq!(tracker.is_on_finalized_chain(16), Some(true));
+}
@@ -0,0 +1,212 @@
+//! LifecycleTracker: per-signature timelines across preprocessed, processed and slot
+//! updates, drops and forks; subscribe_lifecycle with signatures watched through its
+//! handle while running.
+
+mod common;
+
+use common::{slot, slot_update};
+use futures::StreamExt;
+use helius_laserstream::grpc::{
+    subscribe_preprocessed_update, subscribe_update::UpdateOneof, SlotStatus,
+    SubscribePreprocessedRequest, SubscribePreprocessedTransaction,
+    SubscribePreprocessedTransactionInfo, SubscribePreprocessedUpdate, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdateTransactionStatus,
+};
+use helius_laserstream::lifecycle::{subscribe_lifecycle, LifecycleEvent, LifecycleTracker, Stage};
+use helius_laserstream::testing::MockGeyser;
+use std::time::{Duration, Instant};
+
+const SIG: [u8; 64] = [7; 64];
+
+fn status(signature: &[u8], slot: u64) -> SubscribeUpdate {
+    SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::TransactionStatus(
+            SubscribeUpdateTransactionStatus {
+                slot,
+                signature: signature.to_vec(),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+fn preprocessed(signature: &[u8]) -> SubscribePreprocessedUpdate {
+    SubscribePreprocessedUpdate {
+        update_oneof: Some(subscribe_preprocessed_update::UpdateOneof::Transaction(
+            SubscribePreprocessedTransaction {
+                transaction: Some(SubscribePreprocessedTransactionInfo {
+                    signature: signature.to_vec(),
+                    ..Default::default()
+                }),
+                slot: 0,
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+fn ms(start: Instant, ms: u64) -> Instant {
+    start + Duration::from_millis(ms)
+}
+
+#[test]
+fn follows_a_watched_signature_to_finalized() {
+    let start = Instant::now();
+    let mut tracker = LifecycleTracker::new().watched_only();
+    tracker.watch_at(&SIG, start);
+    // Not watched: ignored.
+    assert!(tracker
+        .preprocessed_at(&preprocessed(&[1; 64]), start)
+        .is_empty());
+
+    let stages = |events: Vec<LifecycleEvent>| -> Vec<Stage> {
+        events.into_iter().map(|e| e.stage).collect()
+    };
+    assert_eq!(
+        stages(tracker.preprocessed_at(&preprocessed(&SIG), ms(start, 50))),
+        vec![Stage::Preprocessed]
+    );
+    tracker.slot_at(&slot(10, 9, SlotStatus::SlotProcessed), ms(start, 390));
+    assert_eq!(
+        stages(tracker.apply_at(&status(&SIG, 10), ms(start, 400))),
+        vec![Stage::Processed]
+    );
+    assert_eq!(
+        stages(tracker.slot_at(&slot(10, 9, SlotStatus::SlotConfirmed), ms(start, 1_200))),
+        vec![Stage::Confirmed]
+    );
+    let finalized = tracker.slot_at(&slot(10, 9, SlotStatus::SlotFinalized), ms(start, 13_000));
+    assert_eq!(finalized.len(), 1);
+
+    let timeline = &finalized[0].timeline;
+    assert_eq!(timeline.slot, Some(10));
+    assert_eq!(timeline.succeeded, Some(true));
+    assert_eq!(
+        timeline.latency(Stage::Submitted, Stage::Preprocessed),
+        Some(Duration::from_millis(50))
+    );
+    assert_eq!(
+        timeline.latency(Stage::Processed, Stage::Confirmed),
+        Some(Duration::from_millis(800))
+    );
+    assert_eq!(
+        timeline.time_to_confirm(),
+        Some(Duration::from_millis(1_200))
+    );
+    assert!(tracker.is_empty());
+    assert_eq!(tracker.stats().landing_rate(), Some(1.0));
+    assert_eq!(
+        tracker.stats().mean_time_to_confirm(),
+        Some(Duration::from_millis(1_200))
+    );
+}
+
+#[test]
+fn unlanded_signatures_are_dropped() {
+    let start = Instant::now();
+    let mut tracker = LifecycleTracker::new().with_drop_timeout(Duration::from_secs(60));
+    tracker.preprocessed_at(&preprocessed(&SIG), start);
+    tracker.preprocessed_at(&preprocessed(&[8; 64]), start);
+    tracker.apply_at(&status(&[8; 64], 5), ms(start, 300));
+    tracker.slot_at(&slot(5, 4, SlotStatus::SlotFinalized), ms(start, 13_000));
+
+    assert!(tracker.expire_at(ms(start, 59_000)).is_empty());
+    let dropped = tracker.expire_at(ms(start, 60_000));
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].stage, Stage::Dropped);
+    assert_eq!(dropped[0].timeline.signature, SIG.to_vec());
+
+    let stats = tracker.stats();
+    assert_eq!((stats.landed, stats.dropped), (1, 1));
+    assert_eq!(stats.landing_rate(), Some(0.5));
+}
+
+#[test]
+fn relands_after_its_slot_is_orphaned() {
+    let start = Instant::now();
+    let mut tracker = LifecycleTracker::new();
+    tracker.slot_at(&slot(10, 9, SlotStatus::SlotProcessed), start);
+    tracker.apply_at(&status(&SIG, 10), ms(start, 10));
+
+    let orphaned = tracker.slot_at(&slot(10, 9, SlotStatus::SlotDead), ms(start, 20));
+    assert_eq!(orphaned[0].stage, Stage::Orphaned);
+    assert_eq!(orphaned[0].timeline.slot, None);
+
+    // Slot 11 is already confirmed when the transaction shows up in it.
+    tracker.slot_at(&slot(11, 9, SlotStatus::SlotConfirmed), ms(start, 30));
+    let landed = tracker.apply_at(&status(&SIG, 11), ms(start, 40));
+    let stages: Vec<Stage> = landed.iter().map(|e| e.stage).collect();
+    assert_eq!(stages, vec![Stage::Processed, Stage::Confirmed]);
+    assert_eq!(landed[1].timeline.slot, Some(11));
+    assert!(landed[1].timeline.orphaned.is_some());
+}
+
+/// Appends the stages of the next `count` events.
+async fn receive(
+    events: &mut tokio::sync::mpsc::UnboundedReceiver<LifecycleEvent>,
+    stages: &mut Vec<Stage>,
+    count: usize,
+) {
+    for _ in 0..count {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap_or_else(|_| panic!("no event within 10s after {stages:?}"))
+            .expect("stream ended");
+        stages.push(event.stage);
+    }
+}
+
+#[tokio::test]
+async fn watches_signatures_on_a_running_subscription() {
+    let mock = MockGeyser::start().await.unwrap();
+    let mut request = SubscribeRequest::default();
+    request.transactions_status.insert(
+        "txs".to_string(),
+        SubscribeRequestFilterTransactions::default(),
+    );
+    let (events, lifecycle) = subscribe_lifecycle(
+        mock.config(),
+        request,
+        SubscribePreprocessedRequest::default(),
+        LifecycleTracker::new().watched_only(),
+    );
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            if tx.send(event.unwrap()).is_err() {
+                return;
+            }
+        }
+    });
+    mock.wait_for_requests(1).await;
+    mock.wait_for_preprocessed_requests(1).await;
+
+    let mut stages = Vec::new();
+    for (n, signature) in [SIG, [8; 64]].into_iter().enumerate() {
+        lifecycle.watch(&signature).unwrap();
+        // The two subscriptions are separate connections; let the preprocessed
+        // update arrive before the transaction executes.
+        mock.push_preprocessed(preprocessed(&signature));
+        receive(&mut rx, &mut stages, 2).await;
+
+        let slot_number = 10 + n as u64;
+        let slot_status = |status| slot_update(slot_number, slot_number - 1, status);
+        mock.push(slot_status(SlotStatus::SlotProcessed));
+        mock.push(status(&signature, slot_number));
+        mock.push(slot_status(SlotStatus::SlotConfirmed));
+        mock.push(slot_status(SlotStatus::SlotFinalized));
+        receive(&mut rx, &mut stages, 3).await;
+    }
+    let expected = [
+        Stage::Submitted,
+        Stage::Preprocessed,
+        Stage::Processed,
+        Stage::Confirmed,
+        Stage::Finalized,
+    ];
+    assert_eq!(stages, [expected, expected].concat());
+    assert_eq!(lifecycle.stats().landed, 2);
+    assert_eq!(lifecycle.stats().landing_rate(), Some(1.0));
+}
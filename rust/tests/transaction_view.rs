@@ -0,0 +1,140 @@
+//! TransactionView: key ordering with lookup tables, balance deltas and errors.
+
+use helius_laserstream::grpc::{
+    subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateBlock,
+    SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use helius_laserstream::solana::storage::confirmed_block::{
+    Message, MessageHeader, TokenBalance, Transaction, TransactionError, TransactionStatusMeta,
+    UiTokenAmount,
+};
+use helius_laserstream::TransactionView;
+
+fn key(n: u8) -> Vec<u8> {
+    vec![n; 32]
+}
+
+fn token(index: u32, owner: &str, mint: &str, amount: &str) -> TokenBalance {
+    TokenBalance {
+        account_index: index,
+        mint: mint.to_string(),
+        owner: owner.to_string(),
+        program_id: "token".to_string(),
+        ui_token_amount: Some(UiTokenAmount {
+            amount: amount.to_string(),
+            decimals: 6,
+            ..Default::default()
+        }),
+    }
+}
+
+fn info() -> SubscribeUpdateTransactionInfo {
+    SubscribeUpdateTransactionInfo {
+        signature: vec![7; 64],
+        index: 3,
+        transaction: Some(Transaction {
+            signatures: vec![vec![7; 64]],
+            message: Some(Message {
+                header: Some(MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 1,
+                }),
+                account_keys: vec![key(0), key(1), key(2), key(3)],
+                versioned: true,
+                ..Default::default()
+            }),
+        }),
+        meta: Some(TransactionStatusMeta {
+            fee: 5_000,
+            pre_balances: vec![1_000_000, 50, 10, 1, 0, 9],
+            post_balances: vec![994_000, 50, 1_010, 1, 0, 9],
+            loaded_writable_addresses: vec![key(4)],
+            loaded_readonly_addresses: vec![key(5)],
+            pre_token_balances: vec![
+                token(2, "alice", "usdc", "1000"),
+                token(3, "alice", "usdc", "500"),
+                token(4, "bob", "usdc", "0"),
+            ],
+            post_token_balances: vec![
+                token(2, "alice", "usdc", "800"),
+                token(4, "bob", "usdc", "200"),
+                token(3, "alice", "usdc", "500"),
+            ],
+            compute_units_consumed: Some(12_345),
+            log_messages: vec!["Program log: hi".to_string()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn keys_balances_and_metadata() {
+    let info = info();
+    let view = TransactionView::new(42, &info).unwrap();
+
+    let keys: Vec<u8> = view.account_keys().iter().map(|k| k[0]).collect();
+    assert_eq!(keys, vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(view.fee_payer(), Some(key(0).as_slice()));
+    let writable: Vec<bool> = (0..6).map(|i| view.is_writable(i)).collect();
+    assert_eq!(writable, vec![true, false, true, false, true, false]);
+    assert!(view.is_signer(1) && !view.is_signer(2));
+
+    let sol: Vec<(usize, i64)> = view.balance_changes().iter().map(|c| (c.index, c.delta)).collect();
+    assert_eq!(sol, vec![(0, -6_000), (2, 1_000)]);
+
+    let tokens = view.token_balance_changes();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!((tokens[0].owner.as_str(), tokens[0].delta), ("alice", -200));
+    assert_eq!((tokens[1].owner.as_str(), tokens[1].delta), ("bob", 200));
+    assert_eq!(tokens[0].decimals, 6);
+
+    assert_eq!(view.compute_units_consumed(), Some(12_345));
+    assert_eq!(view.fee(), 5_000);
+    assert_eq!(view.logs().unwrap().len(), 1);
+    assert!(view.is_success());
+}
+
+#[test]
+fn decodes_custom_instruction_error() {
+    let mut info = info();
+    // InstructionError(1, Custom(6001))
+    let mut err = vec![8, 0, 0, 0, 1, 25, 0, 0, 0];
+    err.extend_from_slice(&6001u32.to_le_bytes());
+    info.meta.as_mut().unwrap().err = Some(TransactionError { err });
+
+    let view = TransactionView::new(42, &info).unwrap();
+    let failure = view.error().unwrap();
+    assert_eq!(failure.code(), Some(8));
+    assert_eq!(failure.instruction_index(), Some(1));
+    assert_eq!(failure.custom_error(), Some(6001));
+}
+
+#[test]
+fn works_for_transaction_and_block_updates() {
+    let tx = SubscribeUpdate {
+        filters: vec![],
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(info()),
+            slot: 9,
+        })),
+        created_at: None,
+    };
+    let views = TransactionView::from_update(&tx);
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].slot(), 9);
+
+    let block = SubscribeUpdate {
+        filters: vec![],
+        update_oneof: Some(UpdateOneof::Block(SubscribeUpdateBlock {
+            slot: 10,
+            transactions: vec![info(), info()],
+            ..Default::default()
+        })),
+        created_at: None,
+    };
+    let views = TransactionView::from_update(&block);
+    assert_eq!(views.len(), 2);
+    assert!(views.iter().all(|v| v.slot() == 10 && v.index() == 3));
+}
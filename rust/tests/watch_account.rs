@@ -0,0 +1,160 @@
+//! AccountWatchlist: shared `accounts` filter, latest-version publishing and release
+//! of accounts without receivers; `LaserstreamClient::watch_account` on a mock server.
+
+mod common;
+
+use common::account_update;
+use helius_laserstream::accounts::AccountWatchlist;
+use helius_laserstream::grpc::{CommitmentLevel, SubscribeUpdate, SubscribeUpdateAccountInfo};
+use helius_laserstream::testing::MockGeyser;
+use helius_laserstream::LaserstreamClient;
+use std::time::Duration;
+
+const A: [u8; 32] = [1; 32];
+const B: [u8; 32] = [2; 32];
+
+fn account(pubkey: &[u8], slot: u64, write_version: u64, lamports: u64) -> SubscribeUpdate {
+    account_update(
+        slot,
+        SubscribeUpdateAccountInfo {
+            pubkey: pubkey.to_vec(),
+            lamports,
+            write_version,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn shares_one_filter_across_watchers() {
+    let mut watchlist = AccountWatchlist::new(CommitmentLevel::Processed);
+    assert!(watchlist.request().accounts.is_empty());
+
+    let (_a1, added) = watchlist.watch(&A);
+    assert!(added);
+    let (_a2, added) = watchlist.watch(&A);
+    assert!(!added);
+    let (_b, added) = watchlist.watch(&B);
+    assert!(added);
+
+    let request = watchlist.request();
+    assert_eq!(request.commitment, Some(CommitmentLevel::Processed as i32));
+    assert_eq!(request.accounts.len(), 1);
+    let filter = request.accounts.values().next().unwrap();
+    assert_eq!(
+        filter.account,
+        vec![bs58::encode(A).into_string(), bs58::encode(B).into_string()]
+    );
+}
+
+#[test]
+fn publishes_the_latest_version_only() {
+    let mut watchlist = AccountWatchlist::default();
+    let (mut receiver, _) = watchlist.watch(&A);
+    assert!(receiver.borrow().is_none());
+
+    watchlist.apply(&account(&A, 10, 5, 100));
+    assert!(receiver.has_changed().unwrap());
+    assert_eq!(receiver.borrow_and_update().as_ref().unwrap().lamports, 100);
+
+    // Replayed and unwatched updates don't reach the receiver.
+    watchlist.apply(&account(&A, 10, 4, 90));
+    watchlist.apply(&account(&B, 11, 1, 1));
+    assert!(!receiver.has_changed().unwrap());
+
+    watchlist.apply(&account(&A, 11, 1, 120));
+    assert_eq!(receiver.borrow_and_update().as_ref().unwrap().lamports, 120);
+}
+
+#[test]
+fn releases_accounts_once_the_last_receiver_drops() {
+    let mut watchlist = AccountWatchlist::default();
+    let (first, _) = watchlist.watch(&A);
+    let (second, _) = watchlist.watch(&A);
+
+    drop(first);
+    assert!(!watchlist.release(&A));
+    drop(second);
+    assert!(watchlist.release(&A));
+    assert!(watchlist.is_empty());
+    assert!(watchlist.request().accounts.is_empty());
+    assert!(!watchlist.release(&A));
+}
+
+#[test]
+fn splits_the_list_at_the_per_filter_limit() {
+    let mut watchlist = AccountWatchlist::default().with_max_accounts_per_filter(2);
+    let receivers: Vec<_> = (1..=5u8)
+        .map(|byte| watchlist.watch(&[byte; 32]).0)
+        .collect();
+
+    let request = watchlist.request();
+    assert_eq!(request.accounts.len(), 3);
+    assert!(request.accounts.values().all(|f| f.account.len() <= 2));
+    let mut watched: Vec<_> = request
+        .accounts
+        .values()
+        .flat_map(|f| f.account.clone())
+        .collect();
+    watched.sort();
+    let mut expected: Vec<_> = (1..=5u8)
+        .map(|byte| bs58::encode([byte; 32]).into_string())
+        .collect();
+    expected.sort();
+    assert_eq!(watched, expected);
+    drop(receivers);
+}
+
+/// Waits for the next request whose watched-account list satisfies `check`.
+async fn wait_for_accounts(mock: &MockGeyser, seen: &mut usize, check: impl Fn(usize) -> bool) {
+    loop {
+        *seen += 1;
+        tokio::time::timeout(Duration::from_secs(10), mock.wait_for_requests(*seen))
+            .await
+            .expect("no request within 10s");
+        let request = &mock.requests()[*seen - 1];
+        let watched = request.accounts.values().map(|f| f.account.len()).sum();
+        if check(watched) {
+            return;
+        }
+    }
+}
+
+#[tokio::test]
+async fn watches_through_the_shared_subscription() {
+    let mock = MockGeyser::start().await.unwrap();
+    let client = LaserstreamClient::new(mock.config());
+    let mut receiver = client.watch_account(&A);
+    let mut seen = 0;
+    wait_for_accounts(&mock, &mut seen, |watched| watched == 1).await;
+
+    mock.push(account(&A, 10, 1, 100));
+    // Not watched: filtered out by the server.
+    mock.push(account(&B, 10, 2, 200));
+    tokio::time::timeout(Duration::from_secs(10), receiver.changed())
+        .await
+        .expect("no update within 10s")
+        .unwrap();
+    let info = receiver.borrow_and_update().clone().unwrap();
+    assert_eq!((info.pubkey, info.lamports), (A.to_vec(), 100));
+
+    drop(receiver);
+    wait_for_accounts(&mock, &mut seen, |watched| watched == 0).await;
+}
+
+#[tokio::test]
+async fn coalesces_watch_changes_into_few_writes() {
+    let mock = MockGeyser::start().await.unwrap();
+    let client = LaserstreamClient::new(mock.config());
+    let receivers: Vec<_> = (0..200u8)
+        .map(|byte| client.watch_account(&[byte; 32]))
+        .collect();
+    let mut seen = 0;
+    wait_for_accounts(&mock, &mut seen, |watched| watched == 200).await;
+    // The initial subscription, then one write for the whole batch.
+    assert!(seen <= 3, "{seen} requests for one batch of watches");
+
+    drop(receivers);
+    wait_for_accounts(&mock, &mut seen, |watched| watched == 0).await;
+    assert!(seen <= 5, "{seen} requests after releasing the batch");
+}